@@ -0,0 +1,149 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    DeriveInput, Ident, ImplItem, ItemImpl, Token, Type, Variant,
+};
+
+use crate::ports::{self, Ports};
+
+type Result<T> = core::result::Result<T, syn::Error>;
+
+type PortDefs = Punctuated<Variant, Token![,]>;
+
+/// `inputs(Left, #[optional] Right), outputs(Sum)`
+pub struct ComponentArgs {
+    inputs: Option<PortDefs>,
+    outputs: Option<PortDefs>,
+}
+
+impl Parse for ComponentArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = Self {
+            inputs: None,
+            outputs: None,
+        };
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            let slot = match ident.to_string().as_str() {
+                "inputs" => &mut args.inputs,
+                "outputs" => &mut args.outputs,
+                _ => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "Expected 'inputs' or 'outputs'",
+                    ))
+                }
+            };
+            if slot.is_some() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("Duplicated '{ident}'"),
+                ));
+            }
+
+            let content;
+            parenthesized!(content in input);
+            *slot = Some(content.parse_terminated(Variant::parse)?);
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Generate the enum `{name}{suffix}` with a variant for each port and your
+/// `Inputs`/`Outputs` impl, or `()` when there is no port
+fn port_enum(
+    name: &Ident,
+    ports: Option<PortDefs>,
+    port_trait: Ports,
+) -> Result<(TokenStream, TokenStream)> {
+    let ports = match ports {
+        Some(ports) if !ports.is_empty() => ports,
+        _ => return Ok((quote! { () }, quote! {})),
+    };
+
+    let (ty, doc) = match port_trait {
+        Ports::Inputs => (
+            format_ident!("{name}Inputs"),
+            format!("Input ports of [{name}]"),
+        ),
+        Ports::Outputs => (
+            format_ident!("{name}Outputs"),
+            format!("Output ports of [{name}]"),
+        ),
+    };
+
+    let input: DeriveInput = parse_quote! { enum #ty { #ports } };
+    let impl_ports = ports::try_expand(input, port_trait)?;
+
+    // the attributes of ports are only known by the derive, keep only the docs
+    let variants = ports.into_iter().map(|mut variant| {
+        variant.attrs.retain(|attr| attr.path.is_ident("doc"));
+        variant
+    });
+
+    let definition = quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum #ty {
+            #(#variants),*
+        }
+
+        #impl_ports
+    };
+
+    Ok((quote! { #ty }, definition))
+}
+
+fn try_expand(args: ComponentArgs, mut item: ItemImpl) -> Result<TokenStream> {
+    let name = match &*item.self_ty {
+        Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last().unwrap().ident.clone(),
+        ty => {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "Attribute 'component' only supported in a impl for a named type",
+            ))
+        }
+    };
+
+    for impl_item in item.items.iter() {
+        if let ImplItem::Type(ty) = impl_item {
+            if ty.ident == "Inputs" || ty.ident == "Outputs" {
+                return Err(syn::Error::new(
+                    ty.ident.span(),
+                    format!(
+                        "'type {}' is generated by the attribute 'component'",
+                        ty.ident
+                    ),
+                ));
+            }
+        }
+    }
+
+    let (inputs_ty, inputs) = port_enum(&name, args.inputs, Ports::Inputs)?;
+    let (outputs_ty, outputs) = port_enum(&name, args.outputs, Ports::Outputs)?;
+
+    item.items.push(parse_quote! { type Inputs = #inputs_ty; });
+    item.items
+        .push(parse_quote! { type Outputs = #outputs_ty; });
+
+    Ok(quote! {
+        #inputs
+        #outputs
+        #item
+    })
+}
+
+pub(crate) fn expand_component(args: ComponentArgs, item: ItemImpl) -> TokenStream {
+    match try_expand(args, item) {
+        Ok(expand) => expand,
+        Err(error) => error.to_compile_error(),
+    }
+}
@@ -0,0 +1,128 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Expr, Ident, LitInt, Path, Token,
+};
+
+type Result<T> = core::result::Result<T, syn::Error>;
+
+/// `1 => Schema`
+struct ComponentDef {
+    id: LitInt,
+    schema: Expr,
+}
+
+impl Parse for ComponentDef {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let id = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let schema = input.parse()?;
+        Ok(Self { id, schema })
+    }
+}
+
+/// `1.Out -> 2.In`
+struct ConnectionDef {
+    from: LitInt,
+    out_port: Path,
+    to: LitInt,
+    in_port: Path,
+}
+
+impl Parse for ConnectionDef {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let from = input.parse()?;
+        input.parse::<Token![.]>()?;
+        let out_port = input.parse()?;
+        input.parse::<Token![->]>()?;
+        let to = input.parse()?;
+        input.parse::<Token![.]>()?;
+        let in_port = input.parse()?;
+        Ok(Self {
+            from,
+            out_port,
+            to,
+            in_port,
+        })
+    }
+}
+
+pub struct FlowDef {
+    components: Punctuated<ComponentDef, Token![,]>,
+    connections: Punctuated<ConnectionDef, Token![,]>,
+}
+
+/// Parse `name: { items }`, checking the name of section
+fn section<T: Parse>(input: ParseStream, name: &str) -> Result<Punctuated<T, Token![,]>> {
+    let ident: Ident = input.parse()?;
+    if ident != name {
+        return Err(syn::Error::new(
+            ident.span(),
+            format!("Expected section '{name}'"),
+        ));
+    }
+    input.parse::<Token![:]>()?;
+
+    let content;
+    braced!(content in input);
+    content.parse_terminated(T::parse)
+}
+
+impl Parse for FlowDef {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let components = section(input, "components")?;
+
+        let connections = if input.is_empty() {
+            Punctuated::new()
+        } else {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                Punctuated::new()
+            } else {
+                section(input, "connections")?
+            }
+        };
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(Self {
+            components,
+            connections,
+        })
+    }
+}
+
+pub(crate) fn expand_flow(flow: FlowDef) -> TokenStream {
+    let components = flow.components.iter().map(|ComponentDef { id, schema }| {
+        quote! {
+            .and_then(|flow| flow.add_component(::rs_flow::component::Component::new(#id, #schema)))
+        }
+    });
+    let connections = flow.connections.iter().map(
+        |ConnectionDef {
+             from,
+             out_port,
+             to,
+             in_port,
+         }| {
+            quote! {
+                .and_then(|flow| flow.add_connection(::rs_flow::connection::Connection::new(
+                    #from,
+                    ::rs_flow::ports::Outputs::into_port(&#out_port),
+                    #to,
+                    ::rs_flow::ports::Inputs::into_port(&#in_port),
+                )))
+            }
+        },
+    );
+
+    quote! {
+        ::core::result::Result::<_, ::rs_flow::Error>::Ok(::rs_flow::Flow::new())
+            #(#components)*
+            #(#connections)*
+    }
+}
@@ -1,18 +1,76 @@
-use proc_macro;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, DeriveInput, ItemImpl};
 
+mod component;
+mod flow;
 mod ports;
 
-#[proc_macro_derive(Inputs, attributes(description))]
+#[proc_macro_derive(Inputs, attributes(description, optional, schema, kind, port))]
 pub fn derive_inputs(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     ports::derive_ports(input, ports::Ports::Inputs).into()
 }
 
-#[proc_macro_derive(Outputs, attributes(description))]
+#[proc_macro_derive(Outputs, attributes(description, optional, schema, kind, port))]
 pub fn derive_outputs(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     ports::derive_ports(input, ports::Ports::Outputs).into()
 }
+
+/// Generate the `Inputs` and `Outputs` of a component from a list of ports, declared in
+/// the impl of `ComponentSchema` (or `SyncComponentSchema`), that must not declare
+/// `type Inputs` and `type Outputs`.
+///
+/// The ports are the variants of the enums `{Component}Inputs` and `{Component}Outputs`,
+/// accepting the same attributes of `#[derive(Inputs, Outputs)]`. Without ports the type is `()`.
+/// Must be placed before `#[async_trait]`.
+///
+/// ```ignore
+/// #[component(inputs(Left, #[optional] Right), outputs(#[description("Left + Right")] Sum))]
+/// #[async_trait]
+/// impl ComponentSchema for Add {
+///     type Global = ();
+///
+///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+///         let left = ctx.receive(AddInputs::Left).unwrap().get_number()?;
+///         let right = ctx.receive(AddInputs::Right).map_or(Ok(0.0), |p| p.get_number())?;
+///         ctx.send(AddOutputs::Sum, (left + right).into());
+///         Ok(Next::Continue)
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn component(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(args as component::ComponentArgs);
+    let item = parse_macro_input!(item as ItemImpl);
+
+    component::expand_component(args, item).into()
+}
+
+/// Create a [Flow](https://docs.rs/rs-flow/latest/rs_flow/struct.Flow.html) from components and
+/// connections, returning a `Result<Flow<G>, Error>`.
+///
+/// The ports of a connection are the types that implement `Outputs` and `Inputs`, so a unknown
+/// port is a compile error.
+///
+/// ```ignore
+/// let flow = flow! {
+///     components: {
+///         1 => Message { text: "hello".to_owned() },
+///         2 => Log,
+///     },
+///     connections: {
+///         1.Out -> 2.In,
+///     }
+/// }?;
+/// ```
+#[proc_macro]
+pub fn flow(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as flow::FlowDef);
+
+    flow::expand_flow(input).into()
+}
@@ -1,6 +1,8 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DataStruct, DeriveInput, Fields, LitStr};
+use syn::{
+    Attribute, Data, DataStruct, DeriveInput, Expr, Fields, Ident, Lit, LitStr, MetaNameValue,
+};
 
 type Result<T> = core::result::Result<T, syn::Error>;
 
@@ -9,6 +11,74 @@ pub enum Ports {
     Outputs,
 }
 
+/// Expand the attribute `#[schema(...)]`
+fn schema(attrs: &[Attribute]) -> Result<TokenStream> {
+    match attrs.iter().find(|attr| attr.path.is_ident("schema")) {
+        Some(attr) => {
+            let schema: Expr = attr.parse_args()?;
+            Ok(quote! { .schema(#schema) })
+        }
+        None => Ok(quote! {}),
+    }
+}
+
+/// Expand the attribute `#[kind(...)]`, with a variant of `PackageKind`
+fn kind(attrs: &[Attribute]) -> Result<TokenStream> {
+    match attrs.iter().find(|attr| attr.path.is_ident("kind")) {
+        Some(attr) => {
+            let kind: Ident = attr.parse_args()?;
+            Ok(quote! { .kind(::rs_flow::PackageKind::#kind) })
+        }
+        None => Ok(quote! {}),
+    }
+}
+
+/// Parse the attribute `#[port(id = 5)]`, returning the explicit id of the port
+fn port_id(attrs: &[Attribute]) -> Result<Option<u16>> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident("port")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    let meta: MetaNameValue = attr.parse_args()?;
+    if !meta.path.is_ident("id") {
+        return Err(syn::Error::new_spanned(
+            meta.path,
+            "Expected 'id = <port id>'",
+        ));
+    }
+    match meta.lit {
+        Lit::Int(id) => Ok(Some(id.base10_parse()?)),
+        lit => Err(syn::Error::new_spanned(lit, "Expected a integer port id")),
+    }
+}
+
+/// Expand the attribute `#[optional]`
+fn optional(attrs: &[Attribute]) -> TokenStream {
+    if attrs.iter().any(|attr| attr.path.is_ident("optional")) {
+        quote! { .optional() }
+    } else {
+        quote! {}
+    }
+}
+
+/// Report a port with the same id or label of a port already declared, in compile time
+/// instead of a panic when `Ports::new` is evaluated
+fn check_duplicate(declared: &[(u16, String)], id: u16, label: &str, ident: &Ident) -> Result<()> {
+    if declared.iter().any(|(other, _)| *other == id) {
+        return Err(syn::Error::new(
+            ident.span(),
+            format!("Found ports with same id = {id}"),
+        ));
+    }
+    if declared.iter().any(|(_, other)| other == label) {
+        return Err(syn::Error::new(
+            ident.span(),
+            format!("Found ports with same label = {label:?}"),
+        ));
+    }
+    Ok(())
+}
+
 fn impl_unit_struct(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
     let ty = &input.ident;
     let trait_name = match port_trait {
@@ -32,19 +102,24 @@ fn impl_unit_struct(input: DeriveInput, port_trait: Ports) -> Result<TokenStream
         quote! { None }
     };
 
+    let id = port_id(&input.attrs)?.unwrap_or(0);
+    let optional = optional(&input.attrs);
+    let schema = schema(&input.attrs)?;
+    let kind = kind(&input.attrs)?;
+
     let token = quote! {
         impl #impl_generics #trait_name for #ty #ty_generics #where_clause {
             const PORTS: ::rs_flow::ports::Ports = ::rs_flow::ports::Ports::new(&[
-                ::rs_flow::ports::Port::from(0, #label, #description)
+                ::rs_flow::ports::Port::from(#id, #label, #description)#optional #schema #kind
             ]);
 
             fn into_port(&self) -> ::rs_flow::ports::PortId {
-                0
+                #id
             }
         }
     };
 
-    Ok(token.into())
+    Ok(token)
 }
 
 fn impl_enum(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
@@ -64,13 +139,34 @@ fn impl_enum(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
 
     let mut ports = Vec::<TokenStream>::with_capacity(data.variants.len());
     let mut intos = Vec::<TokenStream>::with_capacity(data.variants.len());
+    let mut declared = Vec::<(u16, String)>::with_capacity(data.variants.len());
+
+    // the variants without a explicit id fill the ids not used by the explicit ones
+    let mut explicit = Vec::with_capacity(data.variants.len());
+    for variant in data.variants.iter() {
+        explicit.extend(port_id(&variant.attrs)?);
+    }
+    let mut next = 0u16;
 
-    for (index, variant) in data.variants.into_iter().enumerate() {
+    for variant in data.variants.into_iter() {
         if let Fields::Unit = variant.fields {
             let ident = variant.ident;
 
-            let id = index as u16;
+            let id = match port_id(&variant.attrs)? {
+                Some(id) => id,
+                None => {
+                    while explicit.contains(&next) || declared.iter().any(|(id, _)| *id == next) {
+                        next += 1;
+                    }
+                    next
+                }
+            };
             let label = ident.to_string();
+            check_duplicate(&declared, id, &label, &ident)?;
+            declared.push((id, label.clone()));
+            let optional = optional(&variant.attrs);
+            let schema = schema(&variant.attrs)?;
+            let kind = kind(&variant.attrs)?;
             let description = variant
                 .attrs
                 .into_iter()
@@ -83,7 +179,8 @@ fn impl_enum(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
                 quote! { None }
             };
 
-            ports.push(quote! { ::rs_flow::ports::Port::from(#id, #label, #description), });
+            ports
+                .push(quote! { ::rs_flow::ports::Port::from(#id, #label, #description)#optional #schema #kind, });
             intos.push(quote! { Self::#ident => #id, })
         } else {
             return Err(syn::Error::new(
@@ -102,7 +199,8 @@ fn impl_enum(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
             Ports::Outputs => quote! { panic!("Component not have a output port"); },
         },
         1 => {
-            quote! { 0 }
+            let id = declared[0].0;
+            quote! { #id }
         }
         _ => {
             quote! {
@@ -128,17 +226,17 @@ fn impl_enum(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
     Ok(token)
 }
 
-fn try_expand(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
+pub(crate) fn try_expand(input: DeriveInput, port_trait: Ports) -> Result<TokenStream> {
     let err = match port_trait {
         Ports::Inputs => "Derive 'Inputs' only supported for enums or unit structs",
         Ports::Outputs => "Derive 'Outputs' only supported for enums or unit structs",
     };
     match &input.data {
         syn::Data::Enum(_) => impl_enum(input, port_trait),
-        syn::Data::Struct(DataStruct { fields, .. }) => match fields {
-            Fields::Unit => impl_unit_struct(input, port_trait),
-            _ => Err(syn::Error::new(input.ident.span(), err)),
-        },
+        syn::Data::Struct(DataStruct {
+            fields: Fields::Unit,
+            ..
+        }) => impl_unit_struct(input, port_trait),
         _ => Err(syn::Error::new(input.ident.span(), err)),
     }
 }
@@ -153,7 +251,7 @@ pub(crate) fn derive_ports(input: DeriveInput, port_trait: Ports) -> TokenStream
                 #error
             };
 
-            token.into()
+            token
         }
     }
 }
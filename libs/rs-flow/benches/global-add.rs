@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use rs_flow::prelude::*;
+
+const COMPONENTS: usize = 100;
+const INCREMENTS: u64 = 1_000;
+
+#[derive(Clone, Copy)]
+struct Locking;
+
+#[async_trait]
+impl ComponentSchema for Locking {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = u64;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for _ in 0..INCREMENTS {
+            ctx.with_mut_global(|counter| *counter += 1)?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Batched;
+
+#[async_trait]
+impl ComponentSchema for Batched {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = u64;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for _ in 0..INCREMENTS {
+            ctx.global_add(1u64);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+fn flow<T: ComponentSchema<Global = u64> + Copy>(component: T) -> Flow<u64> {
+    (0..COMPONENTS).fold(Flow::new(), |flow, id| {
+        flow.add_component(Component::new(id, component)).unwrap()
+    })
+}
+
+fn bench(name: &str, iterations: u32, flow: Flow<u64>) {
+    let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let counter = runtime.block_on(flow.run(0)).unwrap();
+        assert_eq!(counter, COMPONENTS as u64 * INCREMENTS);
+    }
+    let elapsed: Duration = start.elapsed();
+    println!("{name:<10} {:>12?} / iter", elapsed / iterations);
+}
+
+fn main() {
+    const ITERATIONS: u32 = 20;
+
+    bench("locking", ITERATIONS, flow(Locking));
+    bench("batched", ITERATIONS, flow(Batched));
+}
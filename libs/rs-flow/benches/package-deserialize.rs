@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rs_flow::Package;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Owned {
+    name: String,
+    description: String,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Borrowed<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    description: &'a str,
+    #[serde(borrow)]
+    tags: Vec<Cow<'a, str>>,
+}
+
+fn big_object() -> Package {
+    let items = (0..1_000).map(|i| {
+        Package::object([
+            ("name", Package::string(&"item".repeat(64))),
+            ("description", Package::string(&format!("{i}").repeat(4096))),
+            (
+                "tags",
+                Package::array((0..16).map(|t| format!("tag-{t}-{}", "x".repeat(32)))),
+            ),
+        ])
+    });
+    Package::array(items)
+}
+
+fn bench(name: &str, iterations: u32, mut run: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        run();
+    }
+    let elapsed: Duration = start.elapsed();
+    println!("{name:<10} {:>12?} / iter", elapsed / iterations);
+}
+
+fn main() {
+    const ITERATIONS: u32 = 20;
+    let package = big_object();
+
+    bench("owned", ITERATIONS, || {
+        // the owned path consume the package, so it need a copy for each run
+        let items: Vec<Owned> = package.clone().try_into().unwrap();
+        black_box(items);
+    });
+
+    bench("borrowed", ITERATIONS, || {
+        let items: Vec<Borrowed> = package.deserialize_borrowed().unwrap();
+        black_box(items);
+    });
+}
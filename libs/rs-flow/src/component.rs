@@ -1,14 +1,23 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
 use crate::connection::Point;
 use crate::context::Ctx;
 use crate::error::RunResult as Result;
+use crate::package::Package;
 use crate::ports::{Inputs, Outputs, PortId, Ports};
 
 /// Define if next cicle of [Flow](crate::flow::Flow) will be executed
 ///
 /// - If any component return <code> Ok([Next::Break]) </code> flow run will be interrupted and return Ok(Global)
 /// - If all component return <code> Ok([Next::Continue]) </code> flow continue to run for a more cicle
+/// - If a component return <code> Ok([Next::Retry]) </code> it run again in the next cicle, even without
+///   new packages, and it is not required to receive a package, the packages not received stay in the queues.
+///   Usefull for a component that wait a external condition, like a polling
 /// - If any component return <code> Err(_) </code>, flow will be interrupted and return that Error
 ///
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
@@ -16,29 +25,86 @@ pub enum Next {
     #[default]
     Continue,
     Break,
+    Retry,
 }
 
 ///
 /// Define when a [Component] is prepared to run.
 ///
 /// - [`Lazy`](Type::Lazy) :
-///     Wait for at least one [Package](crate::package::Package) received at each input port [Inputs].
+///   Wait for at least one [Package](crate::package::Package) received at each input port [Inputs].
 ///
 /// - [`Eager`](Type::Eager):
 ///     - Wait for at least one [Package](crate::package::Package) received at each input port [Inputs].
 ///     - Wait for all ancestral components to run, it's means that if any
-/// ancestral of this [Component] is prepared to run, this [Component] will not run.
+///       ancestral of this [Component] is prepared to run, this [Component] will not run.
 ///
 /// Obs: If a [Component] does not have an [Inputs] port's, it will be selected
 ///      as the flow's entry point, and will be executed once in the first cicle.
 ///
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum Type {
     #[default]
     Lazy,
     Eager,
 }
 
+///
+/// Function that give the priority of a [Package], see [QueuePolicy::Priority]
+///
+pub type PriorityKey = Arc<dyn Fn(&Package) -> i64 + Send + Sync>;
+
+///
+/// Define the order that the [Package]'s received by a input port are taken
+/// by [Ctx::receive](crate::context::Ctx::receive).
+///
+/// - [`Fifo`](QueuePolicy::Fifo): The first package received is the first taken.
+/// - [`Lifo`](QueuePolicy::Lifo): The last package received is the first taken.
+/// - [`Priority`](QueuePolicy::Priority): The package with the highest key is the first taken,
+///   packages with the same key are taken in the order received.
+///
+#[derive(Clone, Default)]
+pub enum QueuePolicy {
+    #[default]
+    Fifo,
+    Lifo,
+    Priority(PriorityKey),
+}
+
+impl QueuePolicy {
+    /// Create a [QueuePolicy::Priority] with the key function
+    pub fn priority(key: impl Fn(&Package) -> i64 + Send + Sync + 'static) -> Self {
+        QueuePolicy::Priority(Arc::new(key))
+    }
+}
+
+impl std::fmt::Debug for QueuePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueuePolicy::Fifo => f.write_str("Fifo"),
+            QueuePolicy::Lifo => f.write_str("Lifo"),
+            QueuePolicy::Priority(_) => f.write_str("Priority"),
+        }
+    }
+}
+
+///
+/// Define what happen when packages arrive in a input port that is full, see [Component::input_capacity].
+///
+/// - [`Block`](Overflow::Block): The packages wait until the queue have space, and the components
+///   that sent them are not ready to run while their packages wait (back-pressure).
+/// - [`Error`](Overflow::Error): The [Flow](crate::flow::Flow) return a
+///   [QueueFull](crate::error::Error::QueueFull) error.
+/// - [`DropOldest`](Overflow::DropOldest): The oldest packages in the queue are discarded.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    #[default]
+    Block,
+    Error,
+    DropOldest,
+}
+
 ///
 /// Id of a component
 ///
@@ -54,6 +120,11 @@ pub type Id = usize;
 /// A [Flow](crate::flow::Flow) hava a unique [Global](ComponentSchema::Global) type, what means that only component
 /// with the same Self::Global can be use for contruct the flow.
 ///
+/// The generic `G` of [Flow](crate::flow::Flow), [Component] and [Ctx] is always this Global type,
+/// not the type of packages. All components send and receive the same [Package](crate::package::Package)
+/// type, so connected components never disagree about the package type, and a [Component] with other
+/// Global is rejected in compile time by [Flow::add_component](crate::flow::Flow::add_component).
+///
 /// # Examples
 /// ```
 /// use rs_flow::prelude::*;
@@ -102,6 +173,84 @@ pub trait ComponentSchema: Send + Sync + 'static {
     fn description() -> &'static str {
         ""
     }
+
+    /// Name that identify the kind of this component, like in a [FlowSpec](crate::spec::FlowSpec),
+    /// by default is the full path of type
+    fn type_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Input [Ports] of this component, by default the [PORTS](Inputs::PORTS) of `Inputs`.
+    ///
+    /// Can be overridden to compute the ports at runtime, like a number of ports defined in
+    /// construction, see [Ports::owned]. The [Inputs] used to receive packages can be a
+    /// [PortId] of these ports.
+    fn inputs(&self) -> Ports {
+        Self::Inputs::PORTS
+    }
+
+    /// Output [Ports] of this component, by default the [PORTS](Outputs::PORTS) of `Outputs`,
+    /// see [ComponentSchema::inputs]
+    fn outputs(&self) -> Ports {
+        Self::Outputs::PORTS
+    }
+}
+
+///
+/// Define a [Component] that run without async, see [ComponentSchema].
+///
+/// A [Component] created with this trait can be used in a [Flow](crate::flow::Flow) and
+/// also in a [SyncFlow](crate::SyncFlow), that run without any async runtime.
+///
+/// ```
+/// use rs_flow::prelude::*;
+///
+/// #[derive(Outputs)]
+/// struct Out;
+///
+/// struct Numbers;
+///
+/// impl SyncComponentSchema for Numbers {
+///     type Inputs = ();
+///     type Outputs = Out;
+///
+///     type Global = ();
+///
+///     fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+///         ctx.send(Out, 1.into());
+///         Ok(Next::Continue)
+///     }
+/// }
+///
+/// let component = Component::sync(1, Numbers);
+/// ```
+///
+pub trait SyncComponentSchema: Send + Sync + 'static {
+    type Global: Send + Sync;
+
+    type Inputs: Inputs;
+    type Outputs: Outputs;
+
+    fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next>;
+
+    fn description() -> &'static str {
+        ""
+    }
+
+    /// Name that identify the kind of this component, see [ComponentSchema::type_name]
+    fn type_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Input [Ports] of this component, see [ComponentSchema::inputs]
+    fn inputs(&self) -> Ports {
+        Self::Inputs::PORTS
+    }
+
+    /// Output [Ports] of this component, see [ComponentSchema::outputs]
+    fn outputs(&self) -> Ports {
+        Self::Outputs::PORTS
+    }
 }
 
 #[async_trait]
@@ -124,6 +273,48 @@ where
     }
 }
 
+pub(crate) trait SyncComponentRun: Send + Sync + 'static {
+    type Global: Send + Sync;
+
+    fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next>;
+}
+
+impl<T: Sized> SyncComponentRun for T
+where
+    T: SyncComponentSchema,
+{
+    type Global = T::Global;
+
+    #[inline(always)]
+    fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        self.run(ctx)
+    }
+}
+
+/// The `run` of a [Component], that can be async or not
+pub(crate) enum Runner<G> {
+    Async(Arc<dyn ComponentRun<Global = G>>),
+    Sync(Arc<dyn SyncComponentRun<Global = G>>),
+}
+
+impl<G> Clone for Runner<G> {
+    fn clone(&self) -> Self {
+        match self {
+            Runner::Async(data) => Runner::Async(data.clone()),
+            Runner::Sync(data) => Runner::Sync(data.clone()),
+        }
+    }
+}
+
+impl<G: Send + Sync + 'static> Runner<G> {
+    pub(crate) async fn run(&self, ctx: &mut Ctx<G>) -> Result<Next> {
+        match self {
+            Runner::Async(data) => data.run(ctx).await,
+            Runner::Sync(data) => data.run(ctx),
+        }
+    }
+}
+
 ///
 /// Storage the [Component] infos:
 /// - [Id] that identify a operator in a [Flow](crate::flow::Flow),
@@ -167,40 +358,239 @@ where
 /// ```
 pub struct Component<G> {
     pub(crate) id: Id,
-    pub(crate) data: Box<dyn ComponentRun<Global = G>>,
+    pub(crate) data: Runner<G>,
     pub(crate) ty: Type,
     pub(crate) inputs: Ports,
     pub(crate) outputs: Ports,
+    pub(crate) catch_panics: bool,
+    pub(crate) policies: HashMap<PortId, QueuePolicy>,
+    pub(crate) ttls: HashMap<PortId, u32>,
+    pub(crate) capacities: HashMap<PortId, (usize, Overflow)>,
+    pub(crate) cycle_budget: Option<usize>,
+    pub(crate) allow_no_consume: bool,
+    pub(crate) once: bool,
+    pub(crate) type_name: Cow<'static, str>,
+    pub(crate) description: &'static str,
+    pub(crate) config: Package,
+}
+
+/// A clone share the same instance of [ComponentSchema] with the original [Component],
+/// so a state inside of schema (like a [Mutex](std::sync::Mutex)) is also shared.
+impl<G> Clone for Component<G> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            data: self.data.clone(),
+            ty: self.ty,
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            catch_panics: self.catch_panics,
+            policies: self.policies.clone(),
+            ttls: self.ttls.clone(),
+            capacities: self.capacities.clone(),
+            cycle_budget: self.cycle_budget,
+            allow_no_consume: self.allow_no_consume,
+            once: self.once,
+            type_name: self.type_name.clone(),
+            description: self.description,
+            config: self.config.clone(),
+        }
+    }
 }
 
 impl<G> Component<G> {
-    /// Create a component with Type::Lazy
-    pub fn new<T>(id: Id, data: T) -> Self
+    fn create<T>(id: Id, data: T, ty: Type) -> Self
     where
         T: ComponentSchema<Global = G>,
     {
+        let (inputs, outputs) = (data.inputs(), data.outputs());
+        let data = Runner::Async(Arc::new(data));
+        Self::with_runner(id, data, ty, inputs, outputs)
+            .with_type_name(T::type_name())
+            .with_description(T::description())
+    }
+
+    fn create_sync<T>(id: Id, data: T, ty: Type) -> Self
+    where
+        T: SyncComponentSchema<Global = G>,
+    {
+        let (inputs, outputs) = (data.inputs(), data.outputs());
+        let data = Runner::Sync(Arc::new(data));
+        Self::with_runner(id, data, ty, inputs, outputs)
+            .with_type_name(T::type_name())
+            .with_description(T::description())
+    }
+
+    fn with_runner(id: Id, data: Runner<G>, ty: Type, inputs: Ports, outputs: Ports) -> Self {
         Self {
             id,
-            data: Box::new(data),
-            ty: Type::default(),
-            inputs: T::Inputs::PORTS,
-            outputs: T::Outputs::PORTS,
+            data,
+            ty,
+            inputs,
+            outputs,
+            catch_panics: false,
+            policies: HashMap::new(),
+            ttls: HashMap::new(),
+            capacities: HashMap::new(),
+            cycle_budget: None,
+            allow_no_consume: false,
+            once: false,
+            type_name: Cow::Borrowed(""),
+            description: "",
+            config: Package::Empty,
         }
     }
+
+    pub(crate) fn with_type_name(mut self, type_name: impl Into<Cow<'static, str>>) -> Self {
+        self.type_name = type_name.into();
+        self
+    }
+
+    fn with_description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Define the config used to create the data of this component, see
+    /// [ComponentRegistry](crate::spec::ComponentRegistry)
+    pub(crate) fn with_config(mut self, config: Package) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Return if the type of data of this [Component] is the `name`, that can be the full
+    /// path of type or only the name of type, without the module path and generics
+    pub(crate) fn is_type(&self, name: &str) -> bool {
+        let without_generics = self.type_name.split('<').next().unwrap_or_default();
+        self.type_name == name || without_generics.rsplit("::").next() == Some(name)
+    }
+
+    /// Create a component with Type::Lazy
+    pub fn new<T>(id: Id, data: T) -> Self
+    where
+        T: ComponentSchema<Global = G>,
+    {
+        Self::create(id, data, Type::default())
+    }
     /// Create a component with Type::Eager
     pub fn eager<T>(id: Id, data: T) -> Self
+    where
+        T: ComponentSchema<Global = G>,
+    {
+        Self::create(id, data, Type::Eager)
+    }
+    /// Create a component with Type::Lazy from a [SyncComponentSchema]
+    pub fn sync<T>(id: Id, data: T) -> Self
+    where
+        T: SyncComponentSchema<Global = G>,
+    {
+        Self::create_sync(id, data, Type::default())
+    }
+    /// Create a component with Type::Eager from a [SyncComponentSchema]
+    pub fn sync_eager<T>(id: Id, data: T) -> Self
+    where
+        T: SyncComponentSchema<Global = G>,
+    {
+        Self::create_sync(id, data, Type::Eager)
+    }
+    /// Create a component with Type::Lazy that have your [`run`](ComponentSchema::run) isolated,
+    /// if it panic the [Flow](crate::flow::Flow) return a
+    /// [`ComponentPanicked`](crate::error::Error::ComponentPanicked) error instead of panic.
+    ///
+    /// The `run` is wrapped in a [AssertUnwindSafe](std::panic::AssertUnwindSafe), so the
+    /// component data is not required to be [UnwindSafe](std::panic::UnwindSafe).
+    /// Any state that the component (or the global data) hold when panic can be left
+    /// inconsistent, the flow is interrupted and that state must not be trusted after.
+    /// A panic hook installed (the default prints the message) still run.
+    pub fn catch_panics<T>(id: Id, data: T) -> Self
     where
         T: ComponentSchema<Global = G>,
     {
         Self {
-            id,
-            data: Box::new(data),
-            ty: Type::Eager,
-            inputs: T::Inputs::PORTS,
-            outputs: T::Outputs::PORTS,
+            catch_panics: true,
+            ..Self::create(id, data, Type::default())
         }
     }
 
+    /// Create a component with Type::Lazy that run at most once, after the first run
+    /// it is never ready again, even if still receive packages in your input ports.
+    ///
+    /// The packages that remain or arrive in your queues after the run are ignored,
+    /// and not cause a [Deadlock](crate::error::Error::Deadlock).
+    pub fn once<T>(id: Id, data: T) -> Self
+    where
+        T: ComponentSchema<Global = G>,
+    {
+        Self {
+            once: true,
+            ..Self::create(id, data, Type::default())
+        }
+    }
+
+    /// Define the [QueuePolicy] of a input port, by default all ports are [QueuePolicy::Fifo]
+    ///
+    /// # Panics
+    /// Panic if could not found the input port
+    pub fn input_policy(mut self, port: PortId, policy: QueuePolicy) -> Self {
+        assert!(self.inputs.contains(port), "Input port {port} not found");
+        self.policies.insert(port, policy);
+        self
+    }
+
+    /// Define the max age, in cicles, of the packages received in a input port.
+    /// Packages that waited in the queue more than `cicles` after the cicle that
+    /// was sent are discarded when received.
+    ///
+    /// # Panics
+    /// Panic if could not found the input port
+    pub fn input_ttl(mut self, port: PortId, cicles: u32) -> Self {
+        assert!(self.inputs.contains(port), "Input port {port} not found");
+        self.ttls.insert(port, cicles);
+        self
+    }
+
+    /// Define the max number of [Package](crate::package::Package)'s waiting in a input port,
+    /// and what happen when the packages sent to port not fit, see [Overflow].
+    /// By default the input ports not have a limit.
+    ///
+    /// # Panics
+    /// Panic if could not found the input port, or if the `capacity` is 0
+    pub fn input_capacity(mut self, port: PortId, capacity: usize, overflow: Overflow) -> Self {
+        assert!(self.inputs.contains(port), "Input port {port} not found");
+        assert!(
+            capacity > 0,
+            "Capacity of input port {port} must be greater than 0"
+        );
+        self.capacities.insert(port, (capacity, overflow));
+        self
+    }
+
+    /// Define a soft limit of [Package](crate::package::Package)'s received by cicle,
+    /// once reached [`Ctx::should_yield`](crate::context::Ctx::should_yield) return true.
+    ///
+    /// The packages not received stay in the queues, so while all input ports have
+    /// packages the component is ready to run again in the next cicle.
+    pub fn cycle_budget(mut self, budget: usize) -> Self {
+        self.cycle_budget = Some(budget);
+        self
+    }
+
+    /// Allow this [Component] run without receive any package, by default a
+    /// [Flow](crate::flow::Flow) return a [AnyPackageConsumed](crate::error::Error::AnyPackageConsumed)
+    /// error if a component with inputs not receive a package when run.
+    ///
+    /// While all your input ports have packages the component is ready to run, so if
+    /// it never receive the packages it run again in each cicle, until return [Next::Break].
+    pub fn allow_no_consume(mut self) -> Self {
+        self.allow_no_consume = true;
+        self
+    }
+
+    /// Return if the [Component] was created from a [SyncComponentSchema]
+    pub fn is_sync(&self) -> bool {
+        matches!(self.data, Runner::Sync(_))
+    }
+
     /// Return id of component
     pub fn id(&self) -> Id {
         self.id
@@ -211,6 +601,18 @@ impl<G> Component<G> {
         self.ty
     }
 
+    /// Return the name that identify the kind of component, see [ComponentSchema::type_name].
+    /// A component created by a [ComponentRegistry](crate::spec::ComponentRegistry) has the
+    /// name that your type was registered.
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Return the description of component, see [ComponentSchema::description]
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
     /// Return a output point for connection
     ///
     /// # Panics
@@ -0,0 +1,126 @@
+use std::marker::PhantomData;
+
+use crate::prelude::*;
+
+#[derive(Inputs)]
+pub enum In {
+    #[description("Packages to be checked")]
+    Package,
+}
+
+#[derive(Outputs)]
+pub enum Out {
+    #[description("Packages that passed the assertion, unchanged")]
+    Package,
+}
+
+///
+/// Global data that store the packages that failed the assertion of a non-fatal [Assert],
+/// with the id of the [Assert] component.
+///
+pub trait AssertionFailures {
+    /// Return the failures already recorded
+    fn failures(&mut self) -> &mut Vec<(Id, Package)>;
+}
+
+type PredicateFn = Box<dyn Fn(&Package) -> bool + Send + Sync>;
+type FailuresFn<G> = fn(&mut G) -> &mut Vec<(Id, Package)>;
+
+///
+/// Check a predicate on each [Package] received, to embed invariants in a [Flow].
+/// The packages that pass are forwarded unchanged.
+///
+/// - When created by [Assert::new] a package that fail interrupt the flow with the error
+///   [AssertionFailed](crate::error::Error::AssertionFailed).
+/// - When created by [Assert::recording] a package that fail is recorded in the global
+///   data, see [AssertionFailures], not forwarded and the flow continue.
+///
+/// ```
+/// use rs_flow::components::assert::{Assert, AssertionFailures};
+/// use rs_flow::prelude::*;
+///
+/// #[derive(Default)]
+/// struct Global {
+///     failures: Vec<(Id, Package)>,
+/// }
+///
+/// impl AssertionFailures for Global {
+///     fn failures(&mut self) -> &mut Vec<(Id, Package)> {
+///         &mut self.failures
+///     }
+/// }
+///
+/// let positive = |package: &Package| matches!(package, Package::Number(n) if *n > 0.0);
+///
+/// let fatal: Component<Global> = Component::new(1, Assert::new(positive));
+/// let recording: Component<Global> = Component::new(2, Assert::recording(positive));
+/// ```
+///
+pub struct Assert<G> {
+    predicate: PredicateFn,
+    failures: Option<FailuresFn<G>>,
+    _global: PhantomData<fn() -> G>,
+}
+
+impl<G> Assert<G> {
+    /// Create a [Assert] that interrupt the flow when a package fail the `predicate`
+    pub fn new(predicate: impl Fn(&Package) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            predicate: Box::new(predicate),
+            failures: None,
+            _global: PhantomData,
+        }
+    }
+}
+
+impl<G> Assert<G>
+where
+    G: AssertionFailures,
+{
+    /// Create a [Assert] that record in the global data the packages that fail the `predicate`
+    pub fn recording(predicate: impl Fn(&Package) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            failures: Some(G::failures),
+            ..Self::new(predicate)
+        }
+    }
+}
+
+#[async_trait]
+impl<G> ComponentSchema for Assert<G>
+where
+    G: Send + Sync + 'static,
+{
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = G;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let mut failed = Vec::new();
+        while let Some(package) = ctx.receive(In::Package) {
+            if (self.predicate)(&package) {
+                ctx.send(Out::Package, package);
+                continue;
+            }
+            match self.failures {
+                Some(_) => failed.push((ctx.id(), package)),
+                None => {
+                    return Err(Box::new(Error::AssertionFailed {
+                        component: ctx.id(),
+                        package,
+                    }))
+                }
+            }
+        }
+
+        if let Some(failures) = self.failures.filter(|_| !failed.is_empty()) {
+            ctx.with_mut_global(|global| failures(global).extend(failed))?;
+        }
+        Ok(Next::Continue)
+    }
+
+    fn description() -> &'static str {
+        "Forward the packages that pass a predicate, the others fail the flow or are recorded"
+    }
+}
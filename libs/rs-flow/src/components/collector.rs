@@ -0,0 +1,82 @@
+use std::marker::PhantomData;
+
+use crate::prelude::*;
+
+#[derive(Inputs)]
+pub enum In {
+    #[description("Packages to be collected")]
+    Package,
+}
+
+///
+/// Global data that store the packages collected by the [Collector]'s with the marker `K`.
+///
+/// Each marker is a distinct storage, so many [Collector]'s can collect to the same global
+/// without clash.
+///
+pub trait Collected<K> {
+    /// Return the packages already collected with the marker `K`
+    fn collected(&mut self) -> &mut Vec<Package>;
+}
+
+///
+/// Append to the global data all [Package]'s received, it is a terminal [Component]
+/// without outputs that gather the results of a [Flow].
+///
+/// ```
+/// use rs_flow::components::collector::{Collected, Collector};
+/// use rs_flow::prelude::*;
+///
+/// struct Results;
+///
+/// #[derive(Default)]
+/// struct Global {
+///     results: Vec<Package>,
+/// }
+///
+/// impl Collected<Results> for Global {
+///     fn collected(&mut self) -> &mut Vec<Package> {
+///         &mut self.results
+///     }
+/// }
+///
+/// let collector: Component<Global> = Component::new(1, Collector::new(Results));
+/// ```
+///
+pub struct Collector<G, K> {
+    _marker: PhantomData<fn() -> (G, K)>,
+}
+
+impl<G, K> Collector<G, K>
+where
+    G: Collected<K>,
+{
+    /// Create a [Collector] that store the packages with the marker `K`
+    pub fn new(_marker: K) -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<G, K> ComponentSchema for Collector<G, K>
+where
+    G: Collected<K> + Send + Sync + 'static,
+    K: 'static,
+{
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = G;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let packages = std::iter::from_fn(|| ctx.receive(In::Package)).collect::<Vec<_>>();
+        ctx.with_mut_global(|global| global.collected().extend(packages))?;
+        Ok(Next::Continue)
+    }
+
+    fn description() -> &'static str {
+        "Append all packages received to the global data"
+    }
+}
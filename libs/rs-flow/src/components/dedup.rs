@@ -0,0 +1,117 @@
+use std::collections::{HashSet, VecDeque};
+use std::marker::PhantomData;
+
+use crate::prelude::*;
+
+#[derive(Inputs)]
+pub enum In {
+    #[description("Packages that may be repeated")]
+    Package,
+}
+
+#[derive(Outputs)]
+pub enum Out {
+    #[description("Packages received for the first time")]
+    Unique,
+}
+
+type KeyFn = Box<dyn Fn(&Package) -> String + Send + Sync>;
+
+#[derive(Default)]
+struct Seen {
+    keys: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+///
+/// Drop every [Package] already seen, the [Package]'s are compared by a key
+/// computed by the function given in construction. The seen keys are keep across cicles
+/// of a run, each [Flow::run] start without keys seen.
+///
+/// ```
+/// use rs_flow::components::dedup::Dedup;
+/// use rs_flow::prelude::*;
+///
+/// let dedup: Component<()> = Component::new(1, Dedup::new(|package| format!("{package:?}")));
+/// let bounded: Component<()> = Component::new(2, Dedup::with_capacity(|package| format!("{package:?}"), 100));
+/// ```
+///
+pub struct Dedup<G> {
+    key: KeyFn,
+    capacity: Option<usize>,
+    _global: PhantomData<fn() -> G>,
+}
+
+impl<G> Dedup<G> {
+    /// Create a [Dedup] that remember all keys seen
+    pub fn new(key: impl Fn(&Package) -> String + Send + Sync + 'static) -> Self {
+        Self {
+            key: Box::new(key),
+            capacity: None,
+            _global: PhantomData,
+        }
+    }
+
+    /// Create a [Dedup] that remember at most `capacity` keys,
+    /// when full the oldest key is forgotten
+    ///
+    /// # Panics
+    ///
+    /// Panic if `capacity` is 0, a [Dedup] that remember no keys never drop a [Package]
+    ///
+    pub fn with_capacity(
+        key: impl Fn(&Package) -> String + Send + Sync + 'static,
+        capacity: usize,
+    ) -> Self {
+        assert!(capacity > 0, "Dedup capacity must be greater than 0");
+        Self {
+            capacity: Some(capacity),
+            ..Self::new(key)
+        }
+    }
+}
+
+impl Seen {
+    fn first_seen(&mut self, key: String, capacity: Option<usize>) -> bool {
+        if self.keys.contains(&key) {
+            return false;
+        }
+
+        if let Some(capacity) = capacity {
+            while self.order.len() >= capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.keys.remove(&oldest);
+                }
+            }
+            self.order.push_back(key.clone());
+        }
+        self.keys.insert(key);
+
+        true
+    }
+}
+
+#[async_trait]
+impl<G> ComponentSchema for Dedup<G>
+where
+    G: Send + Sync + 'static,
+{
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = G;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In::Package) {
+            let key = (self.key)(&package);
+            if ctx.state_mut::<Seen>().first_seen(key, self.capacity) {
+                ctx.send(Out::Unique, package);
+            }
+        }
+        Ok(Next::Continue)
+    }
+
+    fn description() -> &'static str {
+        "Forward only packages not seen before"
+    }
+}
@@ -0,0 +1,94 @@
+use std::marker::PhantomData;
+
+use crate::package::PackageError;
+use crate::prelude::*;
+
+#[derive(Inputs)]
+pub enum In {
+    #[description("Packages to be transformed")]
+    Package,
+}
+
+#[derive(Outputs)]
+pub enum Out {
+    #[description("Packages transformed")]
+    Package,
+    #[optional]
+    #[description("Message of the error when transform a package fail, only used by `try_map`")]
+    Error,
+}
+
+type MapFn = Box<dyn Fn(Package) -> std::result::Result<Package, PackageError> + Send + Sync>;
+
+///
+/// Transform each [Package] received and send the result.
+///
+/// - When created by [MapComponent::new] a error returned by the function interrupt the flow.
+/// - When created by [MapComponent::try_map] a error returned by the function is sent
+///   as a string [Package] to the [Out::Error] port, and the flow continue.
+///
+/// ```
+/// use rs_flow::components::map::MapComponent;
+/// use rs_flow::prelude::*;
+///
+/// let double: MapComponent<()> = MapComponent::new(|package| {
+///     Ok(Package::number(package.get_number()? * 2.0))
+/// });
+/// ```
+///
+pub struct MapComponent<G> {
+    map: MapFn,
+    route_errors: bool,
+    _global: PhantomData<fn() -> G>,
+}
+
+impl<G> MapComponent<G> {
+    /// Create a [MapComponent] that interrupt the flow when the function fail
+    pub fn new(
+        map: impl Fn(Package) -> std::result::Result<Package, PackageError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            map: Box::new(map),
+            route_errors: false,
+            _global: PhantomData,
+        }
+    }
+
+    /// Create a [MapComponent] that send to [Out::Error] port the errors of the function
+    pub fn try_map(
+        map: impl Fn(Package) -> std::result::Result<Package, PackageError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            route_errors: true,
+            ..Self::new(map)
+        }
+    }
+}
+
+#[async_trait]
+impl<G> ComponentSchema for MapComponent<G>
+where
+    G: Send + Sync + 'static,
+{
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = G;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In::Package) {
+            match (self.map)(package) {
+                Ok(package) => ctx.send(Out::Package, package),
+                Err(error) if self.route_errors => {
+                    ctx.send(Out::Error, Package::string(&error.to_string()))
+                }
+                Err(error) => return Err(Box::new(error)),
+            }
+        }
+        Ok(Next::Continue)
+    }
+
+    fn description() -> &'static str {
+        "Transform each package received"
+    }
+}
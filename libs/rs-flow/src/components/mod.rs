@@ -0,0 +1,12 @@
+/// Component that check a predicate on each package received
+pub mod assert;
+/// Component that append to the global data all packages received
+pub mod collector;
+/// Component that forward only packages not seen before
+pub mod dedup;
+/// Component that transform each package received
+pub mod map;
+/// Component that forward packages to a output chosen by rules
+pub mod router;
+/// Component that run a inner flow as a fragment of pipeline
+pub mod sub_flow;
@@ -0,0 +1,115 @@
+use std::marker::PhantomData;
+
+use crate::prelude::*;
+
+#[derive(Inputs)]
+pub enum In {
+    #[description("Packages to be routed")]
+    Package,
+}
+
+struct Rule {
+    path: String,
+    expected: Package,
+    port: PortId,
+}
+
+///
+/// Forward each [Package] received to the first [Outputs] port that have a rule matched,
+/// a rule match when the package found in a path (see [Package::get_path]) is equal
+/// to the package expected. Packages that not match any rule are sent to the unmatched port.
+///
+/// The [Outputs] ports are defined by the type `O`.
+///
+/// ```
+/// use rs_flow::components::router::Router;
+/// use rs_flow::prelude::*;
+///
+/// #[derive(Outputs)]
+/// enum Out {
+///     Admin,
+///     Guest,
+///     Unmatched,
+/// }
+///
+/// let router: Router<(), Out> = Router::builder()
+///     .rule("user.role", "admin", Out::Admin)
+///     .rule("user.role", "guest", Out::Guest)
+///     .build(Out::Unmatched);
+/// ```
+///
+pub struct Router<G, O> {
+    rules: Vec<Rule>,
+    unmatched: PortId,
+    _marker: PhantomData<fn() -> (G, O)>,
+}
+
+/// Builder of the rules of a [Router]
+pub struct RouterBuilder<G, O> {
+    rules: Vec<Rule>,
+    _marker: PhantomData<fn() -> (G, O)>,
+}
+
+impl<G, O: Outputs> Router<G, O> {
+    /// Create a [RouterBuilder] without rules
+    pub fn builder() -> RouterBuilder<G, O> {
+        RouterBuilder {
+            rules: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn route(&self, package: &Package) -> PortId {
+        self.rules
+            .iter()
+            .find(|rule| package.get_path(&rule.path) == Some(&rule.expected))
+            .map(|rule| rule.port)
+            .unwrap_or(self.unmatched)
+    }
+}
+
+impl<G, O: Outputs> RouterBuilder<G, O> {
+    /// Add a rule that route to `output` the packages that have `expected` in `path`,
+    /// the rules are checked in order that was added
+    pub fn rule(mut self, path: &str, expected: impl Into<Package>, output: O) -> Self {
+        self.rules.push(Rule {
+            path: path.to_owned(),
+            expected: expected.into(),
+            port: output.into_port(),
+        });
+        self
+    }
+
+    /// Create the [Router], packages that not match any rule are sent to `unmatched`
+    pub fn build(self, unmatched: O) -> Router<G, O> {
+        Router {
+            rules: self.rules,
+            unmatched: unmatched.into_port(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<G, O> ComponentSchema for Router<G, O>
+where
+    G: Send + Sync + 'static,
+    O: Outputs + 'static,
+{
+    type Inputs = In;
+    type Outputs = O;
+
+    type Global = G;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In::Package) {
+            let port = self.route(&package);
+            ctx.send_in_port(port, package);
+        }
+        Ok(Next::Continue)
+    }
+
+    fn description() -> &'static str {
+        "Forward each package to the output of the first rule matched"
+    }
+}
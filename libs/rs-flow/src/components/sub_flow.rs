@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::connection::Point;
+use crate::prelude::*;
+
+type InitFn<V> = Box<dyn Fn() -> V + Send + Sync>;
+type MergeFn<G, V> = Box<dyn Fn(&mut G, V) + Send + Sync>;
+
+///
+/// Run a whole [Flow] as a [Component] of another flow, to reuse a fragment of pipeline.
+///
+/// The [Inputs] ports `I` and [Outputs] ports `O` are mapped to boundary ports of the
+/// inner flow. Each run receive all packages waiting in the input ports and push them
+/// in the mapped inner input ports, then the inner flow run until finish, and the
+/// packages sent by the mapped inner output ports are sent by the outputs of this component.
+///
+/// The inner flow have your own global data `V`, created by `init` before each run and
+/// dropped after the run, unless a [merge](SubFlowBuilder::merge) function is given to
+/// put it in the global data `G` of outer flow.
+///
+/// ```
+/// use rs_flow::components::sub_flow::SubFlow;
+/// use rs_flow::components::map::{self, MapComponent};
+/// use rs_flow::connection::Point;
+/// use rs_flow::prelude::*;
+///
+/// #[derive(Inputs)]
+/// struct In;
+///
+/// #[derive(Outputs)]
+/// struct Out;
+///
+/// let inner = Flow::new()
+///     .add_component(Component::new(1, MapComponent::<()>::new(|package| {
+///         Ok(Package::number(package.get_number()? * 2.0))
+///     })))
+///     .unwrap();
+///
+/// let double: SubFlow<(), (), In, Out> = SubFlow::builder(inner, || ())
+///     .input(In, Point::new(1, map::In::Package.into_port()))
+///     .output(Point::new(1, map::Out::Package.into_port()), Out)
+///     .build()
+///     .unwrap();
+/// ```
+///
+pub struct SubFlow<G, V, I, O> {
+    flow: Flow<V>,
+    inputs: Vec<(PortId, Point)>,
+    outputs: Vec<(Point, PortId)>,
+    init: InitFn<V>,
+    merge: Option<MergeFn<G, V>>,
+    _marker: PhantomData<fn() -> (I, O)>,
+}
+
+/// Builder of the boundary ports of a [SubFlow]
+pub struct SubFlowBuilder<G, V, I, O> {
+    sub_flow: SubFlow<G, V, I, O>,
+}
+
+impl<G, V, I, O> SubFlow<G, V, I, O>
+where
+    I: Inputs,
+    O: Outputs,
+{
+    /// Create a [SubFlowBuilder] of the `flow`, that use the global data created by `init`
+    pub fn builder(
+        flow: Flow<V>,
+        init: impl Fn() -> V + Send + Sync + 'static,
+    ) -> SubFlowBuilder<G, V, I, O> {
+        SubFlowBuilder {
+            sub_flow: SubFlow {
+                flow,
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                init: Box::new(init),
+                merge: None,
+                _marker: PhantomData,
+            },
+        }
+    }
+}
+
+impl<G, V, I, O> SubFlowBuilder<G, V, I, O>
+where
+    I: Inputs,
+    O: Outputs,
+{
+    /// Push the packages received by `input` in the input port `to` of the inner flow
+    pub fn input(mut self, input: I, to: Point) -> Self {
+        self.sub_flow.inputs.push((input.into_port(), to));
+        self
+    }
+
+    /// Send by `output` the packages sent by the output port `from` of the inner flow
+    pub fn output(mut self, from: Point, output: O) -> Self {
+        self.sub_flow.outputs.push((from, output.into_port()));
+        self
+    }
+
+    /// Put the global data of inner flow in the global data of outer flow after each run
+    pub fn merge(mut self, merge: impl Fn(&mut G, V) + Send + Sync + 'static) -> Self {
+        self.sub_flow.merge = Some(Box::new(merge));
+        self
+    }
+
+    /// Create the [SubFlow]
+    ///
+    /// # Error
+    ///
+    /// - Error [ComponentNotFound](crate::error::Error::ComponentNotFound) if a boundary
+    ///   [Point] is of a component that not exist in the inner flow
+    /// - Error [InPortNotFound](crate::error::Error::InPortNotFound) or
+    ///   [OutPortNotFound](crate::error::Error::OutPortNotFound) if a boundary [Point] is
+    ///   of a port that not exist in the component
+    pub fn build(self) -> Result<SubFlow<G, V, I, O>> {
+        let flow = &self.sub_flow.flow;
+        let component = |id| {
+            flow.components
+                .get(&id)
+                .ok_or(Error::ComponentNotFound { id })
+        };
+
+        for (_, to) in self.sub_flow.inputs.iter() {
+            if !component(to.id())?.inputs.contains(to.port()) {
+                return Err(Box::new(Error::InPortNotFound {
+                    component: to.id(),
+                    in_port: to.port(),
+                }));
+            }
+        }
+        for (from, _) in self.sub_flow.outputs.iter() {
+            if !component(from.id())?.outputs.contains(from.port()) {
+                return Err(Box::new(Error::OutPortNotFound {
+                    component: from.id(),
+                    out_port: from.port(),
+                }));
+            }
+        }
+
+        Ok(self.sub_flow)
+    }
+}
+
+#[async_trait]
+impl<G, V, I, O> ComponentSchema for SubFlow<G, V, I, O>
+where
+    G: Send + Sync + 'static,
+    V: Send + Sync + 'static,
+    I: Inputs + 'static,
+    O: Outputs + 'static,
+{
+    type Inputs = I;
+    type Outputs = O;
+
+    type Global = G;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let mut inputs: HashMap<Point, Vec<Package>> = HashMap::new();
+        for (port, to) in self.inputs.iter() {
+            let packages = inputs.entry(*to).or_default();
+            while let Some(envelope) = ctx.receive_in_port(*port) {
+                packages.push(envelope.into_package());
+            }
+        }
+
+        let (global, sent) = self
+            .flow
+            .run_recording((self.init)(), inputs, u32::MAX)
+            .await?;
+        if let Some(merge) = self.merge.as_ref() {
+            ctx.with_mut_global(|outer| merge(outer, global))?;
+        }
+
+        for (from, port) in self.outputs.iter() {
+            for package in sent.get(from).into_iter().flatten() {
+                ctx.send_in_port(*port, package.clone());
+            }
+        }
+        Ok(Next::Continue)
+    }
+
+    fn description() -> &'static str {
+        "Run a inner flow with the packages received and send the packages of its outputs"
+    }
+}
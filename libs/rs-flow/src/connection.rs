@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
@@ -110,12 +110,15 @@ impl Connection {
 /// that is usefull for know when components of [`Eager`](crate::component::Type#variant.Eager) type is ready to run.
 ///
 /// That graph cannot create a Loop, end return a error if try
-/// add a connection that create a Loop.
+/// add a connection that create a Loop. Only the feedback connections (back edges),
+/// added with [Connections::add_feedback], can close a loop, they deliver packages
+/// like the others connections but are ignored in the parents and descendants.
 ///
 #[derive(Debug, Clone)]
 pub(crate) struct Connections {
     parents: HashMap<Id, Vec<Id>>,
     connections: HashMap<Point, Vec<Point>>,
+    feedback: HashSet<(Point, Point)>,
 }
 
 /// Empty graph of Flow connections
@@ -124,6 +127,7 @@ impl Default for Connections {
         Connections {
             parents: Default::default(),
             connections: Default::default(),
+            feedback: Default::default(),
         }
     }
 }
@@ -136,28 +140,110 @@ impl Connections {
 
     /// Insert a connection
     pub(crate) fn add(&mut self, connection: Connection) -> Result<()> {
-        if connection.from == connection.to || self.ancestor_of(connection.from, connection.to) {
-            return Err(Error::LoopCreated { connection }.into());
+        self.check(&connection)?;
+
+        self.connections
+            .entry(connection.from())
+            .or_default()
+            .push(connection.to());
+
+        let parents = self.parents.entry(connection.to).or_default();
+        if !parents.contains(&connection.from) {
+            parents.push(connection.from);
         }
 
-        let entry = self.connections.entry(connection.from());
-        let to = connection.to();
-        let to_ports = entry.or_default();
+        Ok(())
+    }
 
-        if to_ports.contains(&to) {
-            return Err(Error::ConnectionAlreadyExist { connection }.into());
+    /// Insert a feedback connection, that can close a loop
+    pub(crate) fn add_feedback(&mut self, connection: Connection) -> Result<()> {
+        if self.contains(&connection) {
+            return Err(Error::ConnectionAlreadyExist { connection });
         }
 
-        to_ports.push(to);
+        self.connections
+            .entry(connection.from())
+            .or_default()
+            .push(connection.to());
+        self.feedback.insert((connection.from(), connection.to()));
 
-        let parents = self.parents.entry(connection.to).or_default();
-        if !parents.contains(&connection.from) {
-            parents.push(connection.from);
+        Ok(())
+    }
+
+    /// Return if the connection is a feedback connection, see [Connections::add_feedback]
+    pub(crate) fn is_feedback(&self, connection: &Connection) -> bool {
+        self.feedback
+            .contains(&(connection.from(), connection.to()))
+    }
+
+    /// Remove a connection
+    pub(crate) fn remove(&mut self, connection: &Connection) -> Result<()> {
+        let to_ports = self
+            .connections
+            .get_mut(&connection.from())
+            .filter(|to_ports| to_ports.contains(&connection.to()))
+            .ok_or_else(|| Error::ConnectionNotFound {
+                connection: connection.clone(),
+            })?;
+        to_ports.retain(|to| *to != connection.to());
+        if to_ports.is_empty() {
+            self.connections.remove(&connection.from());
+        }
+        if self.feedback.remove(&(connection.from(), connection.to())) {
+            return Ok(());
+        }
+
+        // other ports of the same components can still be connected
+        let still_parent = self
+            .connections
+            .iter()
+            .filter(|(from, _)| from.id() == connection.from)
+            .flat_map(|(from, to_ports)| to_ports.iter().map(move |to| (*from, *to)))
+            .any(|(from, to)| to.id() == connection.to && !self.feedback.contains(&(from, to)));
+        if !still_parent {
+            if let Some(parents) = self.parents.get_mut(&connection.to) {
+                parents.retain(|parent| *parent != connection.from);
+                if parents.is_empty() {
+                    self.parents.remove(&connection.to);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove all connections from or to a component
+    pub(crate) fn remove_component(&mut self, id: Id) {
+        let connections = self
+            .to_vec()
+            .into_iter()
+            .chain(self.feedback_to_vec())
+            .filter(|connection| connection.from == id || connection.to == id);
+        for connection in connections {
+            self.remove(&connection)
+                .expect("Connection returned by to_vec exist");
         }
+    }
 
+    /// Validate a [Connection] without insert it
+    pub(crate) fn check(&self, connection: &Connection) -> Result<()> {
+        if self.would_create_loop(connection) {
+            return Err(Error::LoopCreated {
+                connection: connection.clone(),
+            });
+        }
+        if self.contains(connection) {
+            return Err(Error::ConnectionAlreadyExist {
+                connection: connection.clone(),
+            });
+        }
         Ok(())
     }
 
+    pub(crate) fn would_create_loop(&self, connection: &Connection) -> bool {
+        connection.from == connection.to || self.ancestor_of(connection.to, connection.from)
+    }
+
     pub(crate) fn ancestor_of(&self, ancestor: Id, id: Id) -> bool {
         if let Some(parents) = self.parents.get(&id) {
             for parent in parents {
@@ -185,7 +271,82 @@ impl Connections {
         false
     }
 
+    /// Return the parents of a component, components with a connection to it
+    pub(crate) fn parents(&self, id: Id) -> &[Id] {
+        self.parents.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Return all components reachable by the connections that leave a component
+    pub(crate) fn descendants(&self, id: Id) -> HashSet<Id> {
+        let mut descendants = HashSet::new();
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            for to in self
+                .connections
+                .iter()
+                .filter(|(from, _)| from.id() == id)
+                .flat_map(|(from, to_ports)| to_ports.iter().map(move |to| (*from, *to)))
+                .filter(|edge| !self.feedback.contains(edge))
+                .map(|(_, to)| to)
+            {
+                if descendants.insert(to.id()) {
+                    pending.push(to.id());
+                }
+            }
+        }
+        descendants
+    }
+
     pub(crate) fn from(&self, from: Point) -> Option<&Vec<Point>> {
         self.connections.get(&from)
     }
+
+    /// Return if the connection exist
+    pub(crate) fn contains(&self, connection: &Connection) -> bool {
+        self.connections
+            .get(&connection.from())
+            .is_some_and(|to_ports| to_ports.contains(&connection.to()))
+    }
+
+    /// Return all connections, without the feedback connections, ordered by the from and to points
+    pub(crate) fn to_vec(&self) -> Vec<Connection> {
+        let mut connections = self
+            .connections
+            .iter()
+            .flat_map(|(from, to_ports)| to_ports.iter().map(|to| (*from, *to)))
+            .filter(|edge| !self.feedback.contains(edge))
+            .map(|(from, to)| Connection::by(from, to))
+            .collect::<Vec<_>>();
+        connections.sort_by_key(|c| (c.from, c.out_port, c.to, c.in_port));
+        connections
+    }
+
+    /// Return the feedback connections, ordered by the from and to points
+    pub(crate) fn feedback_to_vec(&self) -> Vec<Connection> {
+        let mut connections = self
+            .feedback
+            .iter()
+            .map(|(from, to)| Connection::by(*from, *to))
+            .collect::<Vec<_>>();
+        connections.sort_by_key(|c| (c.from, c.out_port, c.to, c.in_port));
+        connections
+    }
+
+    /// Largest number of input points connected to a same output point
+    pub(crate) fn max_fan_out(&self) -> usize {
+        self.connections
+            .values()
+            .map(|to_ports| to_ports.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Largest number of output points connected to a same input point
+    pub(crate) fn max_fan_in(&self) -> usize {
+        let mut fan_in: HashMap<Point, usize> = HashMap::new();
+        for to in self.connections.values().flatten() {
+            *fan_in.entry(*to).or_default() += 1;
+        }
+        fan_in.into_values().max().unwrap_or(0)
+    }
 }
@@ -1,9 +1,15 @@
-use std::collections::{HashMap, VecDeque};
+use std::any::{Any, TypeId};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::ops::AddAssign;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::context::global::Global;
+use crate::context::envelope::Envelope;
+use crate::context::global::{Accumulated, Delta, Global, GlobalTypeNames, Globals};
+use crate::context::queue::ReceiveQueue;
 
-use crate::component::{Id, Type};
+use crate::component::{Id, Overflow, Type};
+use crate::connection::Point;
 use crate::error::{Error, Result};
 use crate::package::Package;
 use crate::ports::{Inputs, Outputs, PortId};
@@ -17,11 +23,30 @@ pub struct Ctx<G> {
     pub(crate) id: Id,
     pub(crate) ty: Type,
     pub(crate) send: HashMap<PortId, VecDeque<Package>>,
-    pub(crate) receive: HashMap<PortId, VecDeque<Package>>,
+    pub(crate) receive: HashMap<PortId, ReceiveQueue>,
+    pub(crate) optional: HashSet<PortId>,
+    pub(crate) closed: HashSet<PortId>,
+    pub(crate) held: HashSet<PortId>,
+    /// Input ports connected to each output port, see [Ctx::send_to]
+    pub(crate) targets: HashMap<PortId, Vec<Point>>,
+    /// Packages sent to a single input port, by output port
+    pub(crate) directed: HashMap<PortId, Vec<(Point, Package)>>,
+    ttls: HashMap<PortId, u32>,
+    pub(crate) capacities: HashMap<PortId, (usize, Overflow)>,
     pub(crate) consumed: bool,
+    pub(crate) allow_no_consume: bool,
+    pub(crate) prune: bool,
+    pub(crate) once: bool,
+    pub(crate) received: usize,
+    pub(crate) sent: usize,
+    pub(crate) elapsed: Duration,
+    pub(crate) cycle_budget: Option<usize>,
     pub(crate) cicle: u32,
+    pub(crate) traced: bool,
 
     global: Arc<Global<G>>,
+    deltas: HashMap<TypeId, Box<dyn Delta<G>>>,
+    state: Option<Box<dyn Any + Send + Sync>>,
 }
 
 impl<G> Ctx<G> {
@@ -32,25 +57,51 @@ impl<G> Ctx<G> {
                 .iter()
                 .map(|port| (port.port, VecDeque::new())),
         );
-        let receive = HashMap::from_iter(
-            component
-                .inputs
-                .iter()
-                .map(|port| (port.port, VecDeque::new())),
-        );
+        let receive = HashMap::from_iter(component.inputs.iter().map(|port| {
+            let policy = component
+                .policies
+                .get(&port.port)
+                .cloned()
+                .unwrap_or_default();
+            (port.port, ReceiveQueue::new(&policy))
+        }));
+        let optional = component
+            .inputs
+            .iter()
+            .filter(|port| port.optional)
+            .map(|port| port.port)
+            .collect();
         Self {
             id: component.id,
             ty: component.ty,
             send,
             receive,
+            optional,
+            closed: HashSet::new(),
+            held: HashSet::new(),
+            targets: HashMap::new(),
+            directed: HashMap::new(),
+            ttls: component.ttls.clone(),
+            capacities: component.capacities.clone(),
             consumed: false,
+            allow_no_consume: component.allow_no_consume,
+            prune: false,
+            once: component.once,
+            received: 0,
+            sent: 0,
+            elapsed: Duration::ZERO,
+            cycle_budget: component.cycle_budget,
             cicle: 0,
+            traced: false,
             global: global.clone(),
+            deltas: HashMap::new(),
+            state: None,
         }
     }
 
     ///
-    /// Recieve a [Package] from a [Port](crate::ports::Port)
+    /// Recieve a [Package] from a [Port](crate::ports::Port), a optional port
+    /// can return [None] even in the first call
     ///
     /// # Panics
     ///
@@ -58,22 +109,257 @@ impl<G> Ctx<G> {
     ///
     pub fn receive<I: Inputs>(&mut self, in_port: I) -> Option<Package> {
         let port = in_port.into_port();
-        self.receive_in_port(port)
+        self.receive_in_port(port).map(Envelope::into_package)
+    }
+
+    ///
+    /// Recieve a [Package] from the first port of `order` that have a package, so the
+    /// ports are checked from the highest to the lowest priority. Return the package
+    /// with the [PortId] of port that it was received.
+    ///
+    /// # Panics
+    ///
+    /// Panic if recieve from a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn receive_prioritized<I: Inputs>(&mut self, order: &[I]) -> Option<(PortId, Package)> {
+        order.iter().find_map(|in_port| {
+            let port = in_port.into_port();
+            self.receive_in_port(port)
+                .map(|envelope| (port, envelope.into_package()))
+        })
+    }
+
+    ///
+    /// Recieve one [Package] from each port of `ports`, in the same order, only if all ports have
+    /// packages, otherwise return [None] and nothing is received. Unlike the typed ports of
+    /// [Ctx::receive], the ports can be defined at runtime, like in a zip of N inputs.
+    ///
+    /// # Error
+    ///
+    /// Error [InvalidMultipleRecivedPorts](crate::error::Error::InvalidMultipleRecivedPorts)
+    /// if a port is repeated in `ports`
+    ///
+    /// # Panics
+    ///
+    /// Panic if recieve from a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn receive_many_dyn(&mut self, ports: &[PortId]) -> Result<Option<Vec<Package>>> {
+        let unique = ports.iter().collect::<HashSet<_>>();
+        if unique.len() != ports.len() {
+            return Err(Error::InvalidMultipleRecivedPorts {
+                component: self.id,
+                ports: ports.to_vec(),
+            });
+        }
+
+        // stale packages are discarded before, so a port with packages never return None
+        for port in ports {
+            if let Some(ttl) = self.ttls.get(port).copied() {
+                let cicle = self.cicle;
+                self.receive
+                    .get_mut(port)
+                    .ok_or(Error::QueueNotCreated {
+                        component: self.id,
+                        port: *port,
+                    })
+                    .unwrap()
+                    .retain(|envelope| cicle.saturating_sub(envelope.cicle) <= ttl);
+            }
+        }
+        if ports.iter().any(|port| self.queue(*port).is_empty()) {
+            return Ok(None);
+        }
+
+        let packages = ports
+            .iter()
+            .map(|port| {
+                self.receive_in_port(*port)
+                    .map(Envelope::into_package)
+                    .expect("Ports with packages always receive a package")
+            })
+            .collect();
+        Ok(Some(packages))
+    }
+
+    ///
+    /// Recieve a [Package] from a [Port](crate::ports::Port) together with
+    /// the [Id] of the [Component] that sent it
+    ///
+    /// # Panics
+    ///
+    /// Panic if recieve from a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn receive_with_source<I: Inputs>(&mut self, in_port: I) -> Option<(Package, Id)> {
+        let port = in_port.into_port();
+        self.receive_in_port(port).map(|envelope| {
+            let source = envelope.source;
+            (envelope.into_package(), source)
+        })
+    }
+
+    ///
+    /// Return the next [Package] that will be received from a [Port](crate::ports::Port),
+    /// without take it from queue. Peek not count as a consume of packages, see
+    /// [AnyPackageConsumed](crate::error::Error::AnyPackageConsumed).
+    ///
+    /// Packages older than the [`input_ttl`](Component::input_ttl) of port are skipped,
+    /// like when received.
+    ///
+    /// # Panics
+    ///
+    /// Panic if peek a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn peek<I: Inputs>(&self, in_port: I) -> Option<&Package> {
+        let port = in_port.into_port();
+        self.queue(port)
+            .iter()
+            .find(|envelope| self.is_alive(port, envelope))
+            .map(|envelope| envelope.package.as_ref())
+    }
+
+    ///
+    /// Return the number of packages waiting in a [Port](crate::ports::Port),
+    /// without take them from queue, see [Ctx::pending]
+    ///
+    /// # Panics
+    ///
+    /// Panic if peek a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn peek_len<I: Inputs>(&self, in_port: I) -> usize {
+        self.pending_in_port(in_port.into_port())
+    }
+
+    ///
+    /// Return the number of packages waiting in a input port, `0` if the port is
+    /// [closed](Ctx::close). Packages older than the [`input_ttl`](Component::input_ttl)
+    /// of port are not counted. Not consume any package, so can be used to decide if
+    /// the [Component] will wait more packages before receive them.
+    ///
+    /// # Panics
+    ///
+    /// Panic if the [Input](crate::ports::Inputs) Port not exist in this [Component]
+    ///
+    pub fn pending<I: Inputs>(&self, in_port: I) -> usize {
+        self.pending_in_port(in_port.into_port())
+    }
+
+    /// Return the number of packages waiting in each input port, see [Ctx::pending]
+    pub fn pending_all(&self) -> HashMap<PortId, usize> {
+        self.receive
+            .keys()
+            .map(|port| (*port, self.pending_in_port(*port)))
+            .collect()
+    }
+
+    fn pending_in_port(&self, port: PortId) -> usize {
+        if self.closed.contains(&port) {
+            0
+        } else if self.ttls.contains_key(&port) {
+            self.queue(port)
+                .iter()
+                .filter(|envelope| self.is_alive(port, envelope))
+                .count()
+        } else {
+            self.queue(port).len()
+        }
     }
-    fn receive_in_port(&mut self, port: PortId) -> Option<Package> {
-        let package = self
+
+    /// Return if the package is not older than the ttl of port
+    fn is_alive(&self, port: PortId, envelope: &Envelope) -> bool {
+        self.ttls
+            .get(&port)
+            .is_none_or(|ttl| self.cicle.saturating_sub(envelope.cicle) <= *ttl)
+    }
+
+    /// Return if all required inputs have packages, and at least one input
+    /// have packages when all inputs are optional. Closed inputs are not required
+    pub(crate) fn is_ready(&self) -> bool {
+        let required = self
             .receive
-            .get_mut(&port)
+            .iter()
+            .filter(|(port, _)| !self.optional.contains(port) && !self.closed.contains(port))
+            .all(|(_, queue)| !queue.is_empty());
+        let any = self.receive.values().any(|queue| !queue.is_empty());
+        required && any
+    }
+
+    fn queue(&self, port: PortId) -> &ReceiveQueue {
+        self.receive
+            .get(&port)
             .ok_or(Error::QueueNotCreated {
                 component: self.id,
                 port,
             })
             .unwrap()
-            .pop_front();
+    }
+
+    pub(crate) fn receive_in_port(&mut self, port: PortId) -> Option<Envelope> {
+        let queue = self
+            .receive
+            .get_mut(&port)
+            .ok_or(Error::QueueNotCreated {
+                component: self.id,
+                port,
+            })
+            .unwrap();
 
         self.consumed = true;
 
-        package
+        // discard the packages older than the ttl of port
+        let ttl = self.ttls.get(&port);
+        let envelope = std::iter::from_fn(|| queue.pop()).find(|envelope| {
+            ttl.is_none_or(|ttl| self.cicle.saturating_sub(envelope.cicle) <= *ttl)
+        });
+
+        if let Some(envelope) = &envelope {
+            self.received += 1;
+            self.traced |= envelope.traced;
+        }
+
+        envelope
+    }
+
+    /// Close a input port, discarding the packages waiting in the port and all packages
+    /// sent to it until be reopened with [Ctx::reopen]. While closed, [Ctx::receive] from
+    /// the port always return [None] and the port is not required for the [Component] be
+    /// ready to run, so the component still run with packages of the other ports.
+    ///
+    /// # Panics
+    ///
+    /// Panic if close a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn close<I: Inputs>(&mut self, in_port: I) {
+        let port = in_port.into_port();
+        self.receive
+            .get_mut(&port)
+            .ok_or(Error::QueueNotCreated {
+                component: self.id,
+                port,
+            })
+            .unwrap()
+            .retain(|_| false);
+        self.closed.insert(port);
+    }
+
+    /// Reopen a input port closed by [Ctx::close], so the packages sent to it after this
+    /// cicle are received normally. The packages discarded while closed are not recovered.
+    ///
+    /// # Panics
+    ///
+    /// Panic if reopen a [Input](crate::ports::Inputs) Port that not exist in this [Component]
+    ///
+    pub fn reopen<I: Inputs>(&mut self, in_port: I) {
+        let port = in_port.into_port();
+        assert!(
+            self.receive.contains_key(&port),
+            "Input port {port} not found"
+        );
+        self.closed.remove(&port);
+    }
+
+    /// Return if a input port is closed, see [Ctx::close]
+    pub fn is_closed<I: Inputs>(&self, in_port: I) -> bool {
+        self.closed.contains(&in_port.into_port())
     }
 
     /// Send a [Package] to a [Port](crate::ports::Port), if one [Component] is connected to this port than he
@@ -81,6 +367,9 @@ impl<G> Ctx<G> {
     ///
     /// If more than one components is connected in this port, each one recieve a copy of this [Package].
     ///
+    /// The packages sent to a same port, by this method or by [Ctx::send_all_broadcast],
+    /// are received in the order they were sent (with a [Fifo](crate::component::QueuePolicy::Fifo) input).
+    ///
     /// # Panics
     ///
     /// Panic if send to a [Output](crate::ports::Outputs) Port that not exist in this [Component]
@@ -89,7 +378,92 @@ impl<G> Ctx<G> {
         let port = out_port.into_port();
         self.send_in_port(port, package);
     }
-    fn send_in_port(&mut self, port: PortId, package: Package) {
+    /// Send a distinct [Package] to each [Output](crate::ports::Outputs) port, all ports are
+    /// validated before send, so if any port not exist no package is sent.
+    ///
+    /// # Error
+    ///
+    /// Error [QueueNotCreated](crate::error::Error::QueueNotCreated) if a port not exist in this [Component]
+    ///
+    pub fn fan<O: Outputs, V: Into<Package>, const N: usize>(
+        &mut self,
+        outs: [(O, V); N],
+    ) -> Result<()> {
+        let outs = outs.map(|(out_port, package)| (out_port.into_port(), package.into()));
+        if let Some((port, _)) = outs.iter().find(|(port, _)| !self.send.contains_key(port)) {
+            return Err(Error::QueueNotCreated {
+                component: self.id,
+                port: *port,
+            });
+        }
+        for (port, package) in outs {
+            self.send_in_port(port, package);
+        }
+        Ok(())
+    }
+
+    /// Send a [Package] by a output port only to the [Component] `target`, and not to the others
+    /// components connected to the port. Usefull to route each package to a consumer chosen
+    /// at runtime, when all consumers are connected to the same port.
+    ///
+    /// If the `target` is connected to the port by more than one input port, each input
+    /// port receive a copy of the package.
+    ///
+    /// # Error
+    ///
+    /// Error [TargetNotConnected](crate::error::Error::TargetNotConnected) if the `target`
+    /// is not connected to the output port
+    ///
+    pub fn send_to<O: Outputs>(&mut self, out_port: O, target: Id, package: Package) -> Result<()> {
+        let port = out_port.into_port();
+        let targets = self
+            .targets
+            .get(&port)
+            .into_iter()
+            .flatten()
+            .filter(|to| to.id() == target)
+            .copied()
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            return Err(Error::TargetNotConnected {
+                component: self.id,
+                out_port: port,
+                target,
+            });
+        }
+
+        let directed = self.directed.entry(port).or_default();
+        for to in targets {
+            directed.push((to, package.clone()));
+        }
+        self.sent += 1;
+        Ok(())
+    }
+
+    /// Send each [Package] of a batch to all [Output](crate::ports::Outputs) ports of this [Component].
+    ///
+    /// Each package is cloned for each port, so the cost is the number of packages
+    /// multiplied by the number of ports.
+    pub fn send_all_broadcast(&mut self, packages: Vec<Package>) {
+        for queue in self.send.values_mut() {
+            for package in packages.iter() {
+                queue.push_back(package.clone());
+            }
+        }
+        self.sent += packages.len() * self.send.len();
+    }
+
+    /// Send a [Package::EndOfStream] to a [Port](crate::ports::Port), signaling to the connected
+    /// [Component]'s that no more packages will be sent in this port.
+    ///
+    /// # Panics
+    ///
+    /// Panic if send to a [Output](crate::ports::Outputs) Port that not exist in this [Component]
+    ///
+    pub fn send_eos<O: Outputs>(&mut self, out_port: O) {
+        self.send(out_port, Package::EndOfStream);
+    }
+    pub(crate) fn send_in_port(&mut self, port: PortId, package: Package) {
         self.send
             .get_mut(&port)
             .ok_or(Error::QueueNotCreated {
@@ -97,7 +471,8 @@ impl<G> Ctx<G> {
                 port,
             })
             .unwrap()
-            .push_front(package);
+            .push_back(package);
+        self.sent += 1;
     }
 
     /// Interface tha provide a way to read the global data of the [Flow](crate::flow::Flow)
@@ -110,11 +485,236 @@ impl<G> Ctx<G> {
         self.global.with_mut_global(call)
     }
 
+    ///
+    /// Read the global data of type `T`, when the global data of [Flow](crate::flow::Flow)
+    /// is composed of many types, see [Globals]
+    ///
+    /// # Error
+    ///
+    /// - Error [GlobalNotFound](crate::error::Error::GlobalNotFound) if the global data
+    ///   not have the type `T`
+    /// - Error [CannotAccessGlobal](crate::error::Error::CannotAccessGlobal) if the global
+    ///   data could not be accessed
+    ///
+    pub fn with<T: 'static, R>(&self, call: impl FnOnce(&T) -> R) -> Result<R>
+    where
+        G: Globals,
+    {
+        self.try_with(call)?.ok_or(Error::GlobalNotFound {
+            type_name: std::any::type_name::<T>(),
+        })
+    }
+
+    /// Read and modify the global data of type `T`, see [Ctx::with]
+    pub fn with_mut<T: 'static, R>(&self, call: impl FnOnce(&mut T) -> R) -> Result<R>
+    where
+        G: Globals,
+    {
+        self.try_with_mut(call)?.ok_or(Error::GlobalNotFound {
+            type_name: std::any::type_name::<T>(),
+        })
+    }
+
+    /// Read the global data of type `T`, returning [None] if the global data
+    /// not have the type `T`, see [Ctx::with]
+    pub fn try_with<T: 'static, R>(&self, call: impl FnOnce(&T) -> R) -> Result<Option<R>>
+    where
+        G: Globals,
+    {
+        self.global.with_global(|global| global.get().map(call))
+    }
+
+    /// Read and modify the global data of type `T`, returning [None] if the global data
+    /// not have the type `T`, see [Ctx::with]
+    pub fn try_with_mut<T: 'static, R>(&self, call: impl FnOnce(&mut T) -> R) -> Result<Option<R>>
+    where
+        G: Globals,
+    {
+        self.global
+            .with_mut_global(|global| global.get_mut().map(call))
+    }
+
+    /// Return the type name of each type of the global data of the [Flow](crate::flow::Flow),
+    /// usefull to log the shape of shared state
+    pub fn global_type_names(&self) -> Result<Vec<&'static str>>
+    where
+        G: GlobalTypeNames,
+    {
+        self.global.with_global(GlobalTypeNames::type_names)
+    }
+
+    /// Return true if this [Component] already received the number of packages defined in
+    /// your [`cycle_budget`](Component::cycle_budget) in this cicle, hinting it to return
+    /// [Next::Continue](crate::component::Next::Continue) and process the rest in the next cicles.
+    ///
+    /// Always return false if the [Component] not have a budget.
+    pub fn should_yield(&self) -> bool {
+        self.cycle_budget
+            .is_some_and(|budget| self.received >= budget)
+    }
+
+    /// Return the budget of packages received by cicle of this [Component]
+    #[inline]
+    pub fn cycle_budget(&self) -> Option<usize> {
+        self.cycle_budget
+    }
+
+    /// Add a value to the global data of the [Flow](crate::flow::Flow) without lock it.
+    ///
+    /// The values are summed in this [Ctx] and added to the global data once, in a single
+    /// lock, when the `run` of the [Component] finish. So the additions must be commutative,
+    /// the order that the [Component]'s of a cicle apply your sums is not defined.
+    pub fn global_add<T>(&mut self, delta: T)
+    where
+        G: AddAssign<T>,
+        T: AddAssign + Send + 'static,
+    {
+        match self.deltas.entry(TypeId::of::<T>()) {
+            Entry::Occupied(mut entry) => {
+                let accumulated = entry
+                    .get_mut()
+                    .as_any_mut()
+                    .downcast_mut::<Accumulated<T>>()
+                    .expect("Deltas are indexed by your type");
+                accumulated.0 += delta;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(Box::new(Accumulated(delta)));
+            }
+        }
+    }
+
+    pub(crate) fn apply_deltas(&mut self) -> Result<()> {
+        if self.deltas.is_empty() {
+            return Ok(());
+        }
+        let deltas = std::mem::take(&mut self.deltas);
+        self.global.with_mut_global(|global| {
+            for delta in deltas.into_values() {
+                delta.apply(global);
+            }
+        })
+    }
+
+    /// Discard the packages sent and the global deltas of this cicle
+    pub(crate) fn discard(&mut self) {
+        for queue in self.send.values_mut() {
+            queue.clear();
+        }
+        self.directed.clear();
+        self.deltas.clear();
+        self.sent = 0;
+        self.prune = false;
+    }
+
+    /// Return the number of packages waiting in each input port, sorted by port
+    pub(crate) fn waiting(&self) -> Vec<(PortId, usize)> {
+        let mut waiting = self
+            .receive
+            .iter()
+            .map(|(port, queue)| (*port, queue.len()))
+            .collect::<Vec<_>>();
+        waiting.sort();
+        waiting
+    }
+
+    ///
+    /// Return the state of this [Component], that is kept between the cicles of a run,
+    /// like a counter or the last packages received. The state is created with
+    /// [Default] in the first call of a run.
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    ///
+    /// #[derive(Inputs)]
+    /// struct In;
+    ///
+    /// struct Count;
+    ///
+    /// #[async_trait]
+    /// impl ComponentSchema for Count {
+    ///     type Inputs = In;
+    ///     type Outputs = ();
+    ///     type Global = ();
+    ///
+    ///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+    ///         while ctx.receive(In).is_some() {
+    ///             *ctx.state_mut::<u64>() += 1;
+    ///         }
+    ///         println!("received {} packages until now", ctx.state_mut::<u64>());
+    ///         Ok(Next::Continue)
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panic if the state was created with other type
+    ///
+    pub fn state_mut<S: Default + Send + Sync + 'static>(&mut self) -> &mut S {
+        self.state
+            .get_or_insert_with(|| Box::new(S::default()))
+            .downcast_mut()
+            .expect("State of component was created with other type")
+    }
+
+    /// Return the state of this [Component], or [None] if not created yet
+    /// or created with other type, see [Ctx::state_mut]
+    pub fn state<S: Send + Sync + 'static>(&self) -> Option<&S> {
+        self.state.as_ref()?.downcast_ref()
+    }
+
+    ///
+    /// Hold the packages sent to a output port in this cicle, so they are delivered only
+    /// at end of the next cicle and the [Component]'s connected to the port run one cicle
+    /// later than the others, without close the branch.
+    ///
+    /// When more than one component is connected to the port (fan-out) all of them
+    /// receive the packages one cicle later, to pace only one branch send the packages
+    /// to a port connected only to the branch.
+    ///
+    /// # Panics
+    ///
+    /// Panic if the [Output](crate::ports::Outputs) Port not exist in this [Component]
+    ///
+    pub fn skip_downstream_this_cycle<O: Outputs>(&mut self, out_port: O) {
+        let port = out_port.into_port();
+        assert!(
+            self.send.contains_key(&port),
+            "Output port {port} not found"
+        );
+        self.held.insert(port);
+    }
+
+    /// Skip all descendants of this [Component] for the remainder of the run, the others
+    /// branches of the [Flow](crate::flow::Flow) continue to run.
+    ///
+    /// A descendant that also receive packages from a [Component] not pruned (fan-in of
+    /// a pruned and a live branch) is not skipped.
+    pub fn prune_downstream(&mut self) {
+        self.prune = true;
+    }
+
+    /// Return the index of the current cicle of [Flow](crate::flow::Flow), starting in `0`
+    /// for the cicle of entry points and incremented in each cicle
     #[inline]
     pub fn cicle(&self) -> u32 {
         self.cicle
     }
 
+    /// Define the cicle of this [Ctx], used by [Testing](crate::testing::Testing) to run
+    /// the component in the cicles defined by [Testing::at_cycle](crate::testing::Testing::at_cycle)
+    #[inline]
+    pub(crate) fn set_cycle(&mut self, cicle: u32) {
+        self.cicle = cicle;
+    }
+
+    /// Return if this [Component] received any package in this cicle
+    #[inline]
+    pub fn consumed(&self) -> bool {
+        self.consumed
+    }
+
     #[inline]
     pub fn id(&self) -> usize {
         self.id
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::component::Id;
+use crate::package::Package;
+
+///
+/// A [Package] waiting in a receive queue, with the [Id] of the component that sent it
+/// and the cicle that it was sent.
+///
+/// The package is shared by the copies of a fan-out, so send to many inputs clone
+/// only a pointer, and the data is cloned only when received by more than one component.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct Envelope {
+    pub(crate) package: Arc<Package>,
+    pub(crate) source: Id,
+    pub(crate) cicle: u32,
+    /// If the package is derived from a traced package
+    pub(crate) traced: bool,
+}
+
+impl Envelope {
+    pub(crate) fn new(package: Package, source: Id, cicle: u32) -> Self {
+        Self {
+            package: Arc::new(package),
+            source,
+            cicle,
+            traced: false,
+        }
+    }
+
+    /// Take the package, cloning it only if a other queue still share it
+    pub(crate) fn into_package(self) -> Package {
+        Arc::try_unwrap(self.package).unwrap_or_else(|package| (*package).clone())
+    }
+}
@@ -1,4 +1,6 @@
-use std::{fmt::Debug, sync::RwLock};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::{fmt::Debug, ops::AddAssign, sync::RwLock};
 
 use crate::error::{Error, Result};
 
@@ -33,3 +35,209 @@ impl<GD> Debug for Global<GD> {
         f.debug_tuple("Global").finish()
     }
 }
+
+/// A value buffered by a [Ctx](crate::context::Ctx) to be added to the global data
+pub(crate) trait Delta<G>: Send {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn apply(self: Box<Self>, global: &mut G);
+}
+
+pub(crate) struct Accumulated<T>(pub(crate) T);
+
+impl<G, T> Delta<G> for Accumulated<T>
+where
+    G: AddAssign<T>,
+    T: Send + 'static,
+{
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn apply(self: Box<Self>, global: &mut G) {
+        *global += self.0;
+    }
+}
+
+///
+/// Global data composed of many types, that can list the name of each type.
+///
+/// Implemented for tuples, where each element of tuple is a type of global data.
+///
+/// ```
+/// use rs_flow::GlobalTypeNames;
+///
+/// assert_eq!(
+///     (String::new(), 0u8).type_names(),
+///     vec!["alloc::string::String", "u8"]
+/// );
+/// ```
+///
+pub trait GlobalTypeNames {
+    /// Return the type name of each type of global data
+    fn type_names(&self) -> Vec<&'static str>;
+}
+
+impl GlobalTypeNames for () {
+    fn type_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+}
+
+macro_rules! impl_global_type_names {
+    ($($ty: ident),+) => {
+        impl<$($ty),+> GlobalTypeNames for ($($ty,)+) {
+            fn type_names(&self) -> Vec<&'static str> {
+                vec![$(std::any::type_name::<$ty>()),+]
+            }
+        }
+    };
+}
+impl_global_type_names!(A);
+impl_global_type_names!(A, B);
+impl_global_type_names!(A, B, C);
+impl_global_type_names!(A, B, C, D);
+impl_global_type_names!(A, B, C, D, E);
+impl_global_type_names!(A, B, C, D, E, F);
+impl_global_type_names!(A, B, C, D, E, F, H);
+impl_global_type_names!(A, B, C, D, E, F, H, I);
+
+///
+/// Global data composed of many types, where each type can be accessed alone,
+/// like a logger, a config and a metrics sink shared by the components.
+///
+/// Implemented for tuples, when a type is repeated in the tuple the first element
+/// of this type is returned, and for [GlobalBag], when the types are only known at runtime.
+///
+/// ```
+/// use rs_flow::Globals;
+///
+/// let mut global = (String::from("config"), 0u64);
+/// *global.get_mut::<u64>().unwrap() += 1;
+///
+/// assert_eq!(global.get::<String>().unwrap(), "config");
+/// assert_eq!(global.get::<u64>(), Some(&1));
+/// assert_eq!(global.get::<bool>(), None);
+/// ```
+///
+pub trait Globals {
+    /// Return the global data of type `T`, or [None] if not have this type
+    fn get<T: 'static>(&self) -> Option<&T>;
+
+    /// Return the global data of type `T` to be modified, or [None] if not have this type
+    fn get_mut<T: 'static>(&mut self) -> Option<&mut T>;
+}
+
+impl Globals for () {
+    fn get<T: 'static>(&self) -> Option<&T> {
+        None
+    }
+
+    fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        None
+    }
+}
+
+macro_rules! impl_globals {
+    ($($ty: ident $index: tt),+) => {
+        impl<$($ty: 'static),+> Globals for ($($ty,)+) {
+            fn get<T: 'static>(&self) -> Option<&T> {
+                $(
+                    if TypeId::of::<T>() == TypeId::of::<$ty>() {
+                        return (&self.$index as &dyn Any).downcast_ref();
+                    }
+                )+
+                None
+            }
+
+            fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+                $(
+                    if TypeId::of::<T>() == TypeId::of::<$ty>() {
+                        return (&mut self.$index as &mut dyn Any).downcast_mut();
+                    }
+                )+
+                None
+            }
+        }
+    };
+}
+impl_globals!(A 0);
+impl_globals!(A 0, B 1);
+impl_globals!(A 0, B 1, C 2);
+impl_globals!(A 0, B 1, C 2, D 3);
+impl_globals!(A 0, B 1, C 2, D 3, E 4);
+impl_globals!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_globals!(A 0, B 1, C 2, D 3, E 4, F 5, H 6);
+impl_globals!(A 0, B 1, C 2, D 3, E 4, F 5, H 6, I 7);
+
+///
+/// Global data with many values of distinct types inserted at runtime, like a logger,
+/// a config and a metrics sink, that the components access alone with
+/// [Ctx::with](crate::context::Ctx::with) and [Ctx::with_mut](crate::context::Ctx::with_mut).
+///
+/// The [Flow](crate::flow::Flow) return the whole bag after run, with all values
+/// inserted, so each value can be taken back with [GlobalBag::remove].
+///
+/// ```
+/// use rs_flow::{GlobalBag, Globals};
+///
+/// struct Config {
+///     verbose: bool,
+/// }
+///
+/// let mut bag = GlobalBag::new()
+///     .insert(Config { verbose: true })
+///     .insert(Vec::<String>::new());
+///
+/// bag.get_mut::<Vec<String>>().unwrap().push("started".to_owned());
+///
+/// assert!(bag.get::<Config>().unwrap().verbose);
+/// assert_eq!(bag.remove::<Vec<String>>(), Some(vec!["started".to_owned()]));
+/// assert!(bag.get::<Vec<String>>().is_none());
+/// ```
+///
+#[derive(Default)]
+pub struct GlobalBag {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl GlobalBag {
+    /// Create a empty bag
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value in bag, replacing the value of same type if exist
+    pub fn insert<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.values.insert(TypeId::of::<T>(), Box::new(value));
+        self
+    }
+
+    /// Remove and return the value of type `T`
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        let value = self.values.remove(&TypeId::of::<T>())?;
+        Some(*value.downcast().expect("Values are stored by your type id"))
+    }
+
+    /// Return if the bag have a value of type `T`
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl Globals for GlobalBag {
+    fn get<T: 'static>(&self) -> Option<&T> {
+        self.values.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.values.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+}
+
+impl Debug for GlobalBag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlobalBag")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
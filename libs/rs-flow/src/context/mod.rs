@@ -1,19 +1,36 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::{collections::HashMap, sync::Arc};
 
-use crate::component::{Component, Id, Type};
+use crate::component::{Component, Id, Overflow, Type};
 use crate::connection::{Connections, Point};
+use crate::context::envelope::Envelope;
 use crate::context::global::Global;
+use crate::error::{Error, Result};
+use crate::instrumentation::Instrumentation;
 use crate::package::Package;
+use crate::ports::PortId;
 
 mod ctx;
 pub use ctx::Ctx;
 
+pub(crate) mod envelope;
 pub(crate) mod global;
+pub use global::{GlobalBag, GlobalTypeNames, Globals};
+pub(crate) mod queue;
+mod trace;
+pub(crate) use trace::Tracing;
+pub use trace::{PackagePath, PackageTracer};
 
 pub(crate) struct Ctxs<G> {
     connections: Connections,
     contexts: HashMap<Id, Ctx<G>>,
+    pruned: HashSet<Id>,
+    /// Components that not run anymore, [once](crate::component::Component::once)
+    /// components that already run or components replayed from a snapshot
+    retired: HashSet<Id>,
+    /// Packages waiting space in a input port with [Overflow::Block]
+    blocked: HashMap<Point, VecDeque<Envelope>>,
+    tracing: Option<Tracing>,
 }
 impl<G> Ctxs<G> {
     pub(crate) fn new(
@@ -23,25 +40,82 @@ impl<G> Ctxs<G> {
     ) -> Self {
         let contexts = components
             .iter()
-            .map(|(id, component)| (*id, Ctx::from(component, &global)))
+            .map(|(id, component)| {
+                let mut ctx = Ctx::from(component, global);
+                ctx.targets = component
+                    .outputs
+                    .iter()
+                    .filter_map(|port| {
+                        let to = connections.from(component.from(port.port))?;
+                        Some((port.port, to.clone()))
+                    })
+                    .collect();
+                (*id, ctx)
+            })
             .collect();
 
         Self {
             connections: connections.clone(),
             contexts,
+            pruned: HashSet::new(),
+            retired: HashSet::new(),
+            blocked: HashMap::new(),
+            tracing: None,
         }
     }
 
+    /// Trace the first package sent equal to `seed` and the packages derived from it
+    pub(crate) fn trace(&mut self, seed: Package) {
+        self.tracing = Some(Tracing::new(seed));
+    }
+
+    /// Push packages in a receive queue before the first cicle, the
+    /// packages are received like sent by `source`
+    pub(crate) fn inject(
+        &mut self,
+        source: Id,
+        point: Point,
+        packages: Vec<Package>,
+    ) -> Result<()> {
+        let ctx = self
+            .contexts
+            .get_mut(&point.id())
+            .ok_or(Error::ComponentNotFound { id: point.id() })?;
+        let queue = ctx
+            .receive
+            .get_mut(&point.port())
+            .ok_or(Error::InPortNotFound {
+                component: point.id(),
+                in_port: point.port(),
+            })?;
+        queue.push_all(
+            packages
+                .into_iter()
+                .map(|package| Envelope::new(package, source, 0))
+                .collect(),
+        );
+        Ok(())
+    }
+
     pub(crate) fn borrow(&mut self, id: Id) -> Option<Ctx<G>> {
         self.contexts.remove(&id)
     }
 
-    pub(crate) fn refresh_queues(&mut self) {
+    /// Deliver the packages sent to the receive queues
+    ///
+    /// # Error
+    ///
+    /// Error [QueueFull](crate::error::Error::QueueFull) if packages not fit in
+    /// a input port with [Overflow::Error]
+    pub(crate) fn refresh_queues(
+        &mut self,
+        instrumentation: &dyn Instrumentation<G>,
+    ) -> Result<()> {
         // insert the packages in map or append with the exists packages
         fn insert_or_append(
             point: Point,
-            mut packages: VecDeque<Package>,
-            packages_received: &mut HashMap<Point, VecDeque<Package>>,
+            mut packages: VecDeque<Envelope>,
+            packages_received: &mut HashMap<Point, VecDeque<Envelope>>,
         ) {
             packages_received
                 .entry(point)
@@ -49,30 +123,71 @@ impl<G> Ctxs<G> {
                 .or_insert(packages);
         }
 
-        let mut packages_received: HashMap<Point, VecDeque<Package>> = HashMap::new();
+        let mut packages_received: HashMap<Point, VecDeque<Envelope>> = HashMap::new();
 
         for (id, ctx) in self.contexts.iter_mut() {
+            let cicle = ctx.cicle;
             for (port, send_queue) in ctx.send.iter_mut() {
+                // held packages stay in the send queue until the next refresh
+                if ctx.held.remove(port) {
+                    continue;
+                }
+
+                let from = Point::new(*id, *port);
+                for (to, package) in ctx.directed.remove(port).into_iter().flatten() {
+                    instrumentation.package_sent(from, &package);
+                    let mut envelope = Envelope::new(package, *id, cicle);
+                    if let Some(tracing) = self.tracing.as_mut() {
+                        envelope.traced = ctx.traced || tracing.is_seed(&envelope.package);
+                        if envelope.traced {
+                            instrumentation.package_traced(cicle, from, to);
+                        }
+                    }
+                    instrumentation.packages_moved(from, to, 1);
+                    insert_or_append(to, VecDeque::from([envelope]), &mut packages_received);
+                }
+
                 if send_queue.is_empty() {
                     continue;
                 }
+                let to_ports = self.connections.from(from);
+
+                for package in send_queue.iter() {
+                    instrumentation.package_sent(from, package);
+                }
 
-                let mut packages = VecDeque::new();
-                std::mem::swap(&mut packages, send_queue);
+                let packages = send_queue
+                    .drain(..)
+                    .map(|package| {
+                        let mut envelope = Envelope::new(package, *id, cicle);
+                        if let Some(tracing) = self.tracing.as_mut() {
+                            // packages sent by a component that received a traced package are derived from it
+                            envelope.traced = ctx.traced || tracing.is_seed(&envelope.package);
+                            if envelope.traced {
+                                for to in to_ports.into_iter().flatten() {
+                                    instrumentation.package_traced(cicle, from, *to);
+                                }
+                            }
+                        }
+                        envelope
+                    })
+                    .collect::<VecDeque<_>>();
 
-                if let Some(to_ports) = self.connections.from(Point::new(*id, *port)) {
+                if let Some(to_ports) = to_ports {
+                    for to in to_ports {
+                        instrumentation.packages_moved(from, *to, packages.len());
+                    }
                     match to_ports.len() {
                         0 => {}
                         1 => {
-                            let to = to_ports[0].clone();
+                            let to = to_ports[0];
                             insert_or_append(to, packages, &mut packages_received);
                         }
                         _ => {
-                            for i in 1..to_ports.len() {
-                                let to = to_ports[i].clone();
-                                insert_or_append(to, packages.clone(), &mut packages_received);
+                            for to in to_ports.iter().skip(1) {
+                                insert_or_append(*to, packages.clone(), &mut packages_received);
                             }
-                            let to = to_ports[0].clone();
+                            let to = to_ports[0];
                             insert_or_append(to, packages, &mut packages_received);
                         }
                     }
@@ -80,41 +195,165 @@ impl<G> Ctxs<G> {
             }
         }
 
+        // packages blocked by a full input port are delivered before the new packages
+        for (point, mut blocked) in self.blocked.drain() {
+            if let Some(mut packages) = packages_received.remove(&point) {
+                blocked.append(&mut packages);
+            }
+            packages_received.insert(point, blocked);
+        }
+
         // Puting packages in recieve queue
         for (point, mut packages) in packages_received.drain() {
-            if let Some(ctx) = self.contexts.get_mut(&point.id()) {
-                if let Some(queue) = ctx.receive.get_mut(&point.port()) {
-                    queue.append(&mut packages);
+            let Some(ctx) = self.contexts.get_mut(&point.id()) else {
+                continue;
+            };
+            if ctx.closed.contains(&point.port()) {
+                continue;
+            }
+            let Some(queue) = ctx.receive.get_mut(&point.port()) else {
+                continue;
+            };
+            let Some((capacity, overflow)) = ctx.capacities.get(&point.port()) else {
+                queue.push_all(packages);
+                continue;
+            };
+
+            let free = capacity.saturating_sub(queue.len());
+            match overflow {
+                Overflow::Block => {
+                    let blocked = packages.split_off(free.min(packages.len()));
+                    if !blocked.is_empty() {
+                        self.blocked.insert(point, blocked);
+                    }
+                    queue.push_all(packages);
+                }
+                Overflow::Error if packages.len() > free => {
+                    return Err(Error::QueueFull {
+                        component: point.id(),
+                        port: point.port(),
+                    });
+                }
+                Overflow::Error => queue.push_all(packages),
+                Overflow::DropOldest => {
+                    queue.push_all(packages);
+                    while queue.len() > *capacity {
+                        queue.drop_oldest();
+                    }
                 }
             }
         }
+
+        Ok(())
     }
 
-    pub(crate) fn give_back(&mut self, ctx: Ctx<G>) {
+    /// Return if any component have packages held in the send queues,
+    /// see [Ctx::skip_downstream_this_cycle]
+    pub(crate) fn has_held(&self) -> bool {
+        self.contexts
+            .values()
+            .any(|ctx| !ctx.directed.is_empty() || ctx.send.values().any(|queue| !queue.is_empty()))
+    }
+
+    pub(crate) fn give_back(&mut self, mut ctx: Ctx<G>) {
+        if ctx.prune {
+            ctx.prune = false;
+            self.prune_downstream(ctx.id);
+        }
+        if ctx.once {
+            self.retired.insert(ctx.id);
+        }
         self.contexts.insert(ctx.id, ctx);
     }
 
+    /// Not run the component anymore
+    pub(crate) fn retire(&mut self, id: Id) {
+        self.retired.insert(id);
+    }
+
+    /// Return if the component not run anymore, see [Ctxs::retire]
+    pub(crate) fn is_retired(&self, id: Id) -> bool {
+        self.retired.contains(&id)
+    }
+
+    /// Mark as pruned the descendants of a component that only
+    /// receive packages from pruned components or from it
+    fn prune_downstream(&mut self, id: Id) {
+        let descendants = self.connections.descendants(id);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for descendant in descendants.iter() {
+                if self.pruned.contains(descendant) {
+                    continue;
+                }
+                let dead = self
+                    .connections
+                    .parents(*descendant)
+                    .iter()
+                    .all(|parent| *parent == id || self.pruned.contains(parent));
+                if dead {
+                    self.pruned.insert(*descendant);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// Return the components without inputs, sorted by id
     pub(crate) fn entry_points(&self) -> Vec<Id> {
-        self.contexts
+        let mut entry_points = self
+            .contexts
             .iter()
-            .filter(|(_, component)| component.receive.len() == 0)
+            .filter(|(_, component)| component.receive.is_empty())
             .map(|(id, _)| *id)
-            .collect()
+            .collect::<Vec<_>>();
+        entry_points.sort();
+        entry_points
     }
 
+    /// Return the component, port and number of packages of all not empty receive queues,
+    /// ignoring the pruned and retired components
+    pub(crate) fn pending(&self) -> Vec<(Id, PortId, usize)> {
+        let mut pending = self
+            .contexts
+            .iter()
+            .filter(|(id, _)| !self.pruned.contains(id) && !self.retired.contains(id))
+            .flat_map(|(id, ctx)| {
+                ctx.receive
+                    .iter()
+                    .filter(|(_, queue)| !queue.is_empty())
+                    .map(|(port, queue)| (*id, *port, queue.len()))
+            })
+            .collect::<Vec<_>>();
+        pending.sort();
+        pending
+    }
+
+    /// Return the components ready to run in the next cicle, sorted by id
     pub(crate) fn ready_components(&mut self, connections: &Connections) -> Vec<Id> {
+        // components with packages waiting space in a input port not run (back-pressure)
+        let blocked = self
+            .blocked
+            .values()
+            .flatten()
+            .map(|envelope| envelope.source)
+            .collect::<HashSet<_>>();
+
         let mut ready = self
             .contexts
             .iter()
             .filter_map(|(id, ctx)| {
-                if ctx.receive.len() == 0 {
+                if ctx.receive.is_empty()
+                    || self.pruned.contains(id)
+                    || self.retired.contains(id)
+                    || blocked.contains(id)
+                {
                     None
+                } else if ctx.is_ready() {
+                    Some(*id)
                 } else {
-                    if ctx.receive.iter().all(|(_, queue)| queue.len() > 0) {
-                        Some(*id)
-                    } else {
-                        None
-                    }
+                    None
                 }
             })
             .collect::<Vec<Id>>();
@@ -132,10 +371,12 @@ impl<G> Ctxs<G> {
                     Type::Lazy => false,
                 }
             })
-            .map(|id| *id)
+            .copied()
             .collect::<Vec<Id>>();
 
-        ready.retain(|id| !eager_not_ready.contains(&id));
+        ready.retain(|id| !eager_not_ready.contains(id));
+        // sorted to run the components of a cicle always in the same order
+        ready.sort();
 
         ready
     }
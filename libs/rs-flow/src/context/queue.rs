@@ -0,0 +1,150 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::component::{PriorityKey, QueuePolicy};
+use crate::context::envelope::Envelope;
+
+#[derive(Clone)]
+pub(crate) struct Prioritized {
+    key: i64,
+    sequence: Reverse<u64>,
+    envelope: Envelope,
+}
+
+impl PartialEq for Prioritized {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Prioritized {}
+
+impl PartialOrd for Prioritized {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Prioritized {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.key, self.sequence).cmp(&(other.key, other.sequence))
+    }
+}
+
+///
+/// Queue of packages received by a input port, the order that packages
+/// are taken is defined by the [QueuePolicy] of the port
+///
+#[derive(Clone)]
+pub(crate) enum ReceiveQueue {
+    Fifo(VecDeque<Envelope>),
+    Lifo(Vec<Envelope>),
+    Priority {
+        key: PriorityKey,
+        heap: BinaryHeap<Prioritized>,
+        sequence: u64,
+    },
+}
+
+impl ReceiveQueue {
+    pub(crate) fn new(policy: &QueuePolicy) -> Self {
+        match policy {
+            QueuePolicy::Fifo => ReceiveQueue::Fifo(VecDeque::new()),
+            QueuePolicy::Lifo => ReceiveQueue::Lifo(Vec::new()),
+            QueuePolicy::Priority(key) => ReceiveQueue::Priority {
+                key: key.clone(),
+                heap: BinaryHeap::new(),
+                sequence: 0,
+            },
+        }
+    }
+
+    /// Push all packages, in the order that they was received
+    pub(crate) fn push_all(&mut self, packages: VecDeque<Envelope>) {
+        match self {
+            ReceiveQueue::Fifo(queue) => queue.extend(packages),
+            ReceiveQueue::Lifo(stack) => stack.extend(packages),
+            ReceiveQueue::Priority {
+                key,
+                heap,
+                sequence,
+            } => {
+                for envelope in packages {
+                    heap.push(Prioritized {
+                        key: key(&envelope.package),
+                        sequence: Reverse(*sequence),
+                        envelope,
+                    });
+                    *sequence += 1;
+                }
+            }
+        }
+    }
+
+    /// Keep only the packages that `keep` return true
+    pub(crate) fn retain(&mut self, keep: impl Fn(&Envelope) -> bool) {
+        match self {
+            ReceiveQueue::Fifo(queue) => queue.retain(keep),
+            ReceiveQueue::Lifo(stack) => stack.retain(keep),
+            ReceiveQueue::Priority { heap, .. } => heap.retain(|p| keep(&p.envelope)),
+        }
+    }
+
+    /// Discard the package received first
+    pub(crate) fn drop_oldest(&mut self) {
+        match self {
+            ReceiveQueue::Fifo(queue) => {
+                queue.pop_front();
+            }
+            ReceiveQueue::Lifo(stack) => {
+                if !stack.is_empty() {
+                    stack.remove(0);
+                }
+            }
+            ReceiveQueue::Priority { heap, .. } => {
+                let mut packages = std::mem::take(heap).into_vec();
+                if let Some(oldest) = packages
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, p)| p.sequence)
+                    .map(|(index, _)| index)
+                {
+                    packages.swap_remove(oldest);
+                }
+                *heap = BinaryHeap::from(packages);
+            }
+        }
+    }
+
+    /// Take the next package
+    pub(crate) fn pop(&mut self) -> Option<Envelope> {
+        match self {
+            ReceiveQueue::Fifo(queue) => queue.pop_front(),
+            ReceiveQueue::Lifo(stack) => stack.pop(),
+            ReceiveQueue::Priority { heap, .. } => heap.pop().map(|p| p.envelope),
+        }
+    }
+
+    /// Iterate over the packages in the order that they will be taken
+    pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = &Envelope> + '_> {
+        match self {
+            ReceiveQueue::Fifo(queue) => Box::new(queue.iter()),
+            ReceiveQueue::Lifo(stack) => Box::new(stack.iter().rev()),
+            ReceiveQueue::Priority { heap, .. } => {
+                let mut packages = heap.iter().collect::<Vec<_>>();
+                packages.sort_by(|a, b| b.cmp(a));
+                Box::new(packages.into_iter().map(|p| &p.envelope))
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            ReceiveQueue::Fifo(queue) => queue.len(),
+            ReceiveQueue::Lifo(stack) => stack.len(),
+            ReceiveQueue::Priority { heap, .. } => heap.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
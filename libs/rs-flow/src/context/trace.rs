@@ -0,0 +1,105 @@
+use std::sync::Mutex;
+
+use crate::connection::Point;
+use crate::instrumentation::Instrumentation;
+use crate::package::Package;
+
+///
+/// Path of a traced [Package] in a [Flow](crate::flow::Flow), see
+/// [PackageTracer].
+///
+/// Each hop is the cicle that the package was sent, the [Point] that sent it
+/// and the [Point] that received it.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PackagePath {
+    hops: Vec<(u32, Point, Point)>,
+}
+
+impl PackagePath {
+    /// Return the hops sorted by cicle
+    pub fn hops(&self) -> &[(u32, Point, Point)] {
+        &self.hops
+    }
+
+    /// Return if the traced package never crossed a connection
+    pub fn is_empty(&self) -> bool {
+        self.hops.is_empty()
+    }
+}
+
+///
+/// [Instrumentation] that trace the path of a [Package], recording the hops of every
+/// [Connection](crate::connection::Connection) that the package crossed.
+///
+/// The first package sent equal to `seed` is traced, and because components transform
+/// packages, all packages sent by a component in a cicle that it received a traced
+/// package are also traced (including the copies of a fan-out). The trace ends when
+/// the traced packages are consumed without be sent again.
+///
+/// ```
+/// use rs_flow::prelude::*;
+/// use rs_flow::PackageTracer;
+///
+/// # tokio_test::block_on(async {
+/// let tracer = PackageTracer::new(Package::integer(1));
+/// Flow::<()>::new().run_instrumented((), &tracer).await?;
+///
+/// assert!(tracer.path().is_empty());
+/// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+/// # });
+/// ```
+///
+pub struct PackageTracer {
+    seed: Package,
+    hops: Mutex<Vec<(u32, Point, Point)>>,
+}
+
+impl PackageTracer {
+    /// Create a tracer of the first package sent equal to `seed`
+    pub fn new(seed: Package) -> Self {
+        Self {
+            seed,
+            hops: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Return the path of the traced package, with the hops sorted by cicle
+    pub fn path(&self) -> PackagePath {
+        let mut hops = self.hops.lock().unwrap().clone();
+        hops.sort_by_key(|(cicle, from, to)| (*cicle, from.id(), from.port(), to.id(), to.port()));
+        PackagePath { hops }
+    }
+}
+
+impl<G> Instrumentation<G> for PackageTracer {
+    fn traced_seed(&self) -> Option<Package> {
+        Some(self.seed.clone())
+    }
+
+    fn package_traced(&self, cycle: u32, from: Point, to: Point) {
+        self.hops.lock().unwrap().push((cycle, from, to));
+    }
+}
+
+/// State of the trace of a [Package] while the flow run
+pub(crate) struct Tracing {
+    /// Package that still not sent, after sent the packages derived from it are traced
+    seed: Option<Package>,
+}
+
+impl Tracing {
+    pub(crate) fn new(seed: Package) -> Self {
+        Self { seed: Some(seed) }
+    }
+
+    /// Return if a sent package is the seed, only the first package equal to seed is
+    pub(crate) fn is_seed(&mut self, package: &Package) -> bool {
+        if self.seed.as_ref() == Some(package) {
+            self.seed = None;
+            true
+        } else {
+            false
+        }
+    }
+}
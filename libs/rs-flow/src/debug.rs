@@ -0,0 +1,92 @@
+use std::sync::Mutex;
+
+use crate::component::Id;
+use crate::instrumentation::Instrumentation;
+use crate::ports::PortId;
+
+///
+/// What to do with a [Component](crate::component::Component) that returned a error,
+/// chosen by a [Instrumentation::component_failed], like the callback of a [Debugger]
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Stop the flow returning the error
+    Abort,
+    /// Discard the packages sent by the component in the cicle and continue the flow,
+    /// the packages received by the component before the error are lost
+    Skip,
+    /// Run the component again, with the same packages that it had before the error
+    Retry,
+}
+
+///
+/// State of a [Flow](crate::flow::Flow) when a [Component](crate::component::Component)
+/// returned a error, see [Instrumentation::component_failed]
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugContext {
+    /// Component that returned the error
+    pub component: Id,
+    /// Cicle that the error happened
+    pub cicle: u32,
+    /// Number of times that the component was already retried in this cicle
+    pub retries: u32,
+    /// Number of packages waiting in each input port of the component,
+    /// after the error and sorted by port
+    pub inputs: Vec<(PortId, usize)>,
+    /// Number of packages waiting in each input port of the components not running
+    /// in this cicle, sorted by component and port
+    pub pending: Vec<(Id, PortId, usize)>,
+}
+
+///
+/// [Instrumentation] that call `on_error` when a component return a error, instead of stop
+/// the flow. The callback receive the error and the state of the flow, and choose
+/// a [DebugAction] to abort the flow, skip the cicle of the component or retry it.
+///
+/// The components of a cicle run concurrently, and the errors are handled after all of
+/// them finish, in order of id. The changes made in global data by a component that
+/// failed are not undone, except the ones made with [Ctx::global_add](crate::Ctx::global_add).
+///
+/// ```
+/// use rs_flow::prelude::*;
+/// use rs_flow::{DebugAction, Debugger};
+///
+/// # tokio_test::block_on(async {
+/// let debugger = Debugger::new(|error, debug| {
+///     eprintln!("component {} failed: {error}", debug.component);
+///     DebugAction::Skip
+/// });
+/// Flow::<()>::new().run_instrumented((), &debugger).await?;
+/// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+/// # });
+/// ```
+///
+pub struct Debugger<F>(Mutex<F>);
+
+impl<F> Debugger<F>
+where
+    F: FnMut(&(dyn std::error::Error + Send + Sync), &DebugContext) -> DebugAction + Send,
+{
+    /// Create a debugger that call `on_error` with each error of a component
+    pub fn new(on_error: F) -> Self {
+        Self(Mutex::new(on_error))
+    }
+}
+
+impl<G, F> Instrumentation<G> for Debugger<F>
+where
+    F: FnMut(&(dyn std::error::Error + Send + Sync), &DebugContext) -> DebugAction + Send,
+{
+    fn handle_errors(&self) -> bool {
+        true
+    }
+
+    fn component_failed(
+        &self,
+        error: &(dyn std::error::Error + Send + Sync),
+        debug: &DebugContext,
+    ) -> DebugAction {
+        (self.0.lock().unwrap())(error, debug)
+    }
+}
@@ -1,5 +1,7 @@
 use crate::component::Id;
 use crate::connection::Connection;
+use crate::package::serde::PackageDeserializerError;
+use crate::package::{Package, PackageKind};
 use crate::ports::PortId;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -13,24 +15,119 @@ pub enum Error {
     #[error("Not found a operator with id = {id:?}")]
     ComponentNotFound { id: Id },
 
+    #[error("Component with id = {id:?} shifted by {offset:?} overflow the Id")]
+    IdOverflow { id: Id, offset: Id },
+
+    #[error("Component type = {type_name:?} is not registered")]
+    ComponentTypeNotRegistered { type_name: String },
+
+    #[error("Config of component with id = {id:?} is invalid: {error}")]
+    InvalidComponentConfig {
+        id: Id,
+        #[source]
+        error: PackageDeserializerError,
+    },
+
+    #[cfg(feature = "json")]
+    #[error("Flow description is not a valid json: {0}")]
+    InvalidFlowJson(#[source] serde_json::Error),
+
     #[error("Connection = {connection:?} already exist")]
     ConnectionAlreadyExist { connection: Connection },
 
+    #[error("Connection = {connection:?} not found")]
+    ConnectionNotFound { connection: Connection },
+
+    #[error("Component with id = {target:?} is not connected to the out port = {out_port:?} of component with id = {component:?}")]
+    TargetNotConnected {
+        component: Id,
+        out_port: PortId,
+        target: Id,
+    },
+
     #[error("A Loop is created with the connection = {connection:?}")]
     LoopCreated { connection: Connection },
 
+    #[error("Connection = {connection:?} connect a output of kind {out_kind:?} to a input of kind {in_kind:?}")]
+    PortKindMismatch {
+        connection: Connection,
+        out_kind: PackageKind,
+        in_kind: PackageKind,
+    },
+
     #[error("Component with id = {component:?} not have a Input = {in_port:?}")]
     InPortNotFound { component: Id, in_port: PortId },
 
     #[error("Component with id = {component:?} not have a Output = {out_port:?}")]
     OutPortNotFound { component: Id, out_port: PortId },
 
+    #[error("Component with id = {component:?} sent a package to the Output = {out_port:?} that is not connected")]
+    OutPortNotConnected { component: Id, out_port: PortId },
+
+    #[error(
+        "Output = {out_port:?} of component with id = {component:?} is not connected to any Input"
+    )]
+    OutPortUnused { component: Id, out_port: PortId },
+
+    #[error("Input = {in_port:?} of component with id = {component:?} is not connected, the component never be ready to run")]
+    InPortNotConnected { component: Id, in_port: PortId },
+
+    #[error("Component with id = {id:?} have ports but is not connected to any component")]
+    ComponentDisconnected { id: Id },
+
+    #[error("Component with id = {upstream:?} of type {after:?} precede the component with id = {downstream:?} of type {before:?}")]
+    TypeConstraintViolated {
+        before: String,
+        after: String,
+        upstream: Id,
+        downstream: Id,
+    },
+
     #[error("A queue of componenet id = {component:?} and port = {port:?} has not created, verify if a connection with this port exist")]
     QueueNotCreated { component: Id, port: PortId },
 
+    #[error("Input = {port:?} of component with id = {component:?} is full")]
+    QueueFull { component: Id, port: PortId },
+
+    #[error("Component with id = {component:?} cannot receive many times from the same port in {ports:?}")]
+    InvalidMultipleRecivedPorts { component: Id, ports: Vec<PortId> },
+
     #[error("No packages were consumed from the component = {component:?}")]
     AnyPackageConsumed { component: Id },
 
     #[error("The global data could not be accessed")]
     CannotAccessGlobal,
+
+    #[error("Global data of type = {type_name:?} not found")]
+    GlobalNotFound { type_name: &'static str },
+
+    #[error("Component with id = {id:?} panicked with message = {message:?}")]
+    ComponentPanicked { id: Id, message: String },
+
+    #[error("Component with id = {component:?} received the package = {package:?} that failed the assertion")]
+    AssertionFailed { component: Id, package: Package },
+
+    #[error("Component with id = {component:?} not finish your run in {elapsed:?}")]
+    ComponentTimeout {
+        component: Id,
+        elapsed: std::time::Duration,
+    },
+
+    #[error("Flow was cancelled before finish")]
+    Cancelled,
+
+    #[error("Component with id = {id:?} is not sync, create it with Component::sync")]
+    ComponentNotSync { id: Id },
+
+    #[error("Flow still running after the limit of {cicles:?} cicles")]
+    CycleLimitExceeded { cicles: u32 },
+
+    #[error("Cicle {cicle:?} not finish in the deadline of {deadline:?}")]
+    CycleDeadlineExceeded {
+        cicle: u32,
+        deadline: std::time::Duration,
+    },
+
+    #[error("No component is ready to run but packages are waiting in (component, port, length) = {pending:?}")]
+    Deadlock { pending: Vec<(Id, PortId, usize)> },
 }
@@ -0,0 +1,48 @@
+use crate::component::{Id, Next};
+use crate::instrumentation::{ComponentRun, Instrumentation};
+
+///
+/// Events emitted while a [Flow](crate::flow::Flow) run, see
+/// [Flow::on_event](crate::flow::Flow::on_event)
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowEvent {
+    /// A cicle started with the components ready to run, sorted by id
+    CycleStarted { cycle: u32, ready: Vec<Id> },
+    /// A component finished your run in the current cicle, emitted in order of id
+    ComponentFinished { id: Id, next: Next },
+    /// The packages sent in the cicle were delivered to the receive queues
+    QueuesRefreshed,
+    /// The flow finished without errors
+    FlowFinished,
+}
+
+/// [Instrumentation] that call a hook with each [FlowEvent]
+pub(crate) struct EventHook<F>(pub(crate) F);
+
+impl<G, F> Instrumentation<G> for EventHook<F>
+where
+    F: Fn(FlowEvent) + Send + Sync,
+{
+    fn cycle_started(&self, cycle: u32, ready: &[Id]) {
+        (self.0)(FlowEvent::CycleStarted {
+            cycle,
+            ready: ready.to_vec(),
+        });
+    }
+
+    fn component_run(&self, run: &ComponentRun) {
+        (self.0)(FlowEvent::ComponentFinished {
+            id: run.id,
+            next: run.next,
+        });
+    }
+
+    fn queues_refreshed(&self, _cycle: u32) {
+        (self.0)(FlowEvent::QueuesRefreshed);
+    }
+
+    fn flow_finished(&self, _global: &G) {
+        (self.0)(FlowEvent::FlowFinished);
+    }
+}
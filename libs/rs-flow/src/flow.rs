@@ -1,12 +1,25 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::Instant;
+
+use futures::FutureExt;
 
 use crate::component::Next;
-use crate::connection::{Connection, Connections};
+use crate::connection::{Connection, Connections, Point};
 use crate::context::global::Global;
-use crate::context::Ctxs;
+use crate::context::queue::ReceiveQueue;
+use crate::context::{Ctx, Ctxs};
+use crate::debug::{DebugAction, DebugContext};
 use crate::error::{Error, Result, RunResult};
+use crate::event::{EventHook, FlowEvent};
+use crate::instrumentation::{ComponentRun, Instrumentation, Instruments, NoOp, Recording};
+use crate::package::{Package, PackageSchema};
+use crate::ports::{PortId, Ports};
 use crate::prelude::{Component, Id};
+use crate::snapshot::FlowSnapshot;
+use crate::spec::{ComponentSpec, FlowSpec};
 
 ///
 /// A Flow provided a interface to run [Component]'s in a defined order.
@@ -127,8 +140,23 @@ use crate::prelude::{Component, Id};
 /// ```
 ///
 pub struct Flow<G> {
-    components: HashMap<Id, Component<G>>,
+    pub(crate) components: HashMap<Id, Component<G>>,
     connections: Connections,
+    groups: HashMap<String, Vec<Id>>,
+    strict_outputs: bool,
+    type_constraints: Vec<(String, String)>,
+    #[cfg(feature = "tokio")]
+    component_timeout: Option<std::time::Duration>,
+    instrumentations: Vec<Arc<dyn Instrumentation<G>>>,
+}
+
+impl<G> Default for Flow<G>
+where
+    G: Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<G> Flow<G>
@@ -140,22 +168,190 @@ where
         Self {
             components: HashMap::new(),
             connections: Connections::new(),
+            groups: HashMap::new(),
+            strict_outputs: false,
+            type_constraints: Vec::new(),
+            #[cfg(feature = "tokio")]
+            component_timeout: None,
+            instrumentations: Vec::new(),
+        }
+    }
+
+    /// Create a flow with all components and connections
+    ///
+    /// # Error
+    ///
+    /// Error if any [Component] or [Connection] is invalid, see [Flow::add_component]
+    /// and [Flow::add_connection]
+    pub fn from_parts(
+        components: impl IntoIterator<Item = Component<G>>,
+        connections: impl IntoIterator<Item = Connection>,
+    ) -> Result<Self> {
+        Self::from_parts_with_feedback(components, connections, [])
+    }
+
+    /// Create a flow with all components, connections and feedback connections,
+    /// the inverse of [Flow::into_parts]
+    ///
+    /// # Error
+    ///
+    /// Error if any [Component] or [Connection] is invalid, see [Flow::add_component],
+    /// [Flow::add_connection] and [Flow::add_feedback_connection]
+    pub fn from_parts_with_feedback(
+        components: impl IntoIterator<Item = Component<G>>,
+        connections: impl IntoIterator<Item = Connection>,
+        feedback: impl IntoIterator<Item = Connection>,
+    ) -> Result<Self> {
+        let mut flow = Self::new();
+        for component in components {
+            flow = flow.add_component(component)?;
         }
+        for connection in connections {
+            flow = flow.add_connection(connection)?;
+        }
+        for connection in feedback {
+            flow = flow.add_feedback_connection(connection)?;
+        }
+        Ok(flow)
+    }
+
+    /// Create a copy of this flow with the ids of all components shifted by `offset`, and the
+    /// connections and groups updated to the new ids. The copy can be merged in other flow
+    /// without collisions of ids, like a instance of the same flow for each tenant.
+    ///
+    /// The components of copy share the same schemas of this flow, see [Component]'s Clone.
+    ///
+    /// # Error
+    ///
+    /// Error [IdOverflow](crate::error::Error::IdOverflow) if a id shifted not fit in a [Id]
+    pub fn clone_offset(&self, offset: Id) -> Result<Flow<G>> {
+        let shift = |id: Id| {
+            id.checked_add(offset)
+                .ok_or(Error::IdOverflow { id, offset })
+        };
+
+        let components = self
+            .components
+            .values()
+            .map(|component| {
+                let mut component = component.clone();
+                component.id = shift(component.id)?;
+                Ok(component)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let shift_all = |connections: Vec<Connection>| {
+            connections
+                .into_iter()
+                .map(|c| {
+                    Ok(Connection::new(
+                        shift(c.from)?,
+                        c.out_port,
+                        shift(c.to)?,
+                        c.in_port,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let connections = shift_all(self.connections.to_vec())?;
+        let feedback = shift_all(self.connections.feedback_to_vec())?;
+
+        let mut flow = Self::from_parts_with_feedback(components, connections, feedback)?;
+        for (name, ids) in self.groups.iter() {
+            let ids = ids
+                .iter()
+                .map(|id| shift(*id))
+                .collect::<Result<Vec<_>>>()?;
+            flow.groups.insert(name.clone(), ids);
+        }
+        flow.strict_outputs = self.strict_outputs;
+        flow.type_constraints = self.type_constraints.clone();
+        #[cfg(feature = "tokio")]
+        {
+            flow.component_timeout = self.component_timeout;
+        }
+        flow.instrumentations = self.instrumentations.clone();
+
+        Ok(flow)
+    }
+
+    /// Decompose this flow in your components, connections and
+    /// [feedback connections](Flow::add_feedback_connection), all ordered,
+    /// that can be used to recreate the flow with [Flow::from_parts_with_feedback]
+    pub fn into_parts(self) -> (Vec<Component<G>>, Vec<Connection>, Vec<Connection>) {
+        let mut components = self.components.into_values().collect::<Vec<_>>();
+        components.sort_by_key(|component| component.id);
+        (
+            components,
+            self.connections.to_vec(),
+            self.connections.feedback_to_vec(),
+        )
     }
 
     /// Insert a [Component]
     ///
+    /// Every [Component] exchange the same [Package](crate::package::Package) type, the only
+    /// type that varies is the `Global` of the [ComponentSchema](crate::component::ComponentSchema),
+    /// that must be the `G` of the [Flow]. Insert a [Component] with another global not compile:
+    ///
+    /// ```compile_fail
+    /// use rs_flow::prelude::*;
+    ///
+    /// #[derive(Inputs)]
+    /// struct In;
+    ///
+    /// struct Sink;
+    ///
+    /// #[async_trait]
+    /// impl ComponentSchema for Sink {
+    ///     type Inputs = In;
+    ///     type Outputs = ();
+    ///     type Global = String;
+    ///
+    ///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+    ///         Ok(Next::Continue)
+    ///     }
+    /// }
+    ///
+    /// let flow: Flow<u32> = Flow::new().add_component(Component::new(1, Sink)).unwrap();
+    /// ```
+    ///
     /// # Error
     ///
     /// Error if the [Component::id] is already used
     pub fn add_component(mut self, component: Component<G>) -> Result<Self> {
         if self.components.contains_key(&component.id) {
-            return Err(Error::ComponentAlreadyExist { id: component.id }.into());
+            return Err(Error::ComponentAlreadyExist { id: component.id });
         }
         self.components.insert(component.id, component);
         Ok(self)
     }
 
+    /// Remove a [Component] and all [Connection]'s from or to it
+    ///
+    /// # Error
+    ///
+    /// Error if the this [Flow] not have a [Component] with that id
+    pub fn remove_component(&mut self, id: Id) -> Result<Component<G>> {
+        let component = self
+            .components
+            .remove(&id)
+            .ok_or(Error::ComponentNotFound { id })?;
+        self.connections.remove_component(id);
+        for ids in self.groups.values_mut() {
+            ids.retain(|group_id| *group_id != id);
+        }
+        Ok(component)
+    }
+
+    /// Remove a [Connection]
+    ///
+    /// # Error
+    ///
+    /// Error if the [Connection] not exist in this flow
+    pub fn remove_connection(&mut self, connection: &Connection) -> Result<()> {
+        self.connections.remove(connection)
+    }
+
     /// Insert a [Connection]
     ///
     /// # Error
@@ -164,106 +360,1282 @@ where
     /// - Error if the this [Flow] not have a [Component::id] used in [Connection]
     /// - Error if the [Component]'s used in [Connection] not have that Input/Output [Port](crate::ports::Port) defined.
     /// - Error if add a connection create a Loop
+    /// - Error if the Output and Input [Port](crate::ports::Port)'s declare
+    ///   [kinds](crate::ports::Port::kind) that not match, see [PackageKind::accepts](crate::PackageKind::accepts)
     pub fn add_connection(mut self, connection: Connection) -> Result<Self> {
+        self.can_add_connection(&connection)?;
+        self.connections.add(connection)?;
+
+        Ok(self)
+    }
+
+    /// Insert a feedback [Connection] (a back edge), that can close a loop in this [Flow].
+    ///
+    /// The packages sent through a feedback connection are delivered in the next cicle.
+    /// A component only run when all your not optional inputs have packages, so the
+    /// inputs of `connection.to` are usually `#[optional]`, otherwise the packages that
+    /// come back wait for packages in the others inputs. Feedback connections
+    /// are not used to find the [sources](Flow::sources) or [sinks](Flow::sinks) and are
+    /// not included in [Flow::to_spec].
+    ///
+    /// # Error
+    ///
+    /// Same errors returned by [Flow::add_connection], except the Loop error
+    pub fn add_feedback_connection(mut self, connection: Connection) -> Result<Self> {
+        self.check_ports(&connection)?;
+        self.connections.add_feedback(connection)?;
+
+        Ok(self)
+    }
+
+    /// Return the feedback [Connection]'s, see [Flow::add_feedback_connection]
+    pub fn feedback_connections(&self) -> Vec<Connection> {
+        self.connections.feedback_to_vec()
+    }
+
+    /// Check if a [Connection] can be inserted, running all validations of
+    /// [Flow::add_connection] without modify this [Flow]
+    ///
+    /// # Error
+    ///
+    /// Same errors returned by [Flow::add_connection]
+    pub fn can_add_connection(&self, connection: &Connection) -> Result<()> {
+        self.check_ports(connection)?;
+        self.connections.check(connection)
+    }
+
+    /// Check if the components and ports used in the [Connection] exist
+    fn check_ports(&self, connection: &Connection) -> Result<()> {
         if let Some(component) = self.components.get(&connection.from) {
             if !component.outputs.contains(connection.out_port) {
                 return Err(Error::OutPortNotFound {
                     component: connection.from,
                     out_port: connection.out_port,
-                }
-                .into());
+                });
             }
         } else {
             return Err(Error::ComponentNotFound {
                 id: connection.from,
-            }
-            .into());
+            });
         }
 
         if let Some(component) = self.components.get(&connection.to) {
             if !component.inputs.contains(connection.in_port) {
                 return Err(Error::InPortNotFound {
-                    component: connection.from,
+                    component: connection.to,
                     in_port: connection.in_port,
-                }
-                .into());
+                });
             }
         } else {
-            return Err(Error::ComponentNotFound { id: connection.to }.into());
+            return Err(Error::ComponentNotFound { id: connection.to });
         }
 
-        self.connections.add(connection)?;
+        let out_kind = self.components[&connection.from]
+            .outputs
+            .get(connection.out_port)
+            .and_then(|port| port.kind);
+        let in_kind = self.components[&connection.to]
+            .inputs
+            .get(connection.in_port)
+            .and_then(|port| port.kind);
+        if let (Some(out_kind), Some(in_kind)) = (out_kind, in_kind) {
+            if !in_kind.accepts(out_kind) {
+                return Err(Error::PortKindMismatch {
+                    connection: connection.clone(),
+                    out_kind,
+                    in_kind,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return true if insert that [Connection] create a loop in this [Flow]
+    pub fn would_create_loop(&self, connection: &Connection) -> bool {
+        self.connections.would_create_loop(connection)
+    }
+
+    /// Rewrite all [Connection]'s of this flow, each [Connection] is replaced by
+    /// the [Connection]'s returned by `f`.
+    ///
+    /// # Error
+    ///
+    /// Error if any [Connection] returned is invalid, see [Flow::add_connection]
+    ///
+    /// The feedback [Connection]'s are mapped too and stay as feedback connections.
+    pub fn map_connections(
+        mut self,
+        mut f: impl FnMut(Connection) -> Vec<Connection>,
+    ) -> Result<Self> {
+        let connections = std::mem::take(&mut self.connections);
+        for connection in connections.to_vec() {
+            for connection in f(connection) {
+                self = self.add_connection(connection)?;
+            }
+        }
+        for connection in connections.feedback_to_vec() {
+            for connection in f(connection) {
+                self = self.add_feedback_connection(connection)?;
+            }
+        }
+        Ok(self)
+    }
 
+    /// Insert a [Component] in the middle of a [Connection], the [Connection] is removed
+    /// and replaced by a [Connection] from the `connection.from` to the component and other
+    /// from the component to the `connection.to`.
+    ///
+    /// The component is connected by your first [Input](crate::ports::Inputs) and
+    /// [Output](crate::ports::Outputs) ports.
+    ///
+    /// # Error
+    ///
+    /// - Error if the [Connection] not exist in this flow
+    /// - Error if the [Component::id] is already used
+    /// - Error if the [Component] not have a Input or a Output port
+    pub fn insert_between(self, connection: Connection, component: Component<G>) -> Result<Self> {
+        if !self.connections.contains(&connection) {
+            return Err(Error::ConnectionNotFound { connection });
+        }
+
+        let id = component.id;
+        let in_port = match component.inputs.iter().next() {
+            Some(port) => port.port,
+            None => {
+                return Err(Error::InPortNotFound {
+                    component: id,
+                    in_port: 0,
+                })
+            }
+        };
+        let out_port = match component.outputs.iter().next() {
+            Some(port) => port.port,
+            None => {
+                return Err(Error::OutPortNotFound {
+                    component: id,
+                    out_port: 0,
+                })
+            }
+        };
+
+        self.add_component(component)?.map_connections(|c| {
+            if c == connection {
+                vec![
+                    Connection::new(c.from, c.out_port, id, in_port),
+                    Connection::new(id, out_port, c.to, c.in_port),
+                ]
+            } else {
+                vec![c]
+            }
+        })
+    }
+
+    /// Fail the run when a [Component] send a package to a output port that is not connected,
+    /// by default that packages are discarded. Outputs defined as
+    /// [optional](crate::ports::Port::optional) can be unconnected.
+    ///
+    /// That help to find wrong connections, like a connection from a wrong port.
+    pub fn strict_outputs(mut self) -> Self {
+        self.strict_outputs = true;
+        self
+    }
+
+    /// Define that components of type `before` must never be downstream of components
+    /// of type `after`, like a transform that must not receive packages derived from a sink.
+    /// The constraints are only checked by [Flow::validate].
+    ///
+    /// The types are matched by the full path of type or only by the name of type,
+    /// without the module path and generics.
+    pub fn add_type_constraint(mut self, before: &str, after: &str) -> Self {
+        self.type_constraints
+            .push((before.to_owned(), after.to_owned()));
+        self
+    }
+
+    /// Abort the run when a [Component] not finish your [run](crate::component::ComponentSchema::run)
+    /// in the duration, like a component blocked by a network call that hangs.
+    ///
+    /// The timeout is applied to each component, and only work inside a tokio runtime
+    /// with the time driver enabled. Available with the `tokio` feature, not enabled by default.
+    #[cfg(feature = "tokio")]
+    pub fn component_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.component_timeout = Some(timeout);
+        self
+    }
+
+    /// Call the `hook` with each [FlowEvent] emitted while this [Flow] run, that stream
+    /// the progress of run, usefull for progress bars and metrics.
+    ///
+    /// The `hook` is called in the task that run the flow, so it must be fast.
+    pub fn on_event(self, hook: impl Fn(FlowEvent) + Send + Sync + 'static) -> Self {
+        self.instrumentation(EventHook(hook))
+    }
+
+    /// Observe all runs of this [Flow] with a [Instrumentation], calling it again add
+    /// other instrumentation, all of them are called in the order that they were added.
+    /// To observe a single run use [Flow::run_instrumented].
+    pub fn instrumentation(mut self, instrumentation: impl Instrumentation<G> + 'static) -> Self {
+        self.instrumentations.push(Arc::new(instrumentation));
+        self
+    }
+
+    /// Add [Component]'s to a named group, groups only organize the components
+    /// for display and not change the execution of [Flow].
+    ///
+    /// # Error
+    ///
+    /// Error if the this [Flow] not have a [Component] with a id
+    pub fn group(mut self, name: &str, ids: &[Id]) -> Result<Self> {
+        if let Some(id) = ids.iter().find(|id| !self.components.contains_key(id)) {
+            return Err(Error::ComponentNotFound { id: *id });
+        }
+        let group = self.groups.entry(name.to_owned()).or_default();
+        for id in ids {
+            if !group.contains(id) {
+                group.push(*id);
+            }
+        }
         Ok(self)
     }
 
+    /// Return a serializable description of this flow, with all components sorted by id
+    /// and all connections.
+    ///
+    /// The data of components can not be serialized, so a component is described by your
+    /// [type name](crate::component::ComponentSchema::type_name) and the config used to
+    /// create it by a [ComponentRegistry](crate::spec::ComponentRegistry), allowing a
+    /// round trip of a flow built by a registry.
+    pub fn to_spec(&self) -> FlowSpec {
+        let mut components = self
+            .components
+            .values()
+            .map(ComponentSpec::of)
+            .collect::<Vec<_>>();
+        components.sort_by_key(|component| component.id);
+
+        FlowSpec {
+            components,
+            connections: self.connections.to_vec(),
+        }
+    }
+
+    /// Return the groups of [Component]'s, see [Flow::group]
+    pub fn groups(&self) -> &HashMap<String, Vec<Id>> {
+        &self.groups
+    }
+
+    /// Return the [PackageSchema] of a input port of a [Component], or [None] if
+    /// the component or port not exist, or the port not have a schema
+    pub fn in_port_schema(&self, id: Id, port: PortId) -> Option<&PackageSchema> {
+        self.components.get(&id)?.inputs.get(port)?.schema.as_ref()
+    }
+
+    /// Return the [PackageSchema] of a output port of a [Component], or [None] if
+    /// the component or port not exist, or the port not have a schema
+    ///
+    /// An editor can check if a output can be connected to a input with [PackageSchema::accepts]
+    pub fn out_port_schema(&self, id: Id, port: PortId) -> Option<&PackageSchema> {
+        self.components.get(&id)?.outputs.get(port)?.schema.as_ref()
+    }
+
+    /// Return a representation of this flow in the Graphviz DOT language,
+    /// each group is rendered as a cluster subgraph.
+    ///
+    /// Each node is labeled with the component id and [Type](crate::component::Type),
+    /// entry points (components without inputs) are drawn in bold, and each edge is
+    /// labeled with the output port label and the input port label of the [Connection].
+    /// The [feedback connections](Flow::add_feedback_connection) are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+
+        let mut groups = self.groups.iter().collect::<Vec<_>>();
+        groups.sort_by_key(|(name, _)| *name);
+
+        let mut dot = String::from("digraph {\n");
+        for (index, (name, group)) in groups.into_iter().enumerate() {
+            dot += &format!("  subgraph cluster_{index} {{\n    label={name:?};\n");
+            for id in group {
+                dot += &format!("    {};\n", self.dot_node(*id));
+            }
+            dot += "  }\n";
+        }
+        for id in ids {
+            if !self.groups.values().any(|group| group.contains(&id)) {
+                dot += &format!("  {};\n", self.dot_node(id));
+            }
+        }
+        for c in self.connections.to_vec() {
+            let label = self.edge_label(&c);
+            dot += &format!("  {} -> {} [label={label:?}];\n", c.from, c.to);
+        }
+        for c in self.connections.feedback_to_vec() {
+            let label = self.edge_label(&c);
+            dot += &format!(
+                "  {} -> {} [label={label:?}, style=dashed];\n",
+                c.from, c.to
+            );
+        }
+        dot += "}\n";
+        dot
+    }
+
+    /// Return a representation of this flow as a Mermaid `flowchart`, that can be
+    /// embedded in Markdown docs, each group is rendered as a subgraph.
+    ///
+    /// The nodes and edges are labeled as in [Flow::to_dot], the [sources](Flow::sources)
+    /// have the class `source` and the others [sinks](Flow::sinks) have the class `sink`.
+    /// The [feedback connections](Flow::add_feedback_connection) are dotted links.
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    ///
+    /// let flow: Flow<()> = Flow::new();
+    /// assert_eq!(flow.to_mermaid(), "flowchart LR\n");
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+
+        let mut groups = self.groups.iter().collect::<Vec<_>>();
+        groups.sort_by_key(|(name, _)| *name);
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for (index, (name, group)) in groups.into_iter().enumerate() {
+            mermaid += &format!("  subgraph group_{index} [{name:?}]\n");
+            for id in group {
+                mermaid += &format!("    {}\n", self.mermaid_node(*id));
+            }
+            mermaid += "  end\n";
+        }
+        for id in ids.iter() {
+            if !self.groups.values().any(|group| group.contains(id)) {
+                mermaid += &format!("  {}\n", self.mermaid_node(*id));
+            }
+        }
+        for c in self.connections.to_vec() {
+            let label = self.edge_label(&c);
+            mermaid += &format!("  c{} -->|{label:?}| c{}\n", c.from, c.to);
+        }
+        for c in self.connections.feedback_to_vec() {
+            let label = self.edge_label(&c);
+            mermaid += &format!("  c{} -.->|{label:?}| c{}\n", c.from, c.to);
+        }
+
+        let sources = self.sources();
+        let sinks = self
+            .sinks()
+            .into_iter()
+            .filter(|id| !sources.contains(id))
+            .collect::<Vec<_>>();
+        for (class, ids) in [("source", sources), ("sink", sinks)] {
+            if !ids.is_empty() {
+                let ids = ids.iter().map(|id| format!("c{id}")).collect::<Vec<_>>();
+                mermaid += &format!("  class {} {class}\n", ids.join(","));
+            }
+        }
+        if !ids.is_empty() {
+            mermaid += "  classDef source stroke-width:3px\n";
+            mermaid += "  classDef sink stroke-dasharray:5 5\n";
+        }
+        mermaid
+    }
+
+    /// Label of a edge, with the output port label and the input port label of the [Connection]
+    fn edge_label(&self, connection: &Connection) -> String {
+        let label = |ports: Option<&Ports>, port: PortId| {
+            ports
+                .and_then(|ports| ports.get(port))
+                .and_then(|port| port.label)
+                .map(str::to_owned)
+                .unwrap_or_else(|| port.to_string())
+        };
+        let components = &self.components;
+        let out_label = label(
+            components.get(&connection.from).map(|c| &c.outputs),
+            connection.out_port,
+        );
+        let in_label = label(
+            components.get(&connection.to).map(|c| &c.inputs),
+            connection.in_port,
+        );
+        format!("{out_label} -> {in_label}")
+    }
+
+    fn mermaid_node(&self, id: Id) -> String {
+        match self.components.get(&id) {
+            Some(component) => format!("c{id}[\"{id} ({:?})\"]", component.ty),
+            None => format!("c{id}[\"{id}\"]"),
+        }
+    }
+
+    fn type_violations(&self) -> Vec<Error> {
+        let ids_of = |name: &str| {
+            let mut ids = self
+                .components
+                .values()
+                .filter(|component| component.is_type(name))
+                .map(|component| component.id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        let mut violations = Vec::new();
+        for (before, after) in self.type_constraints.iter() {
+            let downstream = ids_of(before);
+            for upstream in ids_of(after) {
+                let descendants = self.connections.descendants(upstream);
+                for id in downstream.iter().filter(|id| descendants.contains(id)) {
+                    violations.push(Error::TypeConstraintViolated {
+                        before: before.clone(),
+                        after: after.clone(),
+                        upstream,
+                        downstream: *id,
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    fn dot_node(&self, id: Id) -> String {
+        let Some(component) = self.components.get(&id) else {
+            return id.to_string();
+        };
+        let label = format!("{id} ({:?})", component.ty);
+        if component.inputs.is_empty() {
+            format!("{id} [label={label:?}, style=bold]")
+        } else {
+            format!("{id} [label={label:?}]")
+        }
+    }
+
+    /// Return the [Component]'s without [Input](crate::ports::Inputs) ports, the entry
+    /// points of the flow, sorted by id
+    pub fn sources(&self) -> Vec<Id> {
+        let mut sources = self
+            .components
+            .values()
+            .filter(|component| component.inputs.is_empty())
+            .map(|component| component.id)
+            .collect::<Vec<_>>();
+        sources.sort();
+        sources
+    }
+
+    /// Return the [Component]'s without [Output](crate::ports::Outputs) ports, or with all
+    /// outputs not connected (or connected only by feedback connections), sorted by id
+    pub fn sinks(&self) -> Vec<Id> {
+        let mut sinks = self
+            .components
+            .values()
+            .filter(|component| {
+                component.outputs.iter().all(|port| {
+                    let from = component.from(port.port);
+                    self.connections.from(from).is_none_or(|to| {
+                        to.iter()
+                            .all(|to| self.connections.is_feedback(&Connection::by(from, *to)))
+                    })
+                })
+            })
+            .map(|component| component.id)
+            .collect::<Vec<_>>();
+        sinks.sort();
+        sinks
+    }
+
+    /// Return the largest number of [Input](crate::ports::Inputs) ports connected
+    /// to a same [Output](crate::ports::Outputs) port, or 0 if there is no [Connection]
+    pub fn max_fan_out(&self) -> usize {
+        self.connections.max_fan_out()
+    }
+
+    /// Return the largest number of [Output](crate::ports::Outputs) ports connected
+    /// to a same [Input](crate::ports::Inputs) port, or 0 if there is no [Connection]
+    pub fn max_fan_in(&self) -> usize {
+        self.connections.max_fan_in()
+    }
+
+    ///
+    /// Check the structure of this [Flow] without run it, returning all problems found:
+    ///
+    /// - [ComponentDisconnected](crate::error::Error::ComponentDisconnected) if a component
+    ///   with ports has no [Connection]
+    /// - [InPortNotConnected](crate::error::Error::InPortNotConnected) if a not optional
+    ///   input port is not connected, so the component never be ready to run
+    /// - [OutPortUnused](crate::error::Error::OutPortUnused) if a not optional output port
+    ///   is not connected, the packages sent to it are discarded. That is only a warning,
+    ///   a [Flow] with unused outputs still run, unless with [Flow::strict_outputs]
+    ///
+    /// - [TypeConstraintViolated](crate::error::Error::TypeConstraintViolated) if a path of
+    ///   connections break a constraint defined by [Flow::add_type_constraint]
+    ///
+    /// The problems of ports are sorted by component id, followed by the violations of
+    /// constraints in the order that they were defined.
+    ///
+    pub fn validate(&self) -> std::result::Result<(), Vec<Error>> {
+        let mut connections = self.connections.to_vec();
+        connections.extend(self.connections.feedback_to_vec());
+
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+
+        let mut errors = Vec::new();
+        for id in ids {
+            let component = &self.components[&id];
+            if component.inputs.is_empty() && component.outputs.is_empty() {
+                continue;
+            }
+            let connected = connections.iter().any(|c| c.from == id || c.to == id);
+            if !connected {
+                errors.push(Error::ComponentDisconnected { id });
+                continue;
+            }
+            for port in component.inputs.iter().filter(|port| !port.optional) {
+                if !connections
+                    .iter()
+                    .any(|c| c.to == id && c.in_port == port.port)
+                {
+                    errors.push(Error::InPortNotConnected {
+                        component: id,
+                        in_port: port.port,
+                    });
+                }
+            }
+            for port in component.outputs.iter().filter(|port| !port.optional) {
+                if self.connections.from(Point::new(id, port.port)).is_none() {
+                    errors.push(Error::OutPortUnused {
+                        component: id,
+                        out_port: port.port,
+                    });
+                }
+            }
+        }
+
+        errors.extend(self.type_violations());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     ///
     /// Run this Flow
     ///
     /// # Error
     ///
-    /// Error if a component return a Error when [run](crate::component::ComponentSchema::run)
+    /// - Error if a component return a Error when [run](crate::component::ComponentSchema::run)
+    /// - Error [Deadlock](crate::error::Error::Deadlock) if no component is ready to run
+    ///   but still have packages waiting to be received
     ///
     /// # Panics
     ///
     /// Panic if a component panic when [run](crate::component::ComponentSchema::run)
     ///
     pub async fn run(&self, global: G) -> RunResult<G> {
-        let global_arc = Arc::new(Global::from_data(global));
+        self.run_instrumented(global, &NoOp).await
+    }
 
-        let mut contexts = Ctxs::new(&self.components, &self.connections, &global_arc);
+    ///
+    /// Run this Flow observed by a [Instrumentation], with the instrumentations of
+    /// [Flow::instrumentation]. [Flow::run] is this method with [NoOp], so a run only pay
+    /// for the instrumentation that it use.
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    /// use rs_flow::{PackageTracer, RunStats};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let flow = Flow::<()>::new();
+    ///
+    /// let stats = RunStats::default();
+    /// flow.run_instrumented((), &stats).await?;
+    ///
+    /// let tracer = PackageTracer::new(Package::integer(1));
+    /// flow.run_instrumented((), &tracer).await?;
+    /// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+    /// # });
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Error returned by a component, if the instrumentation not
+    ///   [handle errors](Instrumentation::handle_errors) or choose [DebugAction::Abort]
+    /// - Same errors returned by [Flow::run]
+    ///
+    pub async fn run_instrumented(
+        &self,
+        global: G,
+        instrumentation: &dyn Instrumentation<G>,
+    ) -> RunResult<G> {
+        let mut execution = Execution::new(self, global, u32::MAX, instrumentation);
+        self.execute(&mut execution).await?;
+        Ok(execution.finish())
+    }
 
-        let mut ready_components = contexts.entry_points();
-        let mut first = true;
+    ///
+    /// Run this Flow in the current thread, without a async runtime, like [Flow::run].
+    ///
+    /// Usefull for flows of components that never wait I/O, like a data transformation.
+    /// A component that await a real I/O block the current thread until it finish,
+    /// and components that need a tokio runtime (like a tokio timer) not work.
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    ///
+    /// struct Hello;
+    ///
+    /// #[async_trait]
+    /// impl ComponentSchema for Hello {
+    ///     type Inputs = ();
+    ///     type Outputs = ();
+    ///     type Global = String;
+    ///
+    ///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+    ///         ctx.with_mut_global(|global| global.push_str("hello"))?;
+    ///         Ok(Next::Continue)
+    ///     }
+    /// }
+    ///
+    /// let flow = Flow::new().add_component(Component::new(1, Hello)).unwrap();
+    /// assert_eq!(flow.run_blocking(String::new()).unwrap(), "hello");
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// Same errors returned by [Flow::run]
+    ///
+    /// # Panics
+    ///
+    /// - Panic if a component panic when [run](crate::component::ComponentSchema::run)
+    #[cfg_attr(
+        feature = "tokio",
+        doc = "- Panic if a [component timeout](Flow::component_timeout) is defined, becaurse it need a tokio runtime"
+    )]
+    ///
+    pub fn run_blocking(&self, global: G) -> RunResult<G> {
+        futures::executor::block_on(self.run(global))
+    }
+
+    ///
+    /// Run this Flow for at most `max_cicles` cicles
+    ///
+    /// # Error
+    ///
+    /// - Error if a component return a Error when [run](crate::component::ComponentSchema::run)
+    /// - Error [CycleLimitExceeded](crate::error::Error::CycleLimitExceeded) if still have
+    ///   components ready to run after `max_cicles` cicles
+    /// - Error [Deadlock](crate::error::Error::Deadlock) if no component is ready to run
+    ///   but still have packages waiting to be received
+    ///
+    /// # Panics
+    ///
+    /// Panic if a component panic when [run](crate::component::ComponentSchema::run)
+    ///
+    pub async fn run_with_limit(&self, global: G, max_cicles: u32) -> RunResult<G> {
+        let mut execution = Execution::new(self, global, max_cicles, &NoOp);
+        self.execute(&mut execution).await?;
+        Ok(execution.finish())
+    }
+
+    ///
+    /// Run this Flow with packages already waiting in input ports, like arguments of a function.
+    /// The components that are ready with these packages run in the first cicle, together
+    /// with the components without inputs.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use rs_flow::connection::Point;
+    /// use rs_flow::prelude::*;
+    ///
+    /// #[derive(Inputs)]
+    /// struct In;
+    ///
+    /// struct Sum;
+    ///
+    /// #[async_trait]
+    /// impl ComponentSchema for Sum {
+    ///     type Inputs = In;
+    ///     type Outputs = ();
+    ///     type Global = f64;
+    ///
+    ///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+    ///         while let Some(package) = ctx.receive(In) {
+    ///             let number = package.get_number()?;
+    ///             ctx.with_mut_global(|sum| *sum += number)?;
+    ///         }
+    ///         Ok(Next::Continue)
+    ///     }
+    /// }
+    ///
+    /// # tokio_test::block_on(async {
+    /// let flow = Flow::new().add_component(Component::new(1, Sum))?;
+    /// let inputs = HashMap::from([(Point::new(1, 0), vec![1.into(), 2.into()])]);
+    ///
+    /// let sum = flow.run_with_inputs(0.0, inputs).await?;
+    /// assert_eq!(sum, 3.0);
+    /// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+    /// # });
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Error [ComponentNotFound](crate::error::Error::ComponentNotFound) if a [Point] is
+    ///   of a component that not exist
+    /// - Error [InPortNotFound](crate::error::Error::InPortNotFound) if a [Point] is of a
+    ///   input port that not exist in the component
+    /// - Same errors returned by [Flow::run]
+    ///
+    pub async fn run_with_inputs(
+        &self,
+        global: G,
+        inputs: HashMap<Point, Vec<Package>>,
+    ) -> RunResult<G> {
+        let mut execution = Execution::new(self, global, u32::MAX, &NoOp);
+        execution.inject(inputs)?;
+        self.execute(&mut execution).await?;
+        Ok(execution.finish())
+    }
+
+    /// Run this Flow like [Flow::run_with_inputs] and also return the packages sent
+    /// by each output port, in the order that they were sent
+    pub(crate) async fn run_recording(
+        &self,
+        global: G,
+        inputs: HashMap<Point, Vec<Package>>,
+        max_cicles: u32,
+    ) -> RunResult<(G, HashMap<Point, Vec<Package>>)> {
+        let recording = Recording::default();
+        let mut execution = Execution::new(self, global, max_cicles, &recording);
+        execution.inject(inputs)?;
+        self.execute(&mut execution).await?;
 
-        let mut cicle = 1;
-        while !ready_components.is_empty() {
-            let mut futures = Vec::with_capacity(ready_components.len());
+        let global = execution.finish();
+        Ok((global, recording.into_outputs()))
+    }
 
-            for id in ready_components {
-                let mut ctx = contexts
+    ///
+    /// Run again only the components that changed since a previous run and their
+    /// descendants, reusing the packages sent by the other components in the `previous`
+    /// snapshot. Return the global data and a snapshot of this run, to be used in the
+    /// next incremental run. A component not recorded in `previous` is always run,
+    /// so the first run can use a [FlowSnapshot::default].
+    ///
+    /// The packages sent by a component in the snapshot are all received in the first
+    /// cicle, so the result is only equal to a full run if:
+    /// - The components are deterministic, given the same packages and config always
+    ///   send the same packages
+    /// - The components not depend on the cicle that a package is received
+    /// - The connections not changed since the previous run
+    /// - The changes that the not affected components made in global data are already
+    ///   in `global`, becaurse they not run again
+    ///
+    /// # Error
+    ///
+    /// - Error [ComponentNotFound](crate::error::Error::ComponentNotFound) if a id in
+    ///   `changed` is of a component that not exist
+    /// - Same errors returned by [Flow::run]
+    ///
+    pub async fn run_incremental(
+        &self,
+        previous: &FlowSnapshot,
+        changed: &[Id],
+        global: G,
+    ) -> RunResult<(G, FlowSnapshot)> {
+        let mut affected = HashSet::new();
+        for id in changed {
+            if !self.components.contains_key(id) {
+                return Err(Box::new(Error::ComponentNotFound { id: *id }));
+            }
+            affected.insert(*id);
+        }
+        for id in self.components.keys() {
+            if !previous.contains(*id) {
+                affected.insert(*id);
+            }
+        }
+        for id in affected.clone() {
+            affected.extend(self.connections.descendants(id));
+        }
+
+        let recording = Recording::default();
+        let mut execution = Execution::new(self, global, u32::MAX, &recording);
+        recording.extend(execution.replay(previous, &affected)?);
+        self.execute(&mut execution).await?;
+
+        let global = execution.finish();
+        let components = self.components.keys().copied().collect();
+        Ok((
+            global,
+            FlowSnapshot::new(components, recording.into_outputs()),
+        ))
+    }
+
+    ///
+    /// Run this Flow requiring that each cicle finish in the `per_cycle` duration, the
+    /// deadline bound all components of a cicle that run concurrently, unlike the
+    /// [component timeout](Flow::component_timeout) that bound each component.
+    ///
+    /// Only work inside a tokio runtime with the time driver enabled.
+    ///
+    /// # Error
+    ///
+    /// - Error [CycleDeadlineExceeded](crate::error::Error::CycleDeadlineExceeded) if the
+    ///   components of a cicle not finish in the deadline, the components still running are dropped
+    /// - Same errors returned by [Flow::run]
+    ///
+    #[cfg(feature = "tokio")]
+    pub async fn run_with_cycle_deadline(
+        &self,
+        global: G,
+        per_cycle: std::time::Duration,
+    ) -> RunResult<G> {
+        let mut execution = Execution::new(self, global, u32::MAX, &NoOp);
+        execution.cycle_deadline = Some(per_cycle);
+        self.execute(&mut execution).await?;
+        Ok(execution.finish())
+    }
+    ///
+    /// Run this Flow until finish or the `token` be cancelled.
+    ///
+    /// The token is checked between cicles and while the components of a cicle run,
+    /// when cancelled in middle of a cicle the components still running are dropped,
+    /// and the changes that they already made in global data are kept.
+    ///
+    /// # Error
+    ///
+    /// - Error [Cancelled](crate::error::Error::Cancelled) if the token is cancelled before the
+    ///   flow finish, the global data is dropped only after all components are dropped
+    /// - Same errors returned by [Flow::run]
+    ///
+    #[cfg(feature = "tokio")]
+    pub async fn run_cancellable(
+        &self,
+        global: G,
+        token: tokio_util::sync::CancellationToken,
+    ) -> RunResult<G> {
+        let mut execution = Execution::new(self, global, u32::MAX, &NoOp);
+        execution.token = Some(token.clone());
+
+        {
+            // the running components are dropped with this future before finish
+            let run = std::pin::pin!(self.execute(&mut execution));
+            let cancelled = std::pin::pin!(token.cancelled());
+            match futures::future::select(run, cancelled).await {
+                futures::future::Either::Left((result, _)) => result?,
+                futures::future::Either::Right(_) => return Err(Box::new(Error::Cancelled)),
+            }
+        }
+
+        Ok(execution.finish())
+    }
+
+    /// Run the cicles of a execution until finish
+    async fn execute(&self, execution: &mut Execution<'_, G>) -> RunResult<()> {
+        let handle_errors = execution.instrumentation.handle_errors();
+        while !execution.is_finished() {
+            #[cfg(feature = "tokio")]
+            let (cicle, deadline) = (execution.cicle, execution.cycle_deadline);
+            let futures =
+                execution
+                    .start_cicle()
+                    .into_iter()
+                    .map(|(component, mut ctx)| async move {
+                        // packages waiting before the run, restored in a retry
+                        let inputs = handle_errors.then(|| ctx.receive.clone());
+                        let result = self.run_component(component, &mut ctx).await;
+                        (component, ctx, inputs, result)
+                    });
+
+            // results in the same order of the ready components
+            let pending = match handle_errors {
+                true => execution.contexts.pending(),
+                false => Vec::new(),
+            };
+            let instrumentation = &execution.instrumentation;
+            let results = async move {
+                if !handle_errors {
+                    return futures::future::try_join_all(futures.map(|run| async {
+                        let (_, ctx, _, result) = run.await;
+                        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((ctx, result?))
+                    }))
+                    .await;
+                }
+                // the errors are handled after all components finish, in order of id
+                let mut results = Vec::new();
+                for (component, ctx, inputs, result) in futures::future::join_all(futures).await {
+                    results.push(
+                        self.recover(instrumentation, &pending, component, ctx, inputs, result)
+                            .await?,
+                    );
+                }
+                Ok(results)
+            };
+            #[cfg(feature = "tokio")]
+            let results = match deadline {
+                Some(deadline) => tokio::time::timeout(deadline, results)
+                    .await
+                    .map_err(|_| Error::CycleDeadlineExceeded { cicle, deadline })??,
+                None => results.await?,
+            };
+            #[cfg(not(feature = "tokio"))]
+            let results = results.await?;
+            if execution.end_cicle(results)? == Next::Break {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ask the instrumentation what to do with the error of a component, running it
+    /// again with the `inputs` that it had before the error while a retry is chosen
+    async fn recover(
+        &self,
+        instrumentation: &Instruments<'_, G>,
+        pending: &[(Id, PortId, usize)],
+        component: &Component<G>,
+        mut ctx: Ctx<G>,
+        inputs: Option<HashMap<PortId, ReceiveQueue>>,
+        mut result: RunResult<Next>,
+    ) -> RunResult<(Ctx<G>, Next)> {
+        let mut retries = 0;
+        let mut elapsed = ctx.elapsed;
+        let next = loop {
+            let error = match result {
+                Ok(next) => break next,
+                Err(error) => error,
+            };
+            let debug = DebugContext {
+                component: component.id,
+                cicle: ctx.cicle,
+                retries,
+                inputs: ctx.waiting(),
+                pending: pending.to_vec(),
+            };
+            ctx.discard();
+            match instrumentation.component_failed(error.as_ref(), &debug) {
+                DebugAction::Abort => return Err(error),
+                DebugAction::Skip => {
+                    ctx.consumed = true;
+                    break Next::Continue;
+                }
+                DebugAction::Retry => {
+                    ctx.receive = inputs.clone().unwrap_or_default();
+                    ctx.consumed = false;
+                    ctx.received = 0;
+                    retries += 1;
+                    result = self.run_component(component, &mut ctx).await;
+                    elapsed += ctx.elapsed;
+                }
+            }
+        };
+        ctx.elapsed = elapsed;
+        Ok((ctx, next))
+    }
+
+    /// Run a component once, catching your panics and applying the timeout if defined
+    async fn run_component(&self, component: &Component<G>, ctx: &mut Ctx<G>) -> RunResult<Next> {
+        let start = Instant::now();
+        let run = async {
+            if component.catch_panics {
+                AssertUnwindSafe(component.data.run(ctx))
+                    .catch_unwind()
+                    .await
+                    .unwrap_or_else(|payload| {
+                        Err(Box::new(Error::ComponentPanicked {
+                            id: component.id,
+                            message: panic_message(payload),
+                        }))
+                    })
+            } else {
+                component.data.run(ctx).await
+            }
+        };
+
+        #[cfg(feature = "tokio")]
+        let result = match self.component_timeout {
+            Some(elapsed) => tokio::time::timeout(elapsed, run)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(Box::new(Error::ComponentTimeout {
+                        component: component.id,
+                        elapsed,
+                    }))
+                }),
+            None => run.await,
+        };
+        #[cfg(not(feature = "tokio"))]
+        let result = run.await;
+
+        ctx.elapsed = start.elapsed();
+        result
+    }
+}
+
+/// State of a [Flow] run, shared by the async and sync runners
+pub(crate) struct Execution<'a, G> {
+    flow: &'a Flow<G>,
+    global: Arc<Global<G>>,
+    contexts: Ctxs<G>,
+    ready_components: Vec<Id>,
+    first: bool,
+    cicle: u32,
+    max_cicles: u32,
+    #[cfg(feature = "tokio")]
+    token: Option<tokio_util::sync::CancellationToken>,
+    #[cfg(feature = "tokio")]
+    cycle_deadline: Option<std::time::Duration>,
+    /// Instrumentations of the flow and of this run
+    instrumentation: Instruments<'a, G>,
+}
+
+impl<'a, G> Execution<'a, G>
+where
+    G: Send + Sync + 'static,
+{
+    pub(crate) fn new(
+        flow: &'a Flow<G>,
+        global: G,
+        max_cicles: u32,
+        instrumentation: &'a dyn Instrumentation<G>,
+    ) -> Self {
+        let global = Arc::new(Global::from_data(global));
+        let mut contexts = Ctxs::new(&flow.components, &flow.connections, &global);
+        let ready_components = contexts.entry_points();
+
+        let mut instruments = flow
+            .instrumentations
+            .iter()
+            .map(|instrumentation| instrumentation.as_ref() as &dyn Instrumentation<G>)
+            .collect::<Vec<_>>();
+        instruments.push(instrumentation);
+        let instrumentation = Instruments(instruments);
+        if let Some(seed) = instrumentation.traced_seed() {
+            contexts.trace(seed);
+        }
+
+        Self {
+            flow,
+            global,
+            contexts,
+            ready_components,
+            first: true,
+            cicle: 0,
+            max_cicles,
+            #[cfg(feature = "tokio")]
+            token: None,
+            #[cfg(feature = "tokio")]
+            cycle_deadline: None,
+            instrumentation,
+        }
+    }
+
+    /// Push packages in input ports before the first cicle, the components
+    /// that become ready run in the first cicle with the entry points
+    pub(crate) fn inject(&mut self, inputs: HashMap<Point, Vec<Package>>) -> Result<()> {
+        for (point, packages) in inputs {
+            self.contexts.inject(point.id(), point, packages)?;
+        }
+        for id in self.contexts.ready_components(&self.flow.connections) {
+            if !self.ready_components.contains(&id) {
+                self.ready_components.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run only the `affected` components, the others are retired and the packages
+    /// that they sent in the `snapshot` are pushed in the input ports of the affected
+    /// components. Return the replayed packages, by the output port that sent them.
+    pub(crate) fn replay(
+        &mut self,
+        snapshot: &FlowSnapshot,
+        affected: &HashSet<Id>,
+    ) -> Result<HashMap<Point, Vec<Package>>> {
+        for id in self.flow.components.keys() {
+            if !affected.contains(id) {
+                self.contexts.retire(*id);
+            }
+        }
+
+        let mut replayed = HashMap::new();
+        for (from, packages) in snapshot.iter() {
+            if affected.contains(&from.id()) || !self.flow.components.contains_key(&from.id()) {
+                continue;
+            }
+            for to in self.flow.connections.from(*from).into_iter().flatten() {
+                if affected.contains(&to.id()) {
+                    self.contexts.inject(from.id(), *to, packages.clone())?;
+                }
+            }
+            replayed.insert(*from, packages.clone());
+        }
+        self.ready_components.retain(|id| affected.contains(id));
+        for id in self.contexts.ready_components(&self.flow.connections) {
+            if !self.ready_components.contains(&id) {
+                self.ready_components.push(id);
+            }
+        }
+        Ok(replayed)
+    }
+
+    /// Return if no component is ready and no package is held to the next cicle,
+    /// a cicle without ready components only deliver the held packages
+    pub(crate) fn is_finished(&self) -> bool {
+        self.ready_components.is_empty() && !self.contexts.has_held()
+    }
+
+    /// Borrow the contexts of the components ready to run in this cicle, sorted by id
+    pub(crate) fn start_cicle(&mut self) -> Vec<(&'a Component<G>, Ctx<G>)> {
+        let flow = self.flow;
+        // the components run and give back results in order of id
+        self.ready_components.sort();
+        self.instrumentation
+            .cycle_started(self.cicle, &self.ready_components);
+        std::mem::take(&mut self.ready_components)
+            .into_iter()
+            .map(|id| {
+                let mut ctx = self
+                    .contexts
                     .borrow(id)
                     .expect("Ready operators never return ids that not exist");
 
                 ctx.consumed = false;
-                ctx.cicle = cicle;
+                ctx.traced = false;
+                ctx.received = 0;
+                ctx.sent = 0;
+                ctx.cicle = self.cicle;
 
-                let component = self
+                let component = flow
                     .components
                     .get(&id)
                     .expect("Ready operators never return ids that not exist");
 
-                futures.push(
-                    async move { component.data.run(&mut ctx).await.map(|next| (ctx, next)) },
-                );
-            }
+                (component, ctx)
+            })
+            .collect()
+    }
 
-            let results = futures::future::try_join_all(futures).await?;
-            if results.iter().any(|(_, next)| next == &Next::Break) {
-                break;
+    /// Give back the contexts of the components that run, and search the components
+    /// ready to run in the next cicle.
+    ///
+    /// Return [Next::Break] if any component returned it
+    pub(crate) fn end_cicle(&mut self, mut results: Vec<(Ctx<G>, Next)>) -> RunResult<Next> {
+        #[cfg(feature = "tokio")]
+        if self
+            .token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(Box::new(Error::Cancelled));
+        }
+
+        for (ctx, next) in results.iter_mut() {
+            ctx.apply_deltas()?;
+            self.instrumentation.component_run(&ComponentRun {
+                id: ctx.id,
+                cycle: self.cicle,
+                elapsed: ctx.elapsed,
+                next: *next,
+                sent: ctx.sent,
+                received: ctx.received,
+            });
+        }
+        if results.iter().any(|(_, next)| next == &Next::Break) {
+            self.instrumentation.cycle_finished(self.cicle);
+            return Ok(Next::Break);
+        }
+
+        let mut retry = Vec::new();
+        for (ctx, next) in results {
+            if next == Next::Retry {
+                retry.push(ctx.id);
+            } else if !ctx.consumed
+                && !ctx.allow_no_consume
+                && !self.first
+                && !ctx.receive.is_empty()
+            {
+                // entry points not have inputs to consume, even when retried
+                return Err(Box::new(Error::AnyPackageConsumed { component: ctx.id }));
+            }
+            if self.flow.strict_outputs {
+                self.check_outputs(&ctx)?;
             }
+            self.contexts.give_back(ctx);
+        }
 
-            for (ctx, _) in results {
-                if !ctx.consumed && !first {
-                    // entry points not have inputs to consume
-                    return Err(Box::new(Error::AnyPackageConsumed { component: ctx.id }));
-                }
-                contexts.give_back(ctx);
+        self.contexts.refresh_queues(&self.instrumentation)?;
+        self.instrumentation.queues_refreshed(self.cicle);
+        self.instrumentation.cycle_finished(self.cicle);
+
+        self.ready_components = self.contexts.ready_components(&self.flow.connections);
+        for id in retry {
+            if !self.ready_components.contains(&id) && !self.contexts.is_retired(id) {
+                self.ready_components.push(id);
             }
+        }
+        if self.is_finished() {
+            let pending = self.contexts.pending();
+            if !pending.is_empty() {
+                return Err(Box::new(Error::Deadlock { pending }));
+            }
+        }
+        if !self.is_finished() && self.cicle + 1 >= self.max_cicles {
+            return Err(Box::new(Error::CycleLimitExceeded {
+                cicles: self.max_cicles,
+            }));
+        }
+
+        self.first = false;
+        self.cicle += 1;
 
-            contexts.refresh_queues();
+        Ok(Next::Continue)
+    }
 
-            ready_components = contexts.ready_components(&self.connections);
+    /// Check that each package sent is to a connected output or a optional output
+    fn check_outputs(&self, ctx: &Ctx<G>) -> Result<()> {
+        let component = &self.flow.components[&ctx.id];
+        for (port, queue) in ctx.send.iter() {
+            let connected = self
+                .flow
+                .connections
+                .from(Point::new(ctx.id, *port))
+                .is_some_and(|to| !to.is_empty());
+            let optional = component.outputs.get(*port).is_some_and(|p| p.optional);
 
-            first = false;
-            cicle += 1;
+            if !queue.is_empty() && !connected && !optional {
+                return Err(Error::OutPortNotConnected {
+                    component: ctx.id,
+                    out_port: *port,
+                });
+            }
         }
+        Ok(())
+    }
 
-        drop(contexts);
+    /// Finish the run and return the global data
+    pub(crate) fn finish(self) -> G {
+        drop(self.contexts);
 
-        let global = Arc::try_unwrap(global_arc)
+        let global = Arc::try_unwrap(self.global)
             .expect("Global no have multiples references, becaurse contexts already drop")
             .take();
-        Ok(global)
+        self.instrumentation.flow_finished(&global);
+        global
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic payload".to_owned()
     }
 }
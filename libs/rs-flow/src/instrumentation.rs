@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::component::{Id, Next};
+use crate::connection::Point;
+use crate::debug::{DebugAction, DebugContext};
+use crate::package::Package;
+
+///
+/// Extension point to observe a [Flow](crate::flow::Flow) run, used for a single run with
+/// [Flow::run_instrumented](crate::flow::Flow::run_instrumented) or for all runs of a flow
+/// with [Flow::instrumentation](crate::flow::Flow::instrumentation).
+///
+/// All methods do nothing by default, so a implementation only override the methods
+/// that it need. [Flow::run](crate::flow::Flow::run) use [NoOp], that cost nothing.
+/// The crate implement the observers of a run with this trait: [RunStats](crate::RunStats),
+/// [PackageTracer](crate::PackageTracer), [Debugger](crate::Debugger),
+/// [Flow::on_event](crate::flow::Flow::on_event) and a `GlobalDump` with the `json` feature.
+///
+/// The methods are called in the task that run the flow, so they must be fast:
+///
+/// - [cycle_started](Instrumentation::cycle_started): a cicle started, with the components ready to run
+/// - [component_run](Instrumentation::component_run): a component finished your run in the cicle
+/// - [component_failed](Instrumentation::component_failed): a component returned a error, only
+///   called if [handle_errors](Instrumentation::handle_errors) return true
+/// - [package_sent](Instrumentation::package_sent): a package was sent by a output port
+/// - [packages_moved](Instrumentation::packages_moved): the packages sent by a output port
+///   were delivered to a connected input port, called once for each connection
+/// - [package_traced](Instrumentation::package_traced): a package derived from the
+///   [traced_seed](Instrumentation::traced_seed) crossed a connection
+/// - [queues_refreshed](Instrumentation::queues_refreshed): the packages sent in the cicle
+///   were delivered
+/// - [cycle_finished](Instrumentation::cycle_finished): a cicle finished, after the packages delivered
+/// - [flow_finished](Instrumentation::flow_finished): the flow finished without errors
+///
+/// ```
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use rs_flow::Instrumentation;
+///
+/// #[derive(Default)]
+/// struct CycleCounter(AtomicU32);
+///
+/// impl<G> Instrumentation<G> for CycleCounter {
+///     fn cycle_finished(&self, _cycle: u32) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+///
+pub trait Instrumentation<G>: Send + Sync {
+    /// Called when a cicle start, with the components ready to run sorted by id
+    #[inline]
+    fn cycle_started(&self, _cycle: u32, _ready: &[Id]) {}
+
+    /// Called once for each component that run in a cicle, in order of id,
+    /// after all components of the cicle finish
+    #[inline]
+    fn component_run(&self, _run: &ComponentRun) {}
+
+    /// Return if this instrumentation choose what to do with the errors of components
+    /// in [component_failed](Instrumentation::component_failed), instead of stop the flow.
+    /// When true the packages waiting for each component are copied before it run,
+    /// to be restored in a [DebugAction::Retry].
+    #[inline]
+    fn handle_errors(&self) -> bool {
+        false
+    }
+
+    /// Called when a component return a error, with the state of the flow, to choose a
+    /// [DebugAction] that abort the flow, skip the cicle of the component or retry it
+    #[inline]
+    fn component_failed(
+        &self,
+        _error: &(dyn std::error::Error + Send + Sync),
+        _debug: &DebugContext,
+    ) -> DebugAction {
+        DebugAction::Abort
+    }
+
+    /// Called for each package sent by the output port `from`, in the order that they were sent
+    #[inline]
+    fn package_sent(&self, _from: Point, _package: &Package) {}
+
+    /// Called when `count` packages sent by `from` are delivered to the input port `to`
+    #[inline]
+    fn packages_moved(&self, _from: Point, _to: Point, _count: usize) {}
+
+    /// Return the package to trace, the first package sent equal to it and all packages
+    /// derived from it are reported in [package_traced](Instrumentation::package_traced)
+    #[inline]
+    fn traced_seed(&self) -> Option<Package> {
+        None
+    }
+
+    /// Called when a traced package sent in `cycle` by `from` is delivered to `to`
+    #[inline]
+    fn package_traced(&self, _cycle: u32, _from: Point, _to: Point) {}
+
+    /// Called when the packages sent in a cicle were delivered to the receive queues,
+    /// not called when a component return [Next::Break]
+    #[inline]
+    fn queues_refreshed(&self, _cycle: u32) {}
+
+    /// Called when a cicle finish, also when a component return [Next::Break]
+    #[inline]
+    fn cycle_finished(&self, _cycle: u32) {}
+
+    /// Called when the flow finish without errors, with the global data
+    #[inline]
+    fn flow_finished(&self, _global: &G) {}
+}
+
+/// Run of a component in a cicle, see [Instrumentation::component_run]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentRun {
+    /// Component that run
+    pub id: Id,
+    /// Cicle that the component run
+    pub cycle: u32,
+    /// Duration of the run, the sum of all runs if the component was retried
+    pub elapsed: Duration,
+    /// Returned by the component, [Next::Continue] if the error was skipped
+    pub next: Next,
+    /// Number of packages sent, each port of a broadcast count as a package sent
+    pub sent: usize,
+    /// Number of packages received
+    pub received: usize,
+}
+
+/// [Instrumentation] that do nothing, used by [Flow::run](crate::flow::Flow::run)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoOp;
+
+impl<G> Instrumentation<G> for NoOp {}
+
+/// All instrumentations of a run, each call is forwarded to all of them in order
+pub(crate) struct Instruments<'a, G>(pub(crate) Vec<&'a dyn Instrumentation<G>>);
+
+impl<G> Instrumentation<G> for Instruments<'_, G> {
+    fn cycle_started(&self, cycle: u32, ready: &[Id]) {
+        self.0.iter().for_each(|i| i.cycle_started(cycle, ready));
+    }
+
+    fn component_run(&self, run: &ComponentRun) {
+        self.0.iter().for_each(|i| i.component_run(run));
+    }
+
+    fn handle_errors(&self) -> bool {
+        self.0.iter().any(|i| i.handle_errors())
+    }
+
+    /// The action is chosen by the first instrumentation that handle errors
+    fn component_failed(
+        &self,
+        error: &(dyn std::error::Error + Send + Sync),
+        debug: &DebugContext,
+    ) -> DebugAction {
+        self.0
+            .iter()
+            .find(|i| i.handle_errors())
+            .map_or(DebugAction::Abort, |i| i.component_failed(error, debug))
+    }
+
+    fn package_sent(&self, from: Point, package: &Package) {
+        self.0.iter().for_each(|i| i.package_sent(from, package));
+    }
+
+    fn packages_moved(&self, from: Point, to: Point, count: usize) {
+        self.0
+            .iter()
+            .for_each(|i| i.packages_moved(from, to, count));
+    }
+
+    /// Only the seed of the first instrumentation that trace a package is traced
+    fn traced_seed(&self) -> Option<Package> {
+        self.0.iter().find_map(|i| i.traced_seed())
+    }
+
+    fn package_traced(&self, cycle: u32, from: Point, to: Point) {
+        self.0
+            .iter()
+            .for_each(|i| i.package_traced(cycle, from, to));
+    }
+
+    fn queues_refreshed(&self, cycle: u32) {
+        self.0.iter().for_each(|i| i.queues_refreshed(cycle));
+    }
+
+    fn cycle_finished(&self, cycle: u32) {
+        self.0.iter().for_each(|i| i.cycle_finished(cycle));
+    }
+
+    fn flow_finished(&self, global: &G) {
+        self.0.iter().for_each(|i| i.flow_finished(global));
+    }
+}
+
+/// Record all packages sent by each output port, in the order that they were sent
+#[derive(Default)]
+pub(crate) struct Recording(Mutex<HashMap<Point, Vec<Package>>>);
+
+impl Recording {
+    /// Add packages as sent before the run, like the packages replayed from a snapshot
+    pub(crate) fn extend(&self, outputs: HashMap<Point, Vec<Package>>) {
+        let mut recording = self.0.lock().unwrap();
+        for (from, packages) in outputs {
+            recording.entry(from).or_default().extend(packages);
+        }
+    }
+
+    pub(crate) fn into_outputs(self) -> HashMap<Point, Vec<Package>> {
+        self.0.into_inner().unwrap()
+    }
+}
+
+impl<G> Instrumentation<G> for Recording {
+    fn package_sent(&self, from: Point, package: &Package) {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(from)
+            .or_default()
+            .push(package.clone());
+    }
+}
+
+///
+/// [Instrumentation] that dump the global data serialized as json when the flow finish,
+/// to inspect the state shared by the [Component](crate::component::Component)'s after the run.
+///
+/// If the global is serialized to a json object each field of that object is a entry
+/// of the dump, otherwise the dump has a single entry named by the type of global.
+///
+/// ```
+/// use rs_flow::prelude::*;
+/// use rs_flow::GlobalDump;
+///
+/// # tokio_test::block_on(async {
+/// let dump = GlobalDump::default();
+/// let global = Flow::new().run_instrumented(vec![1, 2], &dump).await?;
+///
+/// assert_eq!(global, vec![1, 2]);
+/// assert_eq!(dump.into_dump()?["alloc::vec::Vec<i32>"], serde_json::json!([1, 2]));
+/// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+/// # });
+/// ```
+///
+#[cfg(feature = "json")]
+#[derive(Debug, Default)]
+pub struct GlobalDump(Mutex<Option<serde_json::Result<HashMap<String, serde_json::Value>>>>);
+
+#[cfg(feature = "json")]
+impl GlobalDump {
+    /// Return the dump, empty if the flow not finished
+    ///
+    /// # Error
+    ///
+    /// Error if the global could not be serialized
+    pub fn into_dump(self) -> serde_json::Result<HashMap<String, serde_json::Value>> {
+        self.0
+            .into_inner()
+            .unwrap()
+            .unwrap_or_else(|| Ok(HashMap::new()))
+    }
+}
+
+#[cfg(feature = "json")]
+impl<G: serde::Serialize> Instrumentation<G> for GlobalDump {
+    fn flow_finished(&self, global: &G) {
+        let dump = serde_json::to_value(global).map(|value| match value {
+            serde_json::Value::Object(fields) => fields.into_iter().collect(),
+            value => HashMap::from([(std::any::type_name::<G>().to_owned(), value)]),
+        });
+        *self.0.lock().unwrap() = Some(dump);
+    }
+}
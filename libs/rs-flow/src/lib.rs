@@ -1,25 +1,55 @@
+extern crate self as rs_flow;
+
 mod flow;
 pub use flow::Flow;
 
+mod sync_flow;
+pub use sync_flow::SyncFlow;
+
+mod event;
+pub use event::FlowEvent;
+
+mod instrumentation;
+#[cfg(feature = "json")]
+pub use instrumentation::GlobalDump;
+pub use instrumentation::{ComponentRun, Instrumentation, NoOp};
+
+mod stats;
+pub use stats::{ComponentStats, RunStats};
+
+mod snapshot;
+pub use snapshot::FlowSnapshot;
+
+mod debug;
+pub use debug::{DebugAction, DebugContext, Debugger};
+
 mod error;
 pub use error::{Error, RunResult as Result};
 
 mod context;
-pub use context::Ctx;
+pub use context::{Ctx, GlobalBag, GlobalTypeNames, Globals, PackagePath, PackageTracer};
 
 mod package;
-pub use package::Package;
+pub use package::{HashablePackage, Package, PackageError, PackageKind, PackageSchema};
 
 /// Structs for component infos and the trait [ComponentSchema](crate::component::ComponentSchema)
 pub mod component;
+/// Built-in components ready to be used in a [Flow]
+pub mod components;
 /// Structs for connect two components and their ports in a [Flow]
 pub mod connection;
 /// Structs for ports of components and the traits [Inputs](crate::ports::Inputs) and [Outputs](crate::ports::Outputs)
 pub mod ports;
+/// Serializable descriptions of a [Flow] and your components
+pub mod spec;
+/// Utilities for test [Flow]'s
+pub mod testing;
 
-/// Macros for derive [Inputs](crate::ports::Inputs) and [Outputs](crate::ports::Outputs) trait
+/// Macros for derive [Inputs](crate::ports::Inputs) and [Outputs](crate::ports::Outputs) trait,
+/// for generate them in a component with the attribute [component](crate::macros::component),
+/// and for create a [Flow] with the macro [flow!](crate::macros::flow)
 pub mod macros {
-    pub use rs_flow_macros::{Inputs, Outputs};
+    pub use rs_flow_macros::{component, flow, Inputs, Outputs};
 }
 
 /// Common imports for use `rs_flow` crate
@@ -30,6 +60,7 @@ pub mod prelude {
     pub use crate::macros::*;
     pub use crate::package::Package;
     pub use crate::ports::*;
+    pub use crate::sync_flow::SyncFlow;
 
     pub use crate::error::{Error, RunResult as Result};
     pub use crate::Ctx;
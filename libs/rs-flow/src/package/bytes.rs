@@ -0,0 +1,57 @@
+//! Serde representation of [Package::Bytes](super::Package::Bytes), as a object with a single
+//! field `"$bytes"` with the bytes encoded in base64, like `{"$bytes": "AQID"}`, so the bytes
+//! are distinguishable of a array of numbers when deserialized.
+
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserializer, Serializer};
+
+pub(crate) const MARKER: &str = "$bytes";
+
+/// Encode the bytes in base64, the value of field [MARKER]
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+/// Decode the base64 value of field [MARKER]
+pub(crate) fn decode(encoded: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    STANDARD.decode(encoded)
+}
+
+pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(MARKER, &encode(bytes))?;
+    map.end()
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    deserializer.deserialize_map(BytesVisitor)
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a object with only the field {MARKER:?}")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Some(key) = map.next_key::<String>()? else {
+            return Err(de::Error::missing_field(MARKER));
+        };
+        if key != MARKER {
+            return Err(de::Error::unknown_field(&key, &[MARKER]));
+        }
+        let encoded = map.next_value::<String>()?;
+        if map.next_key::<String>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        decode(&encoded).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt::{self, Display, Write};
+
+use super::Package;
+
+/// Depth of nested arrays and objects written, the deeper ones are written as `...`
+const MAX_DEPTH: usize = 32;
+
+///
+/// Compact and human readable form of a [Package], like a json without the
+/// guarantee of round trip, usefull for logs. The fields of objects are sorted.
+///
+/// ```
+/// use rs_flow::Package;
+///
+/// let package = Package::object([
+///     ("name", Package::string("bob")),
+///     ("tags", Package::array([1, 2])),
+///     ("avatar", Package::bytes(&[0; 16])),
+///     ("address", Package::empty()),
+/// ]);
+///
+/// assert_eq!(
+///     package.to_string(),
+///     r#"{"address": null, "avatar": <16 bytes>, "name": "bob", "tags": [1, 2]}"#
+/// );
+/// ```
+///
+impl Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_package(f, self, None, 0)
+    }
+}
+
+impl Package {
+    /// Return the [Display] form of this package with each item of arrays
+    /// and objects in a line, indented by two spaces for each level
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// let package = Package::object([("tags", Package::array(["a"]))]);
+    /// assert_eq!(package.to_pretty(), "{\n  \"tags\": [\n    \"a\"\n  ]\n}");
+    /// ```
+    pub fn to_pretty(&self) -> String {
+        let mut pretty = String::new();
+        write_package(&mut pretty, self, Some(0), 0).expect("Write in a String never fail");
+        pretty
+    }
+}
+
+/// Write the package, compact when `indent` is [None] or in many lines indented by the level
+fn write_package(
+    w: &mut impl Write,
+    package: &Package,
+    indent: Option<usize>,
+    depth: usize,
+) -> fmt::Result {
+    match package {
+        Package::Empty => w.write_str("null"),
+        Package::Integer(integer) => write!(w, "{integer}"),
+        Package::Number(number) => write!(w, "{number}"),
+        Package::String(string) => write!(w, "{string:?}"),
+        Package::Boolean(boolean) => write!(w, "{boolean}"),
+        Package::Bytes(bytes) => write!(w, "<{} bytes>", bytes.len()),
+        Package::EndOfStream => w.write_str("<end of stream>"),
+        Package::Array(array) if array.is_empty() => w.write_str("[]"),
+        Package::Object(object) if object.is_empty() => w.write_str("{}"),
+        Package::Array(_) | Package::Object(_) if depth >= MAX_DEPTH => w.write_str("..."),
+        Package::Array(array) => {
+            let items = array.iter().map(|item| (None, item));
+            write_items(w, ('[', ']'), items, indent, depth)
+        }
+        Package::Object(object) => {
+            let mut fields = object.iter().collect::<Vec<_>>();
+            fields.sort_by_key(|(key, _)| *key);
+            let items = fields.into_iter().map(|(key, item)| (Some(key), item));
+            write_items(w, ('{', '}'), items, indent, depth)
+        }
+    }
+}
+
+fn write_items<'a>(
+    w: &mut impl Write,
+    (open, close): (char, char),
+    items: impl Iterator<Item = (Option<&'a String>, &'a Package)>,
+    indent: Option<usize>,
+    depth: usize,
+) -> fmt::Result {
+    let inner = indent.map(|level| level + 1);
+    w.write_char(open)?;
+    for (index, (key, item)) in items.enumerate() {
+        match (index, inner) {
+            (0, None) => {}
+            (_, None) => w.write_str(", ")?,
+            (index, Some(level)) => {
+                if index > 0 {
+                    w.write_char(',')?;
+                }
+                write!(w, "\n{}", "  ".repeat(level))?;
+            }
+        }
+        if let Some(key) = key {
+            write!(w, "{key:?}: ")?;
+        }
+        write_package(w, item, inner, depth + 1)?;
+    }
+    if let Some(level) = indent {
+        write!(w, "\n{}", "  ".repeat(level))?;
+    }
+    w.write_char(close)
+}
@@ -11,6 +11,9 @@ pub enum PackageError {
     #[error("Package not contain a number")]
     NotNumber,
 
+    #[error("Number of package is not integral or not fit in the type")]
+    NumberOutOfRange,
+
     #[error("Package not contain a bool")]
     NotBoolean,
 
@@ -20,15 +23,35 @@ pub enum PackageError {
     #[error("Package not contain bytes")]
     NotBytes,
 
+    #[error("Bytes with length {len} are not a slice of elements with {size} bytes")]
+    MisalignedBytes { len: usize, size: usize },
+
+    #[error("Bytes are not aligned in memory to {align} bytes")]
+    UnalignedBytes { align: usize },
+
     #[error("Package not contain a array")]
     NotArray,
 
     #[error("Package not contain a object")]
     NotObject,
 
+    #[error("Package not match the schema in path {path:?}")]
+    SchemaMismatch { path: String },
+
+    #[error("Path {path:?} conflict with a value already defined")]
+    PathConflict { path: String },
+
     #[error("{0}")]
     SerializeFail(PackageSerializerError),
 
     #[error("{0}")]
     DeserializeFail(PackageDeserializerError),
+
+    #[cfg(feature = "msgpack")]
+    #[error("Package could not be encoded in MessagePack: {0}")]
+    EncodeMsgpack(#[source] rmp_serde::encode::Error),
+
+    #[cfg(feature = "msgpack")]
+    #[error("MessagePack could not be decoded in a package: {0}")]
+    DecodeMsgpack(#[source] rmp_serde::decode::Error),
 }
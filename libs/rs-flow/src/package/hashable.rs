@@ -0,0 +1,111 @@
+use std::hash::{Hash, Hasher};
+
+use super::package::Package;
+
+///
+/// Wrapper of a [Package] that implement [Hash] and [Eq], so packages can be used as keys
+/// of a [HashMap](std::collections::HashMap) or [HashSet](std::collections::HashSet).
+///
+/// - [Number](Package::Number)'s are compared by your bit pattern, with every `NaN`
+///   normalized to a single `NaN` (so a `NaN` is equal to any other `NaN`, unlike [f64])
+///   and `-0.0` normalized to `0.0`
+/// - [Object](Package::Object)'s are compared and hashed independent of the order of your keys
+/// - A [Integer](Package::Integer) is never equal to a [Number](Package::Number), like
+///   in the comparison of [Package]
+///
+/// ```
+/// use std::collections::HashSet;
+/// use rs_flow::{HashablePackage, Package};
+///
+/// let mut set = HashSet::new();
+/// assert!(set.insert(HashablePackage::from(Package::number(f64::NAN))));
+/// assert!(!set.insert(HashablePackage::from(Package::number(-f64::NAN))));
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct HashablePackage(pub Package);
+
+impl HashablePackage {
+    /// Return the [Package] wrapped
+    pub fn into_inner(self) -> Package {
+        self.0
+    }
+}
+
+impl From<Package> for HashablePackage {
+    fn from(package: Package) -> Self {
+        Self(package)
+    }
+}
+
+impl From<HashablePackage> for Package {
+    fn from(package: HashablePackage) -> Self {
+        package.0
+    }
+}
+
+impl PartialEq for HashablePackage {
+    fn eq(&self, other: &Self) -> bool {
+        canonical_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for HashablePackage {}
+
+impl Hash for HashablePackage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        canonical_hash(&self.0, state);
+    }
+}
+
+fn canonical_bits(number: f64) -> u64 {
+    if number.is_nan() {
+        f64::NAN.to_bits()
+    } else if number == 0.0 {
+        0.0f64.to_bits()
+    } else {
+        number.to_bits()
+    }
+}
+
+fn canonical_eq(a: &Package, b: &Package) -> bool {
+    match (a, b) {
+        (Package::Number(a), Package::Number(b)) => canonical_bits(*a) == canonical_bits(*b),
+        (Package::Array(a), Package::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| canonical_eq(a, b))
+        }
+        (Package::Object(a), Package::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| canonical_eq(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn canonical_hash<H: Hasher>(package: &Package, state: &mut H) {
+    std::mem::discriminant(package).hash(state);
+    match package {
+        Package::Empty | Package::EndOfStream => {}
+        Package::Integer(integer) => integer.hash(state),
+        Package::Number(number) => canonical_bits(*number).hash(state),
+        Package::String(string) => string.hash(state),
+        Package::Boolean(boolean) => boolean.hash(state),
+        Package::Bytes(bytes) => bytes.hash(state),
+        Package::Array(array) => {
+            array.len().hash(state);
+            for package in array {
+                canonical_hash(package, state);
+            }
+        }
+        Package::Object(object) => {
+            let mut keys = object.keys().collect::<Vec<_>>();
+            keys.sort();
+            keys.len().hash(state);
+            for key in keys {
+                key.hash(state);
+                canonical_hash(&object[key], state);
+            }
+        }
+    }
+}
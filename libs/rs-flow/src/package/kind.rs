@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use super::Package;
+
+///
+/// Variant of a [Package], without the value. Declared in a [Port](crate::ports::Port)
+/// to check if a output can be connected to a input before the [Flow](crate::flow::Flow) run.
+///
+/// ```
+/// use rs_flow::{Package, PackageKind};
+///
+/// assert_eq!(Package::from(1.5).kind(), PackageKind::Number);
+/// assert!(PackageKind::Number.accepts(PackageKind::Integer));
+/// assert!(!PackageKind::Integer.accepts(PackageKind::Number));
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PackageKind {
+    /// A [Package::Empty]
+    Empty,
+    /// A [Package::Integer]
+    Integer,
+    /// A [Package::Number]
+    Number,
+    /// A [Package::String]
+    String,
+    /// A [Package::Boolean]
+    Boolean,
+    /// A [Package::Bytes]
+    Bytes,
+    /// A [Package::Array]
+    Array,
+    /// A [Package::Object]
+    Object,
+    /// A [Package::EndOfStream]
+    EndOfStream,
+}
+
+impl PackageKind {
+    /// Return if a input of this kind accept the packages of a `output` kind, that is
+    /// the same kind, or a [Integer](PackageKind::Integer) output in a
+    /// [Number](PackageKind::Number) input
+    pub fn accepts(&self, output: PackageKind) -> bool {
+        *self == output || (*self == PackageKind::Number && output == PackageKind::Integer)
+    }
+}
+
+impl Package {
+    /// Return the [PackageKind] of this package
+    pub fn kind(&self) -> PackageKind {
+        match self {
+            Package::Empty => PackageKind::Empty,
+            Package::Integer(_) => PackageKind::Integer,
+            Package::Number(_) => PackageKind::Number,
+            Package::String(_) => PackageKind::String,
+            Package::Boolean(_) => PackageKind::Boolean,
+            Package::Bytes(_) => PackageKind::Bytes,
+            Package::Array(_) => PackageKind::Array,
+            Package::Object(_) => PackageKind::Object,
+            Package::EndOfStream => PackageKind::EndOfStream,
+        }
+    }
+}
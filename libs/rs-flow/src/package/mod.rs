@@ -1,6 +1,16 @@
+mod bytes;
+mod display;
 mod error;
+mod hashable;
+mod kind;
+#[allow(clippy::module_inception)]
 mod package;
+mod schema;
 
 pub mod serde;
 
+pub use error::PackageError;
+pub use hashable::HashablePackage;
+pub use kind::PackageKind;
 pub use package::Package;
+pub use schema::PackageSchema;
@@ -1,23 +1,45 @@
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 
 use serde::{Deserialize, Serialize};
 
 use super::{
     error::PackageError,
-    serde::{deserialize, serialize, PackageDeserializerError, PackageSerializerError},
+    schema::PackageSchema,
+    serde::{
+        deserialize, deserialize_borrowed, deserialize_strict, serialize, serialize_end_of_stream,
+        serialize_with_limit, PackageDeserializerError, PackageSerializerError,
+    },
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+///
+/// Data sent and received by the [Component](crate::component::Component)'s of a [Flow](crate::flow::Flow).
+///
+/// Two packages are equal when they have the same variant and equal contents, compared
+/// recursively in arrays and objects. The fields of objects are compared without order,
+/// a [Number](Package::Number) is compared with the float equality, so a `NaN` is never
+/// equal to itself, and a [Integer](Package::Integer) is never equal to a [Number](Package::Number).
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[serde(untagged)]
 pub enum Package {
     #[default]
     Empty,
+    /// Integer without the precision loss of a [Number](Package::Number) beyond 2^53, declared
+    /// before [Number](Package::Number) so that integers keep this variant when deserialized
+    Integer(i64),
     Number(f64),
     String(String),
     Boolean(bool),
+    /// Serialized as a object with a single field `"$bytes"` with the bytes encoded in base64,
+    /// like `{"$bytes": "AQID"}`, so a object with only this field is deserialized as bytes
+    #[serde(with = "super::bytes")]
     Bytes(Vec<u8>),
     Array(Vec<Package>),
     Object(HashMap<String, Package>),
+    /// Marker sent by a streaming [Component](crate::component::Component) to signal that
+    /// no more packages will be sent, see [`Ctx::send_eos`](crate::context::Ctx::send_eos)
+    #[serde(serialize_with = "serialize_end_of_stream", skip_deserializing)]
+    EndOfStream,
 }
 
 impl Package {
@@ -45,6 +67,22 @@ impl Package {
     pub fn try_from<T: Serialize>(content: T) -> Result<Self, PackageSerializerError> {
         serialize(content)
     }
+    /// Try create a [Package] from a type that implement Serialize, failing when
+    /// the content is nested deeper than `max_depth` arrays or objects.
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// assert!(Package::try_from_with_limit(vec![vec![1]], 2).is_ok());
+    /// assert!(Package::try_from_with_limit(vec![vec![1]], 1).is_err());
+    /// ```
+    ///
+    pub fn try_from_with_limit<T: Serialize>(
+        content: T,
+        max_depth: usize,
+    ) -> Result<Self, PackageSerializerError> {
+        serialize_with_limit(content, max_depth)
+    }
     /// Try deserialize that [Package] to the type provided
     ///
     /// ```
@@ -72,6 +110,59 @@ impl Package {
     pub fn try_into<T: for<'a> Deserialize<'a>>(self) -> Result<T, PackageDeserializerError> {
         deserialize(self)
     }
+    /// Try deserialize that [Package] to the type provided, using checked conversions
+    /// for integers. Fail when a number not fit in the integer type or is not integral,
+    /// instead of wrap or truncate it like [Package::try_into].
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// assert_eq!(Package::number(300.0).try_into::<u8>().unwrap(), 44);
+    /// assert!(Package::number(300.0).try_into_strict::<u8>().is_err());
+    /// assert_eq!(Package::number(200.0).try_into_strict::<u8>().unwrap(), 200);
+    /// ```
+    ///
+    pub fn try_into_strict<T: for<'a> Deserialize<'a>>(
+        self,
+    ) -> Result<T, PackageDeserializerError> {
+        deserialize_strict(self)
+    }
+    /// Try deserialize that [Package] to the type provided, without consume the [Package].
+    ///
+    /// Strings and bytes can be borrowed from the [Package], that avoid clones
+    /// when deserialize large objects.
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// use rs_flow::Package;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Person<'a> {
+    ///     name: &'a str,
+    ///     #[serde(borrow)]
+    ///     nickname: Cow<'a, str>,
+    ///     age: u16
+    /// }
+    ///
+    /// let object = Package::object([
+    ///     ("name", Package::string("Robert")),
+    ///     ("nickname", Package::string("Boby")),
+    ///     ("age", Package::number(24.0))
+    /// ]);
+    ///
+    /// let person: Person = object.deserialize_borrowed().unwrap();
+    /// assert_eq!(person.name, "Robert");
+    /// assert!(matches!(person.nickname, Cow::Borrowed("Boby")));
+    /// assert_eq!(person.age, 24);
+    /// ```
+    ///
+    pub fn deserialize_borrowed<'a, T: Deserialize<'a>>(
+        &'a self,
+    ) -> Result<T, PackageDeserializerError> {
+        deserialize_borrowed(self)
+    }
 
     /// Create a empty package
     pub fn empty() -> Self {
@@ -81,6 +172,10 @@ impl Package {
     pub fn number(value: f64) -> Self {
         value.into()
     }
+    /// Create a package with a integer
+    pub fn integer(value: i64) -> Self {
+        Self::Integer(value)
+    }
     /// Create a package with a boolean
     pub fn bool(value: bool) -> Self {
         value.into()
@@ -93,6 +188,10 @@ impl Package {
     pub fn bytes(value: &[u8]) -> Self {
         value.into()
     }
+    /// Create a end-of-stream marker package
+    pub fn end_of_stream() -> Self {
+        Self::EndOfStream
+    }
     /// Create a package with a from a vector of packages
     pub fn array<T: Into<Package>>(value: impl IntoIterator<Item = T>) -> Self {
         Self::Array(value.into_iter().map(Into::into).collect())
@@ -110,54 +209,90 @@ impl Package {
         )
     }
 
+    /// Create a object package from pairs of path and value, the segments of path are separated
+    /// by `/` and create the nested objects, like `"user/address/city"` (a leading `/` is ignored).
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// let package = Package::from_pairs(&[
+    ///     ("user/name", "bob".into()),
+    ///     ("user/age", 24.into()),
+    /// ]).unwrap();
+    ///
+    /// assert_eq!(package.get_path("user.name"), Some(&Package::string("bob")));
+    /// assert_eq!(package.get_path("user.age"), Some(&Package::integer(24)));
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// Error [PathConflict](PackageError::PathConflict) if a path is already defined
+    /// or if a segment of path is already defined with a value that is not a object
+    pub fn from_pairs(pairs: &[(&str, Package)]) -> Result<Self, PackageError> {
+        let mut root = HashMap::new();
+        for (path, value) in pairs {
+            let conflict = || PackageError::PathConflict {
+                path: path.to_string(),
+            };
+
+            let mut segments = path.trim_start_matches('/').split('/').peekable();
+            let mut object = &mut root;
+            while let Some(segment) = segments.next() {
+                if segments.peek().is_none() {
+                    if object.contains_key(segment) {
+                        return Err(conflict());
+                    }
+                    object.insert(segment.to_owned(), value.clone());
+                    break;
+                }
+
+                let nested = object
+                    .entry(segment.to_owned())
+                    .or_insert_with(|| Package::Object(HashMap::new()));
+                match nested {
+                    Package::Object(nested) => object = nested,
+                    _ => return Err(conflict()),
+                }
+            }
+        }
+        Ok(Package::Object(root))
+    }
+
     /// Return if the package is Empty variant
     pub fn is_empty(&self) -> bool {
-        match self {
-            Package::Empty => true,
-            _ => false,
-        }
+        matches!(self, Package::Empty)
     }
     /// Return if the package is Number variant
     pub fn is_number(&self) -> bool {
-        match self {
-            Package::Number(_) => true,
-            _ => false,
-        }
+        matches!(self, Package::Number(_))
+    }
+    /// Return if the package is Integer variant
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Package::Integer(_))
     }
     /// Return if the package is Boolean variant
     pub fn is_bool(&self) -> bool {
-        match self {
-            Package::Boolean(_) => true,
-            _ => false,
-        }
+        matches!(self, Package::Boolean(_))
     }
     /// Return if the package is String variant
     pub fn is_string(&self) -> bool {
-        match self {
-            Package::String(_) => true,
-            _ => false,
-        }
+        matches!(self, Package::String(_))
     }
     /// Return if the package is Bytes variant
     pub fn is_bytes(&self) -> bool {
-        match self {
-            Package::Bytes(_) => true,
-            _ => false,
-        }
+        matches!(self, Package::Bytes(_))
     }
     /// Return if the package is Array variant
     pub fn is_array(&self) -> bool {
-        match self {
-            Package::Array(_) => true,
-            _ => false,
-        }
+        matches!(self, Package::Array(_))
     }
     /// Return if the package is Object variant
     pub fn is_object(&self) -> bool {
-        match self {
-            Package::Object(_) => true,
-            _ => false,
-        }
+        matches!(self, Package::Object(_))
+    }
+    /// Return if the package is EndOfStream variant
+    pub fn is_eos(&self) -> bool {
+        matches!(self, Package::EndOfStream)
     }
 
     /// Return a () if the package is a Empty variant otherwise a error
@@ -167,13 +302,66 @@ impl Package {
             _ => Err(PackageError::NotEmpty),
         }
     }
-    /// Return a f64 if the package is a Number variant otherwise a error
+    /// Return a f64 if the package is a Number or Integer variant otherwise a error
     pub fn get_number(self) -> Result<f64, PackageError> {
         match self {
             Package::Number(number) => Ok(number),
+            Package::Integer(integer) => Ok(integer as f64),
             _ => Err(PackageError::NotNumber),
         }
     }
+    /// Return a i64 if the package is a Integer variant, or a Number variant without
+    /// fractional part that fit in a i64
+    ///
+    /// ```
+    /// use rs_flow::{Package, PackageError};
+    ///
+    /// assert_eq!(Package::integer(9007199254740993).get_i64().unwrap(), 9007199254740993);
+    /// assert_eq!(Package::number(-3.0).get_i64().unwrap(), -3);
+    /// assert!(matches!(Package::number(2.5).get_i64(), Err(PackageError::NumberOutOfRange)));
+    /// assert!(matches!(Package::number(1e20).get_i64(), Err(PackageError::NumberOutOfRange)));
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Error [NotNumber](PackageError::NotNumber) if the package is not a number
+    /// - Error [NumberOutOfRange](PackageError::NumberOutOfRange) if the number have a
+    ///   fractional part or not fit in a i64
+    pub fn get_i64(self) -> Result<i64, PackageError> {
+        match self {
+            Package::Integer(integer) => Ok(integer),
+            package => {
+                let number = package.get_number()?;
+                if number.fract() == 0.0 && number >= i64::MIN as f64 && number < i64::MAX as f64 {
+                    Ok(number as i64)
+                } else {
+                    Err(PackageError::NumberOutOfRange)
+                }
+            }
+        }
+    }
+    /// Return a u64 if the package is a Integer variant, or a Number variant without
+    /// fractional part, that fit in a u64, see [Package::get_i64]
+    pub fn get_u64(self) -> Result<u64, PackageError> {
+        match self {
+            Package::Integer(integer) => {
+                u64::try_from(integer).map_err(|_| PackageError::NumberOutOfRange)
+            }
+            package => {
+                let number = package.get_number()?;
+                if number.fract() == 0.0 && number >= 0.0 && number < u64::MAX as f64 {
+                    Ok(number as u64)
+                } else {
+                    Err(PackageError::NumberOutOfRange)
+                }
+            }
+        }
+    }
+    /// Return a usize if the package is a Integer variant, or a Number variant without
+    /// fractional part, that fit in a usize, usefull for indexes and ids, see [Package::get_i64]
+    pub fn get_usize(self) -> Result<usize, PackageError> {
+        usize::try_from(self.get_u64()?).map_err(|_| PackageError::NumberOutOfRange)
+    }
     /// Return a String if the package is a String variant otherwise a error
     pub fn get_string(self) -> Result<String, PackageError> {
         match self {
@@ -195,6 +383,40 @@ impl Package {
             _ => Err(PackageError::NotBytes),
         }
     }
+    /// Return the bytes viewed as a slice of `T` without copy them,
+    /// if the package is a Bytes variant otherwise a error
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// let bytes = [1.5f32, -2.0].iter().flat_map(|n| n.to_ne_bytes()).collect::<Vec<_>>();
+    /// let package = Package::bytes(&bytes);
+    /// assert_eq!(package.get_slice::<f32>().unwrap(), &[1.5, -2.0]);
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Error [MisalignedBytes](PackageError::MisalignedBytes) if the length of bytes is not
+    ///   a multiple of the size of `T`
+    /// - Error [UnalignedBytes](PackageError::UnalignedBytes) if the bytes are not aligned in
+    ///   memory to the alignment of `T`
+    #[cfg(feature = "bytemuck")]
+    pub fn get_slice<T: bytemuck::Pod>(&self) -> Result<&[T], PackageError> {
+        let Package::Bytes(bytes) = self else {
+            return Err(PackageError::NotBytes);
+        };
+        bytemuck::try_cast_slice(bytes).map_err(|error| match error {
+            bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned => {
+                PackageError::UnalignedBytes {
+                    align: std::mem::align_of::<T>(),
+                }
+            }
+            _ => PackageError::MisalignedBytes {
+                len: bytes.len(),
+                size: std::mem::size_of::<T>(),
+            },
+        })
+    }
     /// Return a `Vec<Package>` if the package is a Array variant otherwise a error
     pub fn get_array(self) -> Result<Vec<Package>, PackageError> {
         match self {
@@ -209,7 +431,120 @@ impl Package {
             _ => Err(PackageError::NotObject),
         }
     }
+
+    /// Check if the package match the `schema`
+    ///
+    /// # Error
+    ///
+    /// Error [SchemaMismatch](PackageError::SchemaMismatch) with the dotted path of the
+    /// first part of package that not match, a empty path if is the package itself
+    pub fn validate(&self, schema: &PackageSchema) -> Result<(), PackageError> {
+        match schema.mismatch(self) {
+            Some(path) => Err(PackageError::SchemaMismatch { path }),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the package found following a dotted path like `"user.address.city"`,
+    /// numeric segments are used as index of arrays like `"items.0.price"`.
+    ///
+    /// Return [None] if any segment is missing.
+    pub fn get_path(&self, path: &str) -> Option<&Package> {
+        path.split('.')
+            .try_fold(self, |package, segment| match package {
+                Package::Object(object) => object.get(segment),
+                Package::Array(array) => array.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Merge the `other` package in this package. When both are objects the fields are merged
+    /// recursively, the fields that exist only in one side are kept. Otherwise `other` replace
+    /// this package, so arrays are replaced and not concatenated.
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// let mut config = Package::object([
+    ///     ("server", Package::object([("host", "localhost"), ("port", "80")])),
+    ///     ("tags", Package::array(["a"])),
+    /// ]);
+    /// config.merge(Package::object([
+    ///     ("server", Package::object([("port", "8080")])),
+    ///     ("tags", Package::array(["b"])),
+    /// ]));
+    ///
+    /// assert_eq!(config.get_path("server.host"), Some(&Package::string("localhost")));
+    /// assert_eq!(config.get_path("server.port"), Some(&Package::string("8080")));
+    /// assert_eq!(config.get_path("tags"), Some(&Package::array(["b"])));
+    /// ```
+    pub fn merge(&mut self, other: Package) {
+        match (self, other) {
+            (Package::Object(object), Package::Object(other)) => {
+                for (key, value) in other {
+                    match object.entry(key) {
+                        Entry::Occupied(mut entry) => entry.get_mut().merge(value),
+                        Entry::Vacant(entry) => {
+                            entry.insert(value);
+                        }
+                    }
+                }
+            }
+            (package, other) => *package = other,
+        }
+    }
+
+    /// Return this package merged with `other`, see [Package::merge]
+    pub fn merged(mut self, other: Package) -> Package {
+        self.merge(other);
+        self
+    }
+
+    /// Return the package found following a dotted path to be modified, see [Package::get_path]
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// let mut package = Package::object([("items", Package::array([Package::object([("price", 2.0)])]))]);
+    /// if let Some(price) = package.get_path_mut("items.0.price") {
+    ///     *price = Package::number(3.0);
+    /// }
+    /// assert_eq!(package.get_path("items.0.price"), Some(&Package::number(3.0)));
+    /// ```
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Package> {
+        path.split('.')
+            .try_fold(self, |package, segment| match package {
+                Package::Object(object) => object.get_mut(segment),
+                Package::Array(array) => array.get_mut(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+}
+
+/// Typed slices of bytes packages
+macro_rules! impl_get_slice {
+    ($($name: ident: $ty: ty),+) => {
+        #[cfg(feature = "bytemuck")]
+        impl Package {
+            $(
+                #[doc = concat!("Return the bytes viewed as a `&[", stringify!($ty), "]`, see [Package::get_slice]")]
+                pub fn $name(&self) -> Result<&[$ty], PackageError> {
+                    self.get_slice()
+                }
+            )+
+        }
+    };
 }
+impl_get_slice!(
+    get_u16_slice: u16,
+    get_i16_slice: i16,
+    get_u32_slice: u32,
+    get_i32_slice: i32,
+    get_u64_slice: u64,
+    get_i64_slice: i64,
+    get_f32_slice: f32,
+    get_f64_slice: f64
+);
 
 /// Packages number implmentations
 macro_rules! impl_from_number {
@@ -223,10 +558,27 @@ macro_rules! impl_from_number {
         )+
     };
 }
-impl_from_number!(u8, u16, u32, u64, usize);
-impl_from_number!(i8, i16, i32, i64, isize);
 impl_from_number!(f32, f64);
 
+/// Packages integer implmentations, a unsigned integer bigger than [i64::MAX]
+/// is converted to a [Package::Number]
+macro_rules! impl_from_integer {
+    ($($ty: ty),+) => {
+        $(
+            impl From<$ty> for Package {
+                fn from(value: $ty) -> Self {
+                    match i64::try_from(value) {
+                        Ok(integer) => Package::Integer(integer),
+                        Err(_) => Package::Number(value as f64),
+                    }
+                }
+            }
+        )+
+    };
+}
+impl_from_integer!(u8, u16, u32, u64, usize);
+impl_from_integer!(i8, i16, i32, i64, isize);
+
 /// Packages boolean implmentations
 impl From<bool> for Package {
     fn from(value: bool) -> Self {
@@ -262,3 +614,119 @@ impl<const C: usize> From<[u8; C]> for Package {
         Package::Bytes(value.into())
     }
 }
+
+/// Packages json implementations
+///
+/// A json number is converted to a [Package::Integer] when fit in a i64, otherwise
+/// to a [Package::Number]. A object with only the field `"$bytes"` with a base64 string
+/// is converted to a [Package::Bytes], like when deserialized.
+#[cfg(feature = "json")]
+impl From<serde_json::Value> for Package {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => Package::Empty,
+            Value::Bool(bool) => Package::Boolean(bool),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => Package::Integer(integer),
+                None => Package::Number(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(string) => Package::String(string),
+            Value::Array(array) => Package::Array(array.into_iter().map(Into::into).collect()),
+            Value::Object(object) => {
+                if let (1, Some(Value::String(encoded))) =
+                    (object.len(), object.get(super::bytes::MARKER))
+                {
+                    if let Ok(bytes) = super::bytes::decode(encoded) {
+                        return Package::Bytes(bytes);
+                    }
+                }
+                Package::Object(
+                    object
+                        .into_iter()
+                        .map(|(key, value)| (key, value.into()))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Json has not a bytes type, so a [Package::Bytes] is converted to a object with a single
+/// field `"$bytes"` with the bytes encoded in base64, the same format used when serialized,
+/// that is converted back as a [Package::Bytes].
+///
+/// A [Package::Number] that is NaN or infinite is converted to null, like the
+/// [Package::Empty] and [Package::EndOfStream].
+///
+/// ```
+/// use rs_flow::Package;
+/// use serde_json::json;
+///
+/// let package = Package::from(json!({ "id": 9007199254740993u64, "tags": ["a", "b"] }));
+/// assert_eq!(package.get_path("id"), Some(&Package::integer(9007199254740993)));
+///
+/// let value = serde_json::Value::from(Package::bytes(&[1, 2]));
+/// assert_eq!(value, json!({ "$bytes": "AQI=" }));
+/// assert_eq!(Package::from(value), Package::bytes(&[1, 2]));
+/// ```
+#[cfg(feature = "json")]
+impl From<Package> for serde_json::Value {
+    fn from(value: Package) -> Self {
+        use serde_json::Value;
+
+        match value {
+            Package::Empty | Package::EndOfStream => Value::Null,
+            Package::Integer(integer) => Value::from(integer),
+            Package::Number(number) => Value::from(number),
+            Package::String(string) => Value::String(string),
+            Package::Boolean(bool) => Value::Bool(bool),
+            Package::Bytes(bytes) => Value::Object(
+                [(
+                    super::bytes::MARKER.to_owned(),
+                    Value::String(super::bytes::encode(&bytes)),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            Package::Array(array) => Value::Array(array.into_iter().map(Into::into).collect()),
+            Package::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl Package {
+    /// Encode this package in MessagePack, more compact than json and that keep
+    /// the distinction of integers, numbers, bytes and arrays
+    ///
+    /// ```
+    /// use rs_flow::Package;
+    ///
+    /// let package = Package::object([("id", Package::integer(1)), ("raw", Package::bytes(&[1, 2]))]);
+    /// let encoded = package.to_msgpack().unwrap();
+    /// assert_eq!(Package::from_msgpack(&encoded).unwrap(), package);
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// Error [EncodeMsgpack](PackageError::EncodeMsgpack) if the package could not be encoded
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, PackageError> {
+        rmp_serde::to_vec_named(self).map_err(PackageError::EncodeMsgpack)
+    }
+
+    /// Decode a package encoded in MessagePack, see [Package::to_msgpack]
+    ///
+    /// # Error
+    ///
+    /// Error [DecodeMsgpack](PackageError::DecodeMsgpack) if the bytes are not a valid package
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Package, PackageError> {
+        rmp_serde::from_slice(bytes).map_err(PackageError::DecodeMsgpack)
+    }
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+
+use super::Package;
+
+///
+/// Expected shape of the [Package]'s sent or received by a [Port](crate::ports::Port),
+/// defined in a const context, like the [Ports](crate::ports::Ports) of a component.
+///
+/// ```
+/// use rs_flow::{Package, PackageSchema};
+///
+/// const POINT: PackageSchema =
+///     PackageSchema::Object(&[("x", PackageSchema::Number), ("y", PackageSchema::Number)]);
+///
+/// assert!(POINT.matches(&Package::object([("x", 1.0), ("y", 2.5)])));
+/// assert!(!POINT.matches(&Package::object([("x", 1.0)])));
+/// ```
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PackageSchema {
+    /// Any package
+    Any,
+    /// A [Package::Empty]
+    Empty,
+    /// A [Package::Number] or a [Package::Integer]
+    Number,
+    /// A [Package::Integer]
+    Integer,
+    /// A [Package::Boolean]
+    Boolean,
+    /// A [Package::String]
+    String,
+    /// A [Package::Bytes]
+    Bytes,
+    /// A [Package::Array] with all items matching the schema
+    Array(&'static PackageSchema),
+    /// A [Package::Object] with at least the fields, matching your schemas
+    Object(&'static [(&'static str, PackageSchema)]),
+}
+
+impl PackageSchema {
+    /// Return if the package match this schema
+    pub fn matches(&self, package: &Package) -> bool {
+        self.mismatch(package).is_none()
+    }
+
+    /// Return the dotted path of the first part of package that not match this schema,
+    /// the path of the package itself is a empty string
+    pub(crate) fn mismatch(&self, package: &Package) -> Option<String> {
+        let matched = match (self, package) {
+            (PackageSchema::Any, _)
+            | (PackageSchema::Empty, Package::Empty)
+            | (PackageSchema::Number, Package::Number(_) | Package::Integer(_))
+            | (PackageSchema::Integer, Package::Integer(_))
+            | (PackageSchema::Boolean, Package::Boolean(_))
+            | (PackageSchema::String, Package::String(_))
+            | (PackageSchema::Bytes, Package::Bytes(_)) => true,
+            (PackageSchema::Array(item), Package::Array(array)) => {
+                return array.iter().enumerate().find_map(|(index, package)| {
+                    item.mismatch(package)
+                        .map(|path| join(&index.to_string(), &path))
+                });
+            }
+            (PackageSchema::Object(fields), Package::Object(object)) => {
+                return fields
+                    .iter()
+                    .find_map(|(name, schema)| match object.get(*name) {
+                        Some(package) => schema.mismatch(package).map(|path| join(name, &path)),
+                        None => Some(name.to_string()),
+                    });
+            }
+            _ => false,
+        };
+        (!matched).then(String::new)
+    }
+
+    /// Return if all packages that match the `output` schema also match this schema,
+    /// usefull to check if a output port can be connected to a input port
+    ///
+    /// ```
+    /// use rs_flow::PackageSchema;
+    ///
+    /// assert!(PackageSchema::Number.accepts(&PackageSchema::Integer));
+    /// assert!(!PackageSchema::Number.accepts(&PackageSchema::String));
+    /// assert!(PackageSchema::Any.accepts(&PackageSchema::String));
+    /// ```
+    pub fn accepts(&self, output: &PackageSchema) -> bool {
+        match (self, output) {
+            (PackageSchema::Any, _) => true,
+            (PackageSchema::Number, PackageSchema::Integer) => true,
+            (PackageSchema::Array(input), PackageSchema::Array(output)) => input.accepts(output),
+            (PackageSchema::Object(inputs), PackageSchema::Object(outputs)) => {
+                inputs.iter().all(|(name, input)| {
+                    outputs
+                        .iter()
+                        .any(|(field, output)| field == name && input.accepts(output))
+                })
+            }
+            (input, output) => input == output,
+        }
+    }
+}
+
+fn join(segment: &str, path: &str) -> String {
+    if path.is_empty() {
+        segment.to_owned()
+    } else {
+        format!("{segment}.{path}")
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::HashMap;
+use std::{slice, vec};
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::package::{error::PackageError, Package};
+
+use super::deserializer::MapKeyDeserializer;
+use super::PackageDeserializerError;
+
+pub fn deserialize_borrowed<'a, T: Deserialize<'a>>(
+    package: &'a Package,
+) -> Result<T, PackageDeserializerError> {
+    T::deserialize(package)
+}
+
+/// A owned copy of a scalar package, compound packages are copied without content,
+/// this is enough for deserialize the scalar types and return the same errors
+fn shallow(package: &Package) -> Package {
+    match package {
+        Package::Empty => Package::Empty,
+        Package::Integer(integer) => Package::Integer(*integer),
+        Package::Number(number) => Package::Number(*number),
+        Package::Boolean(bool) => Package::Boolean(*bool),
+        Package::String(_) => Package::String(String::new()),
+        Package::Bytes(_) => Package::Bytes(Vec::new()),
+        Package::Array(_) => Package::Array(Vec::new()),
+        Package::Object(_) => Package::Object(HashMap::new()),
+        Package::EndOfStream => Package::EndOfStream,
+    }
+}
+
+fn get_str(package: &Package) -> Result<&str, PackageDeserializerError> {
+    match package {
+        Package::String(string) => Ok(string),
+        _ => Err(PackageError::NotString.into()),
+    }
+}
+
+fn get_object(package: &Package) -> Result<&HashMap<String, Package>, PackageDeserializerError> {
+    match package {
+        Package::Object(object) => Ok(object),
+        _ => Err(PackageError::NotObject.into()),
+    }
+}
+
+fn get_named<'de>(
+    package: &'de Package,
+    name: &'static str,
+) -> Result<&'de Package, PackageDeserializerError> {
+    let object = get_object(package)?;
+    if object.len() != 1 {
+        return Err(PackageDeserializerError::from_cause(format!(
+            "Object cannot be parsed into '{name}' because not have a unique entry"
+        )));
+    }
+    object.get(name).ok_or_else(|| {
+        PackageDeserializerError::from_cause(format!(
+            "Object cannot be parsed into '{name}' because not have that entry"
+        ))
+    })
+}
+
+macro_rules! forward_to_shallow {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                shallow(self).$method(visitor)
+            }
+        )+
+    };
+}
+
+// region: impl Deserializer
+
+impl<'de> Deserializer<'de> for &'de Package {
+    type Error = PackageDeserializerError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Package::Empty => self.deserialize_unit(visitor),
+            Package::Integer(_) => self.deserialize_i64(visitor),
+            Package::Number(_) => self.deserialize_f64(visitor),
+            Package::String(_) => self.deserialize_str(visitor),
+            Package::Boolean(_) => self.deserialize_bool(visitor),
+            Package::Bytes(_) => self.deserialize_bytes(visitor),
+            Package::Array(_) => self.deserialize_seq(visitor),
+            Package::Object(_) => self.deserialize_map(visitor),
+            Package::EndOfStream => visitor.visit_unit(),
+        }
+    }
+
+    forward_to_shallow!(
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_unit
+    );
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut chars = get_str(self)?.chars();
+        if let (Some(char), None) = (chars.next(), chars.next()) {
+            visitor.visit_char(char)
+        } else {
+            Err(PackageDeserializerError::from_cause(
+                "Not a char".to_owned(),
+            ))
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(get_str(self)?)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Package::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            _ => Err(PackageError::NotBytes.into()),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.is_empty() || self.is_eos() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = get_str(self)?;
+        if name == value {
+            visitor.visit_unit()
+        } else {
+            Err(PackageDeserializerError::from_cause(format!(
+                "Expect '{name}' but found '{value}'"
+            )))
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(get_named(self, name)?)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(BorrowedArray::create(self, None)?)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(BorrowedArray::create(self, Some(len))?)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(BorrowedArray::create(get_named(self, name)?, Some(len))?)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(BorrowedObject::create(get_object(self)?))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(BorrowedObject::create_struct(get_object(self)?, fields))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (variant, value) = match self {
+            // enums are encoded in json as maps with a single key:value pair
+            Package::Object(object) if object.len() == 1 => {
+                let (variant, value) = object.iter().next().expect("Object have a single key");
+                (variant.as_str(), Some(value))
+            }
+            Package::Object(_) => {
+                return Err(PackageDeserializerError::from_cause(
+                    "Expect object with a single key".to_owned(),
+                ));
+            }
+            Package::String(variant) => (variant.as_str(), None),
+            _ => {
+                return Err(PackageDeserializerError::from_cause(
+                    "Expect string or object".to_owned(),
+                ));
+            }
+        };
+
+        visitor.visit_enum(BorrowedEnum { variant, value })
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+}
+
+struct BorrowedEnum<'de> {
+    variant: &'de str,
+    value: Option<&'de Package>,
+}
+
+impl<'de> EnumAccess<'de> for BorrowedEnum<'de> {
+    type Error = PackageDeserializerError;
+    type Variant = BorrowedVariant<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = BorrowedStrDeserializer::<Self::Error>::new(self.variant);
+        let visitor = BorrowedVariant { value: self.value };
+        seed.deserialize(variant).map(|v| (v, visitor))
+    }
+}
+
+struct BorrowedVariant<'de> {
+    value: Option<&'de Package>,
+}
+
+impl<'de> VariantAccess<'de> for BorrowedVariant<'de> {
+    type Error = PackageDeserializerError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.value {
+            Some(value) => Deserialize::deserialize(value),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(PackageDeserializerError::from_cause(
+                "Expect type variant".to_owned(),
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(package) => visitor.visit_seq(BorrowedArray::create(package, Some(len))?),
+            None => Err(PackageDeserializerError::from_cause(
+                "Expect tuple variant".to_owned(),
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(Package::Object(object)) => visitor.visit_map(BorrowedObject::create(object)),
+            _ => Err(PackageDeserializerError::from_cause(
+                "Expect struct variant".to_owned(),
+            )),
+        }
+    }
+}
+
+struct BorrowedArray<'de> {
+    data: slice::Iter<'de, Package>,
+}
+
+impl<'de> BorrowedArray<'de> {
+    fn create(package: &'de Package, len: Option<usize>) -> Result<Self, PackageDeserializerError> {
+        let array = match package {
+            Package::Array(array) => array,
+            _ => return Err(PackageError::NotArray.into()),
+        };
+        if let Some(len) = len {
+            if len != array.len() {
+                return Err(PackageDeserializerError::from_cause(format!(
+                    "Required a array with length '{len}', but found '{}'",
+                    array.len()
+                )));
+            }
+        }
+        Ok(Self { data: array.iter() })
+    }
+}
+
+impl<'de> SeqAccess<'de> for BorrowedArray<'de> {
+    type Error = PackageDeserializerError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.data.next() {
+            Some(package) => seed.deserialize(package).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.data.len())
+    }
+}
+
+struct BorrowedObject<'de> {
+    data: vec::IntoIter<(&'de String, &'de Package)>,
+    last: Option<&'de Package>,
+}
+
+impl<'de> BorrowedObject<'de> {
+    fn create(object: &'de HashMap<String, Package>) -> Self {
+        Self {
+            data: object.iter().collect::<Vec<_>>().into_iter(),
+            last: None,
+        }
+    }
+
+    /// Iterate the entries of fields in the declared order, and after the others
+    /// entries ordered by key, like the owned deserializer
+    fn create_struct(object: &'de HashMap<String, Package>, fields: &[&str]) -> Self {
+        let mut data = fields
+            .iter()
+            .filter_map(|field| object.get_key_value(*field))
+            .collect::<Vec<_>>();
+        let mut others = object
+            .iter()
+            .filter(|(key, _)| !fields.contains(&key.as_str()))
+            .collect::<Vec<_>>();
+        others.sort_by_key(|(key, _)| *key);
+        data.extend(others);
+        Self {
+            data: data.into_iter(),
+            last: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for BorrowedObject<'de> {
+    type Error = PackageDeserializerError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.data.next() {
+            Some((key, value)) => {
+                let key = seed.deserialize(BorrowedMapKey(key))?;
+                self.last = Some(value);
+
+                Ok(Some(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.last.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(PackageDeserializerError::from_cause(
+                "Value is missing".to_owned(),
+            )),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.data.len())
+    }
+}
+// endregion
+
+// region: impl BorrowedMapKey
+
+/// Map key that is borrowed as string, others types are parsed like the [MapKeyDeserializer]
+struct BorrowedMapKey<'de>(&'de str);
+
+impl BorrowedMapKey<'_> {
+    fn owned(&self) -> MapKeyDeserializer {
+        MapKeyDeserializer {
+            key: self.0.to_owned(),
+        }
+    }
+}
+
+macro_rules! forward_to_owned_key {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.owned().$method(visitor)
+            }
+        )+
+    };
+}
+
+impl<'de> Deserializer<'de> for BorrowedMapKey<'de> {
+    type Error = PackageDeserializerError;
+
+    forward_to_owned_key!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_ignored_any
+    );
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.owned().deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.owned().deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.owned().deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.owned().deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.owned().deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.owned().deserialize_enum(name, variants, visitor)
+    }
+}
+// endregion
@@ -1,4 +1,4 @@
-use std::{collections::{hash_map::IntoIter, HashMap}, fmt::Display};
+use std::{collections::HashMap, fmt::Display, vec::IntoIter};
 
 use serde::{
     de::{
@@ -17,6 +17,12 @@ pub struct PackageDeserializerError {
     cause: String
 }
 
+impl PackageDeserializerError {
+    pub(super) fn from_cause(cause: String) -> Self {
+        Self { cause }
+    }
+}
+
 impl From<PackageError> for PackageDeserializerError {
     fn from(value: PackageError) -> Self {
         Self { cause: value.to_string() }
@@ -27,6 +33,10 @@ impl serde::de::Error for PackageDeserializerError {
     fn custom<T: Display>(msg: T) -> Self {
         Self { cause: msg.to_string() }
     }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self { cause: format!("Object cannot be parsed into struct because not have the field '{field}'") }
+    }
 }
 
 
@@ -37,107 +47,262 @@ pub fn deserialize<T: for<'a> Deserialize<'a>>(package: Package) ->
 }
 
 
+/// Deserialize a [Package] using checked conversions for integers, failing when the
+/// number not fit in the integer type or is not integral
+pub fn deserialize_strict<T: for<'a> Deserialize<'a>>(package: Package) -> 
+    Result<T, PackageDeserializerError> 
+{
+    T::deserialize(PackageDeserializer::new(package, true))
+}
+
+
 // region: impl Deserializer
 
+/// Deserializer of a owned [Package], in strict mode the integers are converted
+/// with checks instead of casts
+pub(super) struct PackageDeserializer {
+    package: Package,
+    strict: bool
+}
+
+impl PackageDeserializer {
+    fn new(package: Package, strict: bool) -> Self {
+        Self { package, strict }
+    }
+
+    fn strict_integer<T: TryFrom<i64>>(self) -> Result<T, PackageDeserializerError> {
+        let ty = std::any::type_name::<T>();
+        let out_of_range = || PackageDeserializerError { cause: format!("value out of range for {ty}") };
+
+        let integer = match self.package {
+            Package::Integer(integer) => integer,
+            Package::Number(number) if number.fract() != 0.0 => {
+                return Err(PackageDeserializerError { cause: format!("value {number} is not integral for {ty}") });
+            }
+            Package::Number(number) if number >= i64::MIN as f64 && number < i64::MAX as f64 => {
+                number as i64
+            }
+            Package::Number(_) => return Err(out_of_range()),
+            _ => return Err(PackageError::NotNumber.into()),
+        };
+        T::try_from(integer).map_err(|_| out_of_range())
+    }
+}
+
+macro_rules! forward_to_package_deserializer {
+    ($($method:ident),+) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: serde::de::Visitor<'de> {
+                PackageDeserializer::new(self, false).$method(visitor)
+            }
+        )+
+    };
+}
+
 impl<'de> Deserializer<'de> for Package {
     type Error = PackageDeserializerError;
 
+    forward_to_package_deserializer!(
+        deserialize_any, deserialize_bool, 
+        deserialize_i8, deserialize_i16, deserialize_i32, deserialize_i64, 
+        deserialize_u8, deserialize_u16, deserialize_u32, deserialize_u64, 
+        deserialize_f32, deserialize_f64, deserialize_char, deserialize_str, deserialize_string, 
+        deserialize_bytes, deserialize_byte_buf, deserialize_option, deserialize_unit, 
+        deserialize_seq, deserialize_map, deserialize_identifier, deserialize_ignored_any
+    );
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        PackageDeserializer::new(self, false).deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        PackageDeserializer::new(self, false).deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        PackageDeserializer::new(self, false).deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        PackageDeserializer::new(self, false).deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        PackageDeserializer::new(self, false).deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        PackageDeserializer::new(self, false).deserialize_enum(name, variants, visitor)
+    }
+}
+
+impl<'de> Deserializer<'de> for PackageDeserializer {
+    type Error = PackageDeserializerError;
+
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> 
     {
-        match self {
+        match &self.package {
             Package::Empty => self.deserialize_unit(visitor),
+            Package::Integer(_) => self.deserialize_i64(visitor),
             Package::Number(_) => self.deserialize_f64(visitor),
             Package::String(_) => self.deserialize_string(visitor),
             Package::Boolean(_) => self.deserialize_bool(visitor),
             Package::Bytes(_) => self.deserialize_bytes(visitor),
             Package::Array(_) => self.deserialize_seq(visitor),
-            Package::Object(_) => self.deserialize_map(visitor)
+            Package::Object(_) => self.deserialize_map(visitor),
+            Package::EndOfStream => visitor.visit_unit()
         }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_bool()?;
+        let value = self.package.get_bool()?;
         visitor.visit_bool(value)
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as i8;
+        if self.strict {
+            return visitor.visit_i8(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()? as i8;
         visitor.visit_i8(value)
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as i16;
+        if self.strict {
+            return visitor.visit_i16(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()? as i16;
         visitor.visit_i16(value)
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-            let value = self.get_number()? as i32;
+            if self.strict {
+            return visitor.visit_i32(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()? as i32;
             visitor.visit_i32(value)
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as i64;
+        if self.strict {
+            return visitor.visit_i64(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()?;
         visitor.visit_i64(value)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as u8;
+        if self.strict {
+            return visitor.visit_u8(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()? as u8;
         visitor.visit_u8(value)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as u16;
+        if self.strict {
+            return visitor.visit_u16(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()? as u16;
         visitor.visit_u16(value)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as u32;
+        if self.strict {
+            return visitor.visit_u32(self.strict_integer()?);
+        }
+        let value = self.package.get_i64()? as u32;
         visitor.visit_u32(value)
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as u64;
+        let strict = self.strict;
+        let value = match self.package {
+            // numbers greater than i64::MAX still fit in a u64
+            Package::Number(number) if number.fract() == 0.0 && number >= 0.0 && number < u64::MAX as f64 => {
+                number as u64
+            },
+            package if strict => PackageDeserializer::new(package, strict).strict_integer()?,
+            package => package.get_i64()? as u64,
+        };
         visitor.visit_u64(value)
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()? as f32;
+        let value = self.package.get_number()? as f32;
         visitor.visit_f32(value)
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_number()?;
+        let value = self.package.get_number()?;
         visitor.visit_f64(value)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let string = self.get_string()?;
+        let string = self.package.get_string()?;
         let mut chars = string.chars();
         if let (Some(char), None) = (chars.next(), chars.next()){
             visitor.visit_char(char)
@@ -149,35 +314,35 @@ impl<'de> Deserializer<'de> for Package {
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_string()?;
+        let value = self.package.get_string()?;
         visitor.visit_str(&value)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_string()?;
+        let value = self.package.get_string()?;
         visitor.visit_string(value)
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_bytes()?;
+        let value = self.package.get_bytes()?;
         visitor.visit_bytes(&value)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_bytes()?;
+        let value = self.package.get_bytes()?;
         visitor.visit_byte_buf(value)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        if self.is_empty() {
+        if self.package.is_empty() || self.package.is_eos() {
             visitor.visit_none()
         } else {
             visitor.visit_some(self)
@@ -187,7 +352,7 @@ impl<'de> Deserializer<'de> for Package {
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        self.get_empty()?;
+        self.package.get_empty()?;
         visitor.visit_unit()
     }
 
@@ -198,7 +363,7 @@ impl<'de> Deserializer<'de> for Package {
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.get_string()?;
+        let value = self.package.get_string()?;
         if name == value {
             visitor.visit_unit()
         } else {
@@ -213,10 +378,10 @@ impl<'de> Deserializer<'de> for Package {
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let mut value = self.get_object()?;
+        let mut value = self.package.get_object()?;
         if value.len() == 1  {
             if let Some(package) = value.remove(name) {
-                visitor.visit_newtype_struct(package)
+                visitor.visit_newtype_struct(PackageDeserializer::new(package, self.strict))
             } else {
                 Err(PackageDeserializerError { cause: format!("Object cannot be parsed into struct '{name}' because not have that entry") })
             }
@@ -228,13 +393,13 @@ impl<'de> Deserializer<'de> for Package {
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        visitor.visit_seq(DiscompoundArray::create(self, None, None)?)
+        visitor.visit_seq(DiscompoundArray::create(self.package, self.strict, None, None)?)
     }
 
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        visitor.visit_seq(DiscompoundArray::create(self, None, Some(len))?)
+        visitor.visit_seq(DiscompoundArray::create(self.package, self.strict, None, Some(len))?)
     }
 
     fn deserialize_tuple_struct<V>(
@@ -245,26 +410,26 @@ impl<'de> Deserializer<'de> for Package {
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        visitor.visit_seq(DiscompoundArray::create(self, Some(name), Some(len))?)
+        visitor.visit_seq(DiscompoundArray::create(self.package, self.strict, Some(name), Some(len))?)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let object = self.get_object()?;
-        visitor.visit_map(DiscompoundObject::create(object))
+        let object = self.package.get_object()?;
+        visitor.visit_map(DiscompoundObject::create(object, self.strict))
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let object = self.get_object()?;
-        visitor.visit_map(DiscompoundObject::create(object))
+        let object = self.package.get_object()?;
+        visitor.visit_map(DiscompoundObject::create_struct(object, fields, self.strict))
     }
 
     fn deserialize_enum<V>(
@@ -276,7 +441,7 @@ impl<'de> Deserializer<'de> for Package {
     where
         V: serde::de::Visitor<'de> 
     {
-        let (variant, value) = match self {
+        let (variant, value) = match self.package {
             Package::Object(value) => {
                 let mut iter = value.into_iter();
                 let (variant, value) = match iter.next() {
@@ -305,7 +470,7 @@ impl<'de> Deserializer<'de> for Package {
             }
         };
 
-        visitor.visit_enum(EnumDeserializer { variant, value })
+        visitor.visit_enum(EnumDeserializer { variant, value, strict: self.strict })
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -324,6 +489,7 @@ impl<'de> Deserializer<'de> for Package {
 struct EnumDeserializer {
     variant: String,
     value: Option<Package>,
+    strict: bool,
 }
 impl<'de> EnumAccess<'de> for EnumDeserializer {
     type Error = PackageDeserializerError;
@@ -334,13 +500,14 @@ impl<'de> EnumAccess<'de> for EnumDeserializer {
         V: DeserializeSeed<'de>,
     {
         let variant = self.variant.into_deserializer();
-        let visitor = VariantDeserializer { value: self.value };
+        let visitor = VariantDeserializer { value: self.value, strict: self.strict };
         seed.deserialize(variant).map(|v| (v, visitor))
     }
 }
 
 struct VariantDeserializer {
     value: Option<Package>,
+    strict: bool,
 }
 
 impl<'de> VariantAccess<'de> for VariantDeserializer {
@@ -348,7 +515,7 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
 
     fn unit_variant(self) -> Result<(), Self::Error> {
         match self.value {
-            Some(value) => Deserialize::deserialize(value),
+            Some(value) => Deserialize::deserialize(PackageDeserializer::new(value, self.strict)),
             None => Ok(()),
         }
     }
@@ -357,7 +524,7 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
     where
         T: DeserializeSeed<'de> {
         match self.value {
-            Some(value) => seed.deserialize(value),
+            Some(value) => seed.deserialize(PackageDeserializer::new(value, self.strict)),
             None => {
                 Err(PackageDeserializerError { 
                     cause: "Expect type variant".to_owned()
@@ -371,7 +538,7 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
         V: serde::de::Visitor<'de> {
         match self.value {
             Some(package) => {
-                visitor.visit_seq(DiscompoundArray::create(package, None, Some(len))?)
+                visitor.visit_seq(DiscompoundArray::create(package, self.strict, None, Some(len))?)
             }
             _ => {
                 Err(PackageDeserializerError { 
@@ -390,7 +557,7 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
         V: serde::de::Visitor<'de> {
         match self.value {
             Some(Package::Object(object)) => {
-                visitor.visit_map(DiscompoundObject::create(object))
+                visitor.visit_map(DiscompoundObject::create(object, self.strict))
             }
             _ => {
                 Err(PackageDeserializerError { 
@@ -402,10 +569,11 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
 }
 
 struct DiscompoundArray {
-    data: Vec<Package>
+    data: Vec<Package>,
+    strict: bool
 }
 impl DiscompoundArray {
-    pub fn create(package: Package, name: Option<&'static str>, len: Option<usize>) -> 
+    pub fn create(package: Package, strict: bool, name: Option<&'static str>, len: Option<usize>) -> 
         Result<Self, PackageDeserializerError>
     {   
         let package = if let Some(name) = name {
@@ -436,7 +604,7 @@ impl DiscompoundArray {
 
         package.reverse();
         Ok(
-            Self { data: package }
+            Self { data: package, strict }
         )
     }
 }
@@ -450,7 +618,7 @@ impl<'de> SeqAccess<'de> for DiscompoundArray {
         T: serde::de::DeserializeSeed<'de> 
     {
         if let Some(package) = self.data.pop() {
-            let value = seed.deserialize(package)?;
+            let value = seed.deserialize(PackageDeserializer::new(package, self.strict))?;
             Ok(Some(value))
         } else {
             Ok(None)
@@ -459,13 +627,27 @@ impl<'de> SeqAccess<'de> for DiscompoundArray {
 }
 
 struct DiscompoundObject {
-    data: IntoIter<String, Package>,
-    last: Option<Package>
+    data: IntoIter<(String, Package)>,
+    last: Option<Package>,
+    strict: bool
 }
 
 impl DiscompoundObject {
-    pub fn create(object: HashMap<String, Package>) -> Self {
-        Self { data: object.into_iter(), last: None }
+    pub fn create(object: HashMap<String, Package>, strict: bool) -> Self {
+        let data = object.into_iter().collect::<Vec<_>>();
+        Self { data: data.into_iter(), last: None, strict }
+    }
+
+    /// Iterate the entries of fields in the declared order, and after the others
+    /// entries ordered by key. A missing field is reported by the visitor.
+    pub fn create_struct(mut object: HashMap<String, Package>, fields: &[&str], strict: bool) -> Self {
+        let mut data = fields.iter()
+            .filter_map(|field| object.remove_entry(*field))
+            .collect::<Vec<_>>();
+        let mut others = object.into_iter().collect::<Vec<_>>();
+        others.sort_by(|(a, _), (b, _)| a.cmp(b));
+        data.extend(others);
+        Self { data: data.into_iter(), last: None, strict }
     }
 }
 impl<'de> MapAccess<'de> for DiscompoundObject {
@@ -490,7 +672,7 @@ impl<'de> MapAccess<'de> for DiscompoundObject {
     where
         V: DeserializeSeed<'de> {
         match self.last.take() {
-            Some(value) => seed.deserialize(value),
+            Some(value) => seed.deserialize(PackageDeserializer::new(value, self.strict)),
             None => Err(PackageDeserializerError { 
                 cause: "Value is missing".to_owned()
             }),
@@ -501,8 +683,8 @@ impl<'de> MapAccess<'de> for DiscompoundObject {
 
 // region: impl MapKeyDeserializer
 
-struct MapKeyDeserializer {
-    key: String
+pub(super) struct MapKeyDeserializer {
+    pub(super) key: String
 }
 
 impl<'de> Deserializer<'de> for MapKeyDeserializer {
@@ -649,7 +831,7 @@ impl<'de> Deserializer<'de> for MapKeyDeserializer {
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        if self.key == "" {
+        if self.key.is_empty() {
             visitor.visit_none()
         } else {
             visitor.visit_some(self)
@@ -659,7 +841,7 @@ impl<'de> Deserializer<'de> for MapKeyDeserializer {
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        if self.key == "" {
+        if self.key.is_empty() {
             visitor.visit_unit()
         } else {
             Err(PackageDeserializerError { cause: "Key could not be parsed".to_owned() })
@@ -1,5 +1,7 @@
+mod borrowed;
 mod deserializer;
 mod serializer;
 
+pub use borrowed::*;
 pub use deserializer::*;
 pub use serializer::*;
@@ -29,7 +29,23 @@ impl serde::ser::Error for PackageSerializerError {
 }
 
 pub fn serialize<T: Serialize>(value: T) -> Result<Package, PackageSerializerError> {
-    value.serialize(PackageSerializer)
+    value.serialize(PackageSerializer { max_depth: None })
+}
+
+pub fn serialize_with_limit<T: Serialize>(
+    value: T,
+    max_depth: usize,
+) -> Result<Package, PackageSerializerError> {
+    value.serialize(PackageSerializer {
+        max_depth: Some(max_depth),
+    })
+}
+
+/// Name of the unit struct used to represent a [Package::EndOfStream] on serialization
+const END_OF_STREAM: &str = "$rs_flow::EndOfStream";
+
+pub(crate) fn serialize_end_of_stream<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_unit_struct(END_OF_STREAM)
 }
 
 // region: MapKeySerializer
@@ -110,9 +126,9 @@ impl Serializer for MapKeySerializer {
         Ok("".to_string())
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(self)
     }
@@ -134,20 +150,20 @@ impl Serializer for MapKeySerializer {
         Ok(variant.to_string())
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         Err(PackageSerializerError {
             cause: "Variant cannot be serialized into string".to_owned(),
         })
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -155,7 +171,7 @@ impl Serializer for MapKeySerializer {
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         Err(PackageSerializerError {
             cause: "Variant cannot be serialized into string".to_owned(),
@@ -229,9 +245,9 @@ impl SerializeSeq for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -244,9 +260,9 @@ impl SerializeTuple for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -260,9 +276,9 @@ impl SerializeTupleStruct for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -276,9 +292,9 @@ impl SerializeTupleVariant for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -292,16 +308,16 @@ impl SerializeMap for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -315,13 +331,9 @@ impl SerializeStruct for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -335,13 +347,9 @@ impl SerializeStructVariant for Impossible {
     type Ok = String;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unreachable!()
     }
@@ -353,15 +361,35 @@ impl SerializeStructVariant for Impossible {
 // endregion
 
 // region: PackageSerializer
-struct PackageSerializer;
+#[derive(Clone, Copy)]
+struct PackageSerializer {
+    /// Remaining levels of nesting allowed, `None` means unlimited
+    max_depth: Option<usize>,
+}
+
+impl PackageSerializer {
+    /// Serializer used for the values nested one level below this one
+    fn nested(self) -> Result<Self, PackageSerializerError> {
+        match self.max_depth {
+            Some(0) => Err(PackageSerializerError {
+                cause: "max depth exceeded".to_string(),
+            }),
+            max_depth => Ok(PackageSerializer {
+                max_depth: max_depth.map(|depth| depth - 1),
+            }),
+        }
+    }
+}
 
 struct CompoundArray {
     name: Option<String>,
     data: Vec<Package>,
+    serializer: PackageSerializer,
 }
 struct CompoundObjects {
     name: Option<String>,
     data: HashMap<String, Package>,
+    serializer: PackageSerializer,
 }
 
 impl Serializer for PackageSerializer {
@@ -436,9 +464,9 @@ impl Serializer for PackageSerializer {
         Ok(Package::Empty)
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         value.serialize(self)
     }
@@ -448,6 +476,9 @@ impl Serializer for PackageSerializer {
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        if name == END_OF_STREAM {
+            return Ok(Package::EndOfStream);
+        }
         Ok(Package::String(name.to_string()))
     }
 
@@ -460,20 +491,20 @@ impl Serializer for PackageSerializer {
         Ok(Package::Empty)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(self)?;
+        let value = value.serialize(self.nested()?)?;
         let key = name.to_string();
         Ok(Package::Object(HashMap::from([(key, value)])))
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -481,9 +512,9 @@ impl Serializer for PackageSerializer {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(self)?;
+        let value = value.serialize(self.nested()?)?;
         let key = variant.to_string();
         Ok(Package::Object(HashMap::from([(key, value)])))
     }
@@ -493,6 +524,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundArray {
             name: None,
             data: Vec::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 
@@ -500,6 +532,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundArray {
             name: None,
             data: Vec::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 
@@ -511,6 +544,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundArray {
             name: Some(name.to_owned()),
             data: Vec::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 
@@ -524,6 +558,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundArray {
             name: Some(variant.to_string()),
             data: Vec::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 
@@ -532,6 +567,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundObjects {
             name: None,
             data: HashMap::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 
@@ -543,6 +579,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundObjects {
             name: None,
             data: HashMap::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 
@@ -556,6 +593,7 @@ impl Serializer for PackageSerializer {
         Ok(CompoundObjects {
             name: Some(variant.to_string()),
             data: HashMap::with_capacity(len),
+            serializer: self.nested()?,
         })
     }
 }
@@ -564,11 +602,11 @@ impl SerializeSeq for CompoundArray {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.push(value);
         Ok(())
     }
@@ -582,11 +620,11 @@ impl SerializeTuple for CompoundArray {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.push(value);
         Ok(())
     }
@@ -600,11 +638,11 @@ impl SerializeTupleStruct for CompoundArray {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.push(value);
         Ok(())
     }
@@ -626,11 +664,11 @@ impl SerializeTupleVariant for CompoundArray {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.push(value);
         Ok(())
     }
@@ -652,17 +690,13 @@ impl SerializeMap for CompoundObjects {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_entry<K: ?Sized, V: ?Sized>(
-        &mut self,
-        key: &K,
-        value: &V,
-    ) -> Result<(), Self::Error>
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
     where
-        K: serde::Serialize,
-        V: serde::Serialize,
+        K: ?Sized + serde::Serialize,
+        V: ?Sized + serde::Serialize,
     {
         let key = key.serialize(MapKeySerializer)?;
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.insert(key, value);
         Ok(())
     }
@@ -671,15 +705,15 @@ impl SerializeMap for CompoundObjects {
         Ok(Package::Object(self.data))
     }
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unimplemented!()
     }
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
         unimplemented!()
     }
@@ -689,21 +723,17 @@ impl SerializeStruct for CompoundObjects {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.insert(key.to_owned(), value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Package::Object(HashMap::from(self.data)))
+        Ok(Package::Object(self.data))
     }
 }
 
@@ -711,26 +741,22 @@ impl SerializeStructVariant for CompoundObjects {
     type Ok = Package;
     type Error = PackageSerializerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        let value = value.serialize(PackageSerializer)?;
+        let value = value.serialize(self.serializer)?;
         self.data.insert(key.to_owned(), value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
         if let Some(name) = self.name {
-            let data = Package::Object(HashMap::from(self.data));
+            let data = Package::Object(self.data);
             let data = Package::Object(HashMap::from([(name, data)]));
             return Ok(data);
         }
-        Ok(Package::Object(HashMap::from(self.data)))
+        Ok(Package::Object(self.data))
     }
 }
 
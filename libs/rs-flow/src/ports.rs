@@ -1,13 +1,19 @@
+use std::borrow::Cow;
+
 use serde::{Deserialize, Serialize};
 
+use crate::package::{PackageKind, PackageSchema};
+
 pub type PortId = u16;
 
 ///
 /// One of the [Ports] of a [Component](crate::component::Component)
 ///
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Port {
-    /// [Port] id, indentify a Input/Outpot [Port] of a [Component](crate::component::Component)
+    /// [Port] id, indentify a Input/Outpot [Port] of a [Component](crate::component::Component).
+    /// When use #[derive(Inputs, Outputs)] that is the first id not used, in the order of variants,
+    /// or is defined with the attribute `#[port(id = ...)]`, so reorder variants not change it.
     pub port: PortId,
 
     /// A Name given for this port. When use #[derive(Inputs, Outputs)] that label is created as the type name.
@@ -15,6 +21,25 @@ pub struct Port {
 
     /// Description of what mean a [Package](crate::package::Package) send/recieve by this [Port]
     pub description: Option<&'static str>,
+
+    /// A optional input [Port] not need packages for the [Component](crate::component::Component)
+    /// be ready to run, and a optional output [Port] can be unconnected when the
+    /// [Flow](crate::flow::Flow) run with [strict outputs](crate::flow::Flow::strict_outputs).
+    /// When use #[derive(Inputs, Outputs)] that is defined with the attribute `#[optional]`.
+    #[serde(default)]
+    pub optional: bool,
+
+    /// Expected shape of the [Package](crate::package::Package)'s sent/received by this [Port],
+    /// only a metadata that is not checked when the [Flow](crate::flow::Flow) run.
+    /// When use #[derive(Inputs, Outputs)] that is defined with the attribute `#[schema(...)]`.
+    #[serde(skip_deserializing)]
+    pub schema: Option<PackageSchema>,
+
+    /// [PackageKind] of the [Package](crate::package::Package)'s sent/received by this [Port],
+    /// a output and a input with different kinds can not be connected.
+    /// When use #[derive(Inputs, Outputs)] that is defined with the attribute `#[kind(...)]`.
+    #[serde(default)]
+    pub kind: Option<PackageKind>,
 }
 
 impl Port {
@@ -24,6 +49,9 @@ impl Port {
             port,
             label: None,
             description: None,
+            optional: false,
+            schema: None,
+            kind: None,
         }
     }
     /// Define a [Port] with all.
@@ -36,22 +64,41 @@ impl Port {
             port,
             label: Some(label),
             description,
+            optional: false,
+            schema: None,
+            kind: None,
         }
     }
+    /// Define this [Port] as optional
+    pub const fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+    /// Define the expected shape of packages of this [Port]
+    pub const fn schema(mut self, schema: PackageSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+    /// Define the [PackageKind] of packages of this [Port]
+    pub const fn kind(mut self, kind: PackageKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
 }
 
 ///
 /// Set of [Port]'s, can represent all [Inputs] or [Outputs] of a [Component](crate::component::Component)
 ///
-#[derive(Debug, Serialize)]
-pub struct Ports(&'static [Port]);
+#[derive(Debug, Clone, Serialize)]
+pub struct Ports(Cow<'static, [Port]>);
 
 impl Ports {
     /// Create a new Ports
     ///
     /// # Panics
     ///
-    /// Panic if found two [Port]'s if with same [PortId] or same label
+    /// Panic if found two [Port]'s if with same [PortId] or same label,
+    /// use [Ports::try_new] to known which port collided
     ///
     pub const fn new(ports: &'static [Port]) -> Self {
         let length = ports.len();
@@ -62,21 +109,77 @@ impl Ports {
                 if ports[i].port == ports[j].port {
                     panic!("Found ports with same id")
                 }
+                if let (Some(a), Some(b)) = (ports[i].label, ports[j].label) {
+                    if same_label(a, b) {
+                        panic!("Found ports with same label")
+                    }
+                }
                 j += 1;
             }
             i += 1;
         }
-        Self(ports)
+        Self(Cow::Borrowed(ports))
+    }
+
+    /// Create a Ports, returning a error with the [PortId] or label of the
+    /// first [Port] that collide with a previous one
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    /// use rs_flow::ports::PortsError;
+    ///
+    /// let ports = Ports::try_new(vec![Port::from(0, "A", None), Port::from(1, "B", None)]);
+    /// assert_eq!(ports.unwrap().len(), 2);
+    ///
+    /// let ports = Ports::try_new(vec![Port::from(0, "A", None), Port::from(0, "B", None)]);
+    /// assert_eq!(ports.unwrap_err(), PortsError::DuplicateId { port: 0 });
+    ///
+    /// let ports = Ports::try_new(vec![Port::from(0, "A", None), Port::from(1, "A", None)]);
+    /// assert_eq!(ports.unwrap_err(), PortsError::DuplicateLabel { label: "A" });
+    /// ```
+    pub fn try_new(ports: Vec<Port>) -> Result<Self, PortsError> {
+        for (i, port) in ports.iter().enumerate() {
+            for other in ports[..i].iter() {
+                if other.port == port.port {
+                    return Err(PortsError::DuplicateId { port: port.port });
+                }
+                if let (Some(label), Some(other)) = (port.label, other.label) {
+                    if label == other {
+                        return Err(PortsError::DuplicateLabel { label });
+                    }
+                }
+            }
+        }
+        Ok(Self(Cow::Owned(ports)))
+    }
+
+    /// Create a Ports computed at runtime, like a number of ports defined in
+    /// the construction of [Component](crate::component::Component)
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    ///
+    /// let ports = Ports::owned((0..3).map(Port::new).collect());
+    /// assert_eq!(ports.len(), 3);
+    /// assert!(ports.contains(2));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panic if found two [Port]'s if with same [PortId] or same label, see [Ports::try_new]
+    ///
+    pub fn owned(ports: Vec<Port>) -> Self {
+        Self::try_new(ports).unwrap_or_else(|error| panic!("{error}"))
     }
 
     /// Create a empty Ports
     pub fn empty() -> Self {
-        Self(&[])
+        Self(Cow::Borrowed(&[]))
     }
 
     /// Return if Ports is empty
     pub fn is_empty(&self) -> bool {
-        return self.0.is_empty();
+        self.0.is_empty()
     }
 
     /// Return if exist a Port with a PortId
@@ -93,6 +196,65 @@ impl Ports {
     pub fn iter(&self) -> impl Iterator<Item = &Port> {
         self.0.iter()
     }
+
+    /// Return the number of ports
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return the Port with a PortId, or [None] if not exist
+    pub fn get(&self, port: PortId) -> Option<&Port> {
+        self.0.iter().find(|p| p.port == port)
+    }
+
+    /// Return the PortId of the Port with a label, or [None] if not exist
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    ///
+    /// #[derive(Outputs)]
+    /// enum Out {
+    ///     Ok,
+    ///     Error,
+    /// }
+    ///
+    /// assert_eq!(Out::PORTS.len(), 2);
+    /// assert_eq!(Out::PORTS.port_id_of("Error"), Some(Out::Error.into_port()));
+    /// assert_eq!(Out::PORTS.port_id_of("Missing"), None);
+    /// assert_eq!(Out::PORTS.get(Out::Ok.into_port()).unwrap().label, Some("Ok"));
+    /// ```
+    pub fn port_id_of(&self, label: &str) -> Option<PortId> {
+        self.0
+            .iter()
+            .find(|p| p.label.is_some_and(|l| l == label))
+            .map(|p| p.port)
+    }
+}
+
+/// Error of [Ports] with a collision between two [Port]'s, see [Ports::try_new]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PortsError {
+    #[error("Found ports with same id = {port:?}")]
+    DuplicateId { port: PortId },
+
+    #[error("Found ports with same label = {label:?}")]
+    DuplicateLabel { label: &'static str },
+}
+
+/// Compare two labels in a const context
+const fn same_label(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
 }
 
 ///
@@ -139,11 +301,22 @@ pub trait Inputs {
     const PORTS: Ports;
 
     /// Return a input [PortId] of a [Component](crate::component::Component)
+    #[allow(clippy::wrong_self_convention)]
     fn into_port(&self) -> PortId;
 }
 
+/// A [PortId] receive from the input port with this id, usefull for [Ports]
+/// computed at runtime, see [ComponentSchema::inputs](crate::component::ComponentSchema::inputs)
+impl Inputs for PortId {
+    const PORTS: Ports = Ports(Cow::Borrowed(&[]));
+
+    fn into_port(&self) -> PortId {
+        *self
+    }
+}
+
 impl Inputs for () {
-    const PORTS: Ports = Ports(&[]);
+    const PORTS: Ports = Ports(Cow::Borrowed(&[]));
 
     fn into_port(&self) -> PortId {
         panic!("Component not have a input port");
@@ -205,11 +378,22 @@ pub trait Outputs {
     const PORTS: Ports;
 
     /// Return a output [PortId] of a [Component](crate::component::Component)
+    #[allow(clippy::wrong_self_convention)]
     fn into_port(&self) -> PortId;
 }
 
+/// A [PortId] send to the output port with this id, usefull for [Ports]
+/// computed at runtime, see [ComponentSchema::outputs](crate::component::ComponentSchema::outputs)
+impl Outputs for PortId {
+    const PORTS: Ports = Ports(Cow::Borrowed(&[]));
+
+    fn into_port(&self) -> PortId {
+        *self
+    }
+}
+
 impl Outputs for () {
-    const PORTS: Ports = Ports(&[]);
+    const PORTS: Ports = Ports(Cow::Borrowed(&[]));
 
     fn into_port(&self) -> PortId {
         panic!("Component not have a output port");
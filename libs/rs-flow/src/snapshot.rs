@@ -0,0 +1,42 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::component::Id;
+use crate::connection::Point;
+use crate::package::Package;
+
+///
+/// Packages sent by each output port of the [Component](crate::component::Component)'s in a
+/// run, used by [Flow::run_incremental](crate::flow::Flow::run_incremental) to reuse the
+/// outputs of components that not changed.
+///
+/// A default snapshot not have any component, so a incremental run with it run all
+/// components of the flow.
+///
+#[derive(Debug, Clone, Default)]
+pub struct FlowSnapshot {
+    components: HashSet<Id>,
+    outputs: HashMap<Point, Vec<Package>>,
+}
+
+impl FlowSnapshot {
+    pub(crate) fn new(components: HashSet<Id>, outputs: HashMap<Point, Vec<Package>>) -> Self {
+        Self {
+            components,
+            outputs,
+        }
+    }
+
+    /// Return if the outputs of the component are in this snapshot
+    pub fn contains(&self, id: Id) -> bool {
+        self.components.contains(&id)
+    }
+
+    /// Return all packages sent by a output port in the order that they were sent
+    pub fn outputs(&self, point: Point) -> &[Package] {
+        self.outputs.get(&point).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Point, &Vec<Package>)> {
+        self.outputs.iter()
+    }
+}
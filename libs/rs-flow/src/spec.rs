@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::component::{Component, ComponentSchema, Id, Type};
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::flow::Flow;
+use crate::package::Package;
+use crate::ports::Port;
+
+///
+/// A serializable description of a [Component](crate::component::Component),
+/// identified by your id and the type of your data, with the config used to create it.
+/// The config can also be deserialized from a field named `data`.
+///
+/// The ports are only informative, for tools like editors, so they are serialized
+/// but not deserialized, becaurse a component created from a spec has the ports of your type.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentSpec {
+    pub id: Id,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    #[serde(default, alias = "data")]
+    pub config: Package,
+    #[serde(default)]
+    pub ty: Type,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<Port>,
+    #[serde(default, skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<Port>,
+}
+
+impl ComponentSpec {
+    /// Create a component description without config
+    pub fn new(id: Id, type_name: impl Into<String>) -> Self {
+        Self {
+            id,
+            type_name: type_name.into(),
+            config: Package::Empty,
+            ty: Type::default(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Create the description of a [Component], with the config used to create it
+    /// if was created by a [ComponentRegistry]
+    pub fn of<G>(component: &Component<G>) -> Self {
+        Self {
+            id: component.id,
+            type_name: component.type_name.to_string(),
+            config: component.config.clone(),
+            ty: component.ty,
+            inputs: component.inputs.iter().cloned().collect(),
+            outputs: component.outputs.iter().cloned().collect(),
+        }
+    }
+
+    /// Define the config of component
+    pub fn with_config(mut self, config: Package) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+///
+/// A serializable description of a [Flow](crate::flow::Flow), with your components and connections.
+///
+/// ```
+/// use rs_flow::connection::Connection;
+/// use rs_flow::spec::{ComponentSpec, FlowSpec};
+///
+/// let before = FlowSpec {
+///     components: vec![ComponentSpec::new(1, "Source"), ComponentSpec::new(2, "Sink")],
+///     connections: vec![Connection::new(1, 0, 2, 0)],
+/// };
+/// let mut after = before.clone();
+/// after.components.push(ComponentSpec::new(3, "Sink"));
+///
+/// let diff = before.diff(&after);
+/// assert_eq!(diff.added_components, vec![ComponentSpec::new(3, "Sink")]);
+/// assert!(diff.removed_components.is_empty());
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FlowSpec {
+    pub components: Vec<ComponentSpec>,
+    pub connections: Vec<Connection>,
+}
+
+impl FlowSpec {
+    fn component(&self, id: Id) -> Option<&ComponentSpec> {
+        self.components.iter().find(|component| component.id == id)
+    }
+
+    /// Return the structural differences from this spec to the `other`.
+    ///
+    /// Components are compared by id and type, a component with the same id
+    /// but other type is reported as removed and added.
+    pub fn diff(&self, other: &FlowSpec) -> FlowDiff {
+        let same_component =
+            |a: &ComponentSpec, b: &ComponentSpec| a.id == b.id && a.type_name == b.type_name;
+
+        let mut diff = FlowDiff::default();
+        for component in &self.components {
+            match other.component(component.id) {
+                Some(new) if same_component(component, new) => {
+                    if component.config != new.config {
+                        diff.changed_configs.push(ConfigChange {
+                            id: component.id,
+                            before: component.config.clone(),
+                            after: new.config.clone(),
+                        });
+                    }
+                }
+                _ => diff.removed_components.push(component.clone()),
+            }
+        }
+        for component in &other.components {
+            match self.component(component.id) {
+                Some(old) if same_component(old, component) => {}
+                _ => diff.added_components.push(component.clone()),
+            }
+        }
+
+        diff.removed_connections = self
+            .connections
+            .iter()
+            .filter(|connection| !other.connections.contains(connection))
+            .cloned()
+            .collect();
+        diff.added_connections = other
+            .connections
+            .iter()
+            .filter(|connection| !self.connections.contains(connection))
+            .cloned()
+            .collect();
+
+        diff.sort();
+        diff
+    }
+}
+
+/// The config of a component that changed between two [FlowSpec]'s
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub id: Id,
+    pub before: Package,
+    pub after: Package,
+}
+
+///
+/// Structural differences between two [FlowSpec]'s, created by [FlowSpec::diff]
+///
+/// The [Display] show one difference by line, prefixed by `+` if added,
+/// `-` if removed and `~` if changed.
+///
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FlowDiff {
+    pub added_components: Vec<ComponentSpec>,
+    pub removed_components: Vec<ComponentSpec>,
+    pub changed_configs: Vec<ConfigChange>,
+    pub added_connections: Vec<Connection>,
+    pub removed_connections: Vec<Connection>,
+}
+
+impl FlowDiff {
+    /// Return if the specs compared are structurally equals
+    pub fn is_empty(&self) -> bool {
+        self.added_components.is_empty()
+            && self.removed_components.is_empty()
+            && self.changed_configs.is_empty()
+            && self.added_connections.is_empty()
+            && self.removed_connections.is_empty()
+    }
+
+    fn sort(&mut self) {
+        let connection_key = |c: &Connection| (c.from, c.out_port, c.to, c.in_port);
+
+        self.added_components.sort_by_key(|component| component.id);
+        self.removed_components
+            .sort_by_key(|component| component.id);
+        self.changed_configs.sort_by_key(|change| change.id);
+        self.added_connections.sort_by_key(connection_key);
+        self.removed_connections.sort_by_key(connection_key);
+    }
+}
+
+impl Display for FlowDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for component in &self.removed_components {
+            writeln!(f, "- component {} ({})", component.id, component.type_name)?;
+        }
+        for component in &self.added_components {
+            writeln!(f, "+ component {} ({})", component.id, component.type_name)?;
+        }
+        for change in &self.changed_configs {
+            writeln!(
+                f,
+                "~ component {} config: {:?} -> {:?}",
+                change.id, change.before, change.after
+            )?;
+        }
+        for c in &self.removed_connections {
+            writeln!(
+                f,
+                "- connection {}:{} -> {}:{}",
+                c.from, c.out_port, c.to, c.in_port
+            )?;
+        }
+        for c in &self.added_connections {
+            writeln!(
+                f,
+                "+ connection {}:{} -> {}:{}",
+                c.from, c.out_port, c.to, c.in_port
+            )?;
+        }
+        Ok(())
+    }
+}
+
+type Constructor<G> = Box<dyn Fn(Id, Package) -> Result<Component<G>> + Send + Sync>;
+
+///
+/// Registry of the component types that can be created from a [ComponentSpec], each type
+/// is created deserializing the [config](ComponentSpec::config) of spec in the component data.
+///
+/// ```
+/// use rs_flow::prelude::*;
+/// use rs_flow::spec::{ComponentRegistry, ComponentSpec, FlowSpec};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Add {
+///     value: f64,
+/// }
+///
+/// #[async_trait]
+/// impl ComponentSchema for Add {
+///     type Inputs = ();
+///     type Outputs = ();
+///     type Global = f64;
+///
+///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+///         ctx.with_mut_global(|total| *total += self.value)?;
+///         Ok(Next::Continue)
+///     }
+/// }
+///
+/// let registry = ComponentRegistry::new().register::<Add>("Add");
+/// let spec = FlowSpec {
+///     components: vec![
+///         ComponentSpec::new(1, "Add").with_config(Package::object([("value", 2)])),
+///         ComponentSpec::new(2, "Add").with_config(Package::object([("value", 3)])),
+///     ],
+///     connections: vec![],
+/// };
+///
+/// # tokio_test::block_on(async {
+/// let total = registry.build(&spec)?.run(0.0).await?;
+/// assert_eq!(total, 5.0);
+/// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+/// # });
+/// ```
+///
+pub struct ComponentRegistry<G> {
+    constructors: HashMap<String, Constructor<G>>,
+}
+
+impl<G> Default for ComponentRegistry<G>
+where
+    G: Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> ComponentRegistry<G>
+where
+    G: Send + Sync + 'static,
+{
+    /// Create a registry without types
+    pub fn new() -> Self {
+        Self {
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Register a component type with a name, a type registered again with
+    /// the same name replace the previous.
+    ///
+    /// The components created have the name as [type name](ComponentSchema::type_name),
+    /// so the spec returned by [Flow::to_spec] can be built again by this registry.
+    pub fn register<T>(mut self, type_name: &str) -> Self
+    where
+        T: ComponentSchema<Global = G> + DeserializeOwned,
+    {
+        let name = type_name.to_owned();
+        let constructor = move |id: Id, config: Package| {
+            let data = config
+                .clone()
+                .try_into::<T>()
+                .map_err(|error| Error::InvalidComponentConfig { id, error })?;
+            Ok(Component::new(id, data)
+                .with_type_name(name.clone())
+                .with_config(config))
+        };
+        self.constructors
+            .insert(type_name.to_owned(), Box::new(constructor));
+        self
+    }
+
+    /// Return if a component type is registered with the name
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.constructors.contains_key(type_name)
+    }
+
+    /// Create a [Component] from your spec, with the [Type] of spec
+    ///
+    /// # Error
+    ///
+    /// - Error [ComponentTypeNotRegistered](crate::error::Error::ComponentTypeNotRegistered)
+    ///   if the type of spec is not registered
+    /// - Error [InvalidComponentConfig](crate::error::Error::InvalidComponentConfig)
+    ///   if the config could not be deserialized in the component type
+    pub fn create(&self, spec: &ComponentSpec) -> Result<Component<G>> {
+        let constructor = self.constructors.get(&spec.type_name).ok_or_else(|| {
+            Error::ComponentTypeNotRegistered {
+                type_name: spec.type_name.clone(),
+            }
+        })?;
+        let mut component = constructor(spec.id, spec.config.clone())?;
+        component.ty = spec.ty;
+        Ok(component)
+    }
+
+    /// Create a [Flow] with all components and connections of the spec
+    ///
+    /// # Error
+    ///
+    /// - Same errors of [ComponentRegistry::create]
+    /// - Same errors of [Flow::from_parts]
+    pub fn build(&self, spec: &FlowSpec) -> Result<Flow<G>> {
+        let components = spec
+            .components
+            .iter()
+            .map(|component| self.create(component))
+            .collect::<Result<Vec<_>>>()?;
+        Flow::from_parts(components, spec.connections.iter().cloned())
+    }
+
+    /// Create a [Flow] from a json of a [FlowSpec], like the graph saved by a visual editor
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    /// use rs_flow::spec::ComponentRegistry;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Hello {
+    ///     name: String,
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl ComponentSchema for Hello {
+    ///     type Inputs = ();
+    ///     type Outputs = ();
+    ///     type Global = Vec<String>;
+    ///
+    ///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+    ///         ctx.with_mut_global(|hellos| hellos.push(format!("hello {}", self.name)))?;
+    ///         Ok(Next::Continue)
+    ///     }
+    /// }
+    ///
+    /// let json = r#"{
+    ///     "components": [{ "id": 1, "type": "Hello", "data": { "name": "bob" } }],
+    ///     "connections": []
+    /// }"#;
+    /// let flow = ComponentRegistry::new().register::<Hello>("Hello").from_json(json).unwrap();
+    /// # tokio_test::block_on(async {
+    /// assert_eq!(flow.run(Vec::new()).await.unwrap(), vec!["hello bob".to_owned()]);
+    /// # });
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Error [InvalidFlowJson](crate::error::Error::InvalidFlowJson) if the json is not a [FlowSpec]
+    /// - Same errors of [ComponentRegistry::build]
+    #[cfg(feature = "json")]
+    pub fn from_json(&self, json: &str) -> Result<Flow<G>> {
+        let spec: FlowSpec = serde_json::from_str(json).map_err(Error::InvalidFlowJson)?;
+        self.build(&spec)
+    }
+}
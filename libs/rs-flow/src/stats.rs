@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::component::Id;
+use crate::instrumentation::{ComponentRun, Instrumentation};
+
+/// Metrics of a [Component](crate::component::Component) in a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComponentStats {
+    /// Number of cicles that the component run
+    pub invocations: u32,
+    /// Sum of the duration of all runs of the component
+    pub total_duration: Duration,
+    /// Number of packages sent, each port of a broadcast count as a package sent
+    pub packages_sent: u64,
+    /// Number of packages received
+    pub packages_received: u64,
+}
+
+///
+/// [Instrumentation] that collect metrics of each [Component](crate::component::Component)
+/// of a run, like the number of runs and the total duration of runs, that help to identify
+/// the bottlenecks of the flow.
+///
+/// ```
+/// use rs_flow::prelude::*;
+/// use rs_flow::RunStats;
+///
+/// # tokio_test::block_on(async {
+/// let stats = RunStats::default();
+/// Flow::<()>::new().run_instrumented((), &stats).await?;
+///
+/// assert_eq!(stats.iter().count(), 0);
+/// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+/// # });
+/// ```
+///
+#[derive(Debug, Default)]
+pub struct RunStats {
+    components: Mutex<HashMap<Id, ComponentStats>>,
+}
+
+impl RunStats {
+    /// Return the metrics of a component, or [None] if it never run
+    pub fn get(&self, id: Id) -> Option<ComponentStats> {
+        self.components.lock().unwrap().get(&id).copied()
+    }
+
+    /// Return the metrics of all components that run, sorted by id
+    pub fn iter(&self) -> impl Iterator<Item = (Id, ComponentStats)> {
+        let mut components = self
+            .components
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, stats)| (*id, *stats))
+            .collect::<Vec<_>>();
+        components.sort_by_key(|(id, _)| *id);
+        components.into_iter()
+    }
+}
+
+impl<G> Instrumentation<G> for RunStats {
+    fn component_run(&self, run: &ComponentRun) {
+        let mut components = self.components.lock().unwrap();
+        let stats = components.entry(run.id).or_default();
+        stats.invocations += 1;
+        stats.total_duration += run.elapsed;
+        stats.packages_sent += run.sent as u64;
+        stats.packages_received += run.received as u64;
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Instant;
+
+use crate::component::{Component, Next, Runner};
+use crate::connection::Connection;
+use crate::error::{Error, Result, RunResult};
+use crate::event::FlowEvent;
+use crate::flow::{Execution, Flow};
+use crate::instrumentation::{Instrumentation, NoOp};
+
+///
+/// A [Flow] that only have [Component]'s created from a
+/// [SyncComponentSchema](crate::component::SyncComponentSchema), and run without any async runtime.
+///
+/// The components are scheduled in the same cicles of a [Flow], but the components
+/// of a cicle run sequentially in the current thread.
+///
+/// ```
+/// use rs_flow::prelude::*;
+///
+/// #[derive(Outputs)]
+/// struct Out;
+///
+/// struct Hello;
+///
+/// impl SyncComponentSchema for Hello {
+///     type Inputs = ();
+///     type Outputs = Out;
+///
+///     type Global = Vec<String>;
+///
+///     fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+///         ctx.with_mut_global(|global| global.push("hello".to_owned()))?;
+///         Ok(Next::Continue)
+///     }
+/// }
+///
+/// let flow = SyncFlow::new().add_component(Component::sync(1, Hello)).unwrap();
+/// assert_eq!(flow.run(Vec::new()).unwrap(), vec!["hello".to_owned()]);
+/// ```
+///
+pub struct SyncFlow<G> {
+    flow: Flow<G>,
+}
+
+impl<G> Default for SyncFlow<G>
+where
+    G: Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> SyncFlow<G>
+where
+    G: Send + Sync + 'static,
+{
+    /// Create a flow without components or connections
+    pub fn new() -> Self {
+        Self { flow: Flow::new() }
+    }
+
+    /// Insert a [Component]
+    ///
+    /// # Error
+    ///
+    /// - Error if the [Component::id] is already used
+    /// - Error if the [Component] is not sync, see [Component::sync]
+    pub fn add_component(mut self, component: Component<G>) -> Result<Self> {
+        if !component.is_sync() {
+            return Err(Error::ComponentNotSync { id: component.id });
+        }
+        self.flow = self.flow.add_component(component)?;
+        Ok(self)
+    }
+
+    /// Call the `hook` with each [FlowEvent](crate::FlowEvent) emitted while this flow run,
+    /// see [Flow::on_event]
+    pub fn on_event(mut self, hook: impl Fn(FlowEvent) + Send + Sync + 'static) -> Self {
+        self.flow = self.flow.on_event(hook);
+        self
+    }
+
+    /// Observe the run of this flow with a [Instrumentation](crate::Instrumentation),
+    /// see [Flow::instrumentation]
+    pub fn instrumentation(mut self, instrumentation: impl Instrumentation<G> + 'static) -> Self {
+        self.flow = self.flow.instrumentation(instrumentation);
+        self
+    }
+
+    /// Insert a [Connection]
+    ///
+    /// # Error
+    ///
+    /// Same errors returned by [Flow::add_connection]
+    pub fn add_connection(mut self, connection: Connection) -> Result<Self> {
+        self.flow = self.flow.add_connection(connection)?;
+        Ok(self)
+    }
+
+    ///
+    /// Run this Flow in the current thread
+    ///
+    /// # Error
+    ///
+    /// Error if a component return a Error when [run](crate::component::SyncComponentSchema::run)
+    ///
+    /// # Panics
+    ///
+    /// Panic if a component panic when [run](crate::component::SyncComponentSchema::run)
+    ///
+    pub fn run(&self, global: G) -> RunResult<G> {
+        self.run_instrumented(global, &NoOp)
+    }
+
+    ///
+    /// Run this Flow in the current thread observed by a [Instrumentation](crate::Instrumentation),
+    /// see [Flow::run_instrumented]
+    ///
+    /// # Error
+    ///
+    /// Same errors returned by [SyncFlow::run]
+    ///
+    pub fn run_instrumented(
+        &self,
+        global: G,
+        instrumentation: &dyn Instrumentation<G>,
+    ) -> RunResult<G> {
+        let mut execution = Execution::new(&self.flow, global, u32::MAX, instrumentation);
+
+        while !execution.is_finished() {
+            let mut results = Vec::new();
+            for (component, mut ctx) in execution.start_cicle() {
+                let Runner::Sync(data) = &component.data else {
+                    unreachable!("SyncFlow only have sync components");
+                };
+
+                let start = Instant::now();
+                let next = data.run(&mut ctx)?;
+                ctx.elapsed = start.elapsed();
+                results.push((ctx, next));
+            }
+
+            if execution.end_cicle(results)? == Next::Break {
+                break;
+            }
+        }
+
+        Ok(execution.finish())
+    }
+}
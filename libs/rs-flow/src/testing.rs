@@ -0,0 +1,493 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::component::{Component, Id, Next};
+use crate::connection::{Connection, Point};
+use crate::context::envelope::Envelope;
+use crate::context::global::{Global, Globals};
+use crate::context::Ctx;
+use crate::error::{Error, RunResult};
+use crate::flow::Flow;
+use crate::package::Package;
+use crate::ports::{Inputs, Outputs, PortId};
+
+///
+/// Harness to test a single [Component] without create a [Flow].
+///
+/// The packages defined as inputs are put in the receive queues of the component,
+/// that run once, and the packages sent are returned in a [TestingResult]. A small
+/// subgraph of connected components can be tested with [Testing::test_flow].
+///
+/// ```
+/// use rs_flow::prelude::*;
+/// use rs_flow::testing::Testing;
+///
+/// #[derive(Inputs)]
+/// struct In;
+///
+/// #[derive(Outputs)]
+/// struct Out;
+///
+/// struct Double;
+///
+/// #[async_trait]
+/// impl ComponentSchema for Double {
+///     type Inputs = In;
+///     type Outputs = Out;
+///
+///     type Global = ();
+///
+///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+///         while let Some(package) = ctx.receive(In) {
+///             ctx.send(Out, (package.get_number()? * 2.0).into());
+///         }
+///         Ok(Next::Continue)
+///     }
+/// }
+///
+/// tokio_test::block_on(async {
+///     let result = Testing::new(())
+///         .input(In, 21.into())
+///         .test(Component::new(1, Double))
+///         .await
+///         .unwrap();
+///
+///     assert_eq!(result.output(Out), &[Package::number(42.0)]);
+/// });
+/// ```
+///
+pub struct Testing<G> {
+    global: G,
+    inputs: HashMap<PortId, Vec<Package>>,
+    inputs_at: HashMap<u32, HashMap<PortId, Vec<Package>>>,
+    inputs_to: HashMap<Point, Vec<Package>>,
+    connections: Vec<Connection>,
+    cicle: u32,
+}
+
+impl<G> Testing<G>
+where
+    G: Send + Sync + 'static,
+{
+    /// Create a harness with the global data used by the component
+    pub fn new(global: G) -> Self {
+        Self {
+            global,
+            inputs: HashMap::new(),
+            inputs_at: HashMap::new(),
+            inputs_to: HashMap::new(),
+            connections: Vec::new(),
+            cicle: 0,
+        }
+    }
+
+    /// Put a [Package] in the receive queue of a input port, the packages
+    /// are received in the order that was inputed
+    pub fn input<I: Inputs>(mut self, port: I, package: Package) -> Self {
+        self.inputs
+            .entry(port.into_port())
+            .or_default()
+            .push(package);
+        self
+    }
+
+    /// Put a [Package] in the receive queue of a input port before the component
+    /// run in that cicle, see [Testing::test_cycles]
+    pub fn input_at<I: Inputs>(mut self, cicle: u32, port: I, package: Package) -> Self {
+        self.inputs_at
+            .entry(cicle)
+            .or_default()
+            .entry(port.into_port())
+            .or_default()
+            .push(package);
+        self
+    }
+
+    /// Put a [Package] in the receive queue of a input port of a component of the
+    /// subgraph run by [Testing::test_flow]
+    pub fn input_to(mut self, point: Point, package: Package) -> Self {
+        self.inputs_to.entry(point).or_default().push(package);
+        self
+    }
+
+    /// Connect two components of the subgraph run by [Testing::test_flow]
+    pub fn connect(mut self, connection: Connection) -> Self {
+        self.connections.push(connection);
+        self
+    }
+
+    /// Define the cicle that the component run, by default the first cicle (0)
+    pub fn at_cycle(mut self, cicle: u32) -> Self {
+        self.cicle = cicle;
+        self
+    }
+
+    /// Run the component once
+    ///
+    /// # Error
+    ///
+    /// - Error if a input was defined for a port that the component not have
+    /// - Error if the component return a Error when run
+    pub async fn test(self, component: Component<G>) -> RunResult<TestingResult<G>> {
+        self.test_cycles(component, 1).await
+    }
+
+    /// Run the component up to `cycles` times, in consecutive cicles starting from
+    /// [the first](Testing::at_cycle), like a [Flow] that run the component in each cicle.
+    /// Stop after a run that return [Next::Break].
+    ///
+    /// The packages defined by [Testing::input] are received in the first run, and the
+    /// packages defined by [Testing::input_at] before the run in that cicle. The packages
+    /// not received stay in the queues to the next run.
+    ///
+    /// # Error
+    ///
+    /// - Error if a input was defined for a port that the component not have
+    /// - Error if the component return a Error when run
+    pub async fn test_cycles(
+        mut self,
+        component: Component<G>,
+        cycles: usize,
+    ) -> RunResult<TestingResult<G>> {
+        let global = Arc::new(Global::from_data(self.global));
+        let mut ctx = Ctx::from(&component, &global);
+
+        let mut next = Next::Continue;
+        let mut outputs_by_cycle = Vec::with_capacity(cycles);
+        for cicle in (self.cicle..).take(cycles) {
+            ctx.set_cycle(cicle);
+            ctx.consumed = false;
+            ctx.received = 0;
+            ctx.sent = 0;
+
+            let mut inputs = self.inputs_at.remove(&cicle).unwrap_or_default();
+            if cicle == self.cicle {
+                for (port, packages) in std::mem::take(&mut self.inputs) {
+                    let queue = inputs.entry(port).or_default();
+                    queue.splice(0..0, packages);
+                }
+            }
+            for (port, packages) in inputs {
+                let queue = ctx.receive.get_mut(&port).ok_or(Error::InPortNotFound {
+                    component: component.id,
+                    in_port: port,
+                })?;
+                let packages = packages
+                    .into_iter()
+                    .map(|package| Envelope::new(package, component.id, cicle))
+                    .collect::<VecDeque<_>>();
+                queue.push_all(packages);
+            }
+
+            next = component.data.run(&mut ctx).await?;
+            ctx.apply_deltas()?;
+
+            let outputs: HashMap<PortId, Vec<Package>> = ctx
+                .send
+                .iter_mut()
+                .map(|(port, queue)| (*port, queue.drain(..).collect()))
+                .collect();
+            outputs_by_cycle.push(outputs);
+
+            if next == Next::Break {
+                break;
+            }
+        }
+
+        let mut outputs = HashMap::<PortId, Vec<Package>>::new();
+        for cycle in outputs_by_cycle.iter() {
+            for (port, packages) in cycle {
+                outputs
+                    .entry(*port)
+                    .or_default()
+                    .extend(packages.iter().cloned());
+            }
+        }
+
+        drop(ctx);
+        let global = Arc::try_unwrap(global)
+            .expect("Global no have multiples references, becaurse context already drop")
+            .take();
+
+        Ok(TestingResult {
+            next,
+            outputs,
+            outputs_by_cycle,
+            global,
+        })
+    }
+}
+
+impl<G> Testing<G>
+where
+    G: Send + Sync + 'static,
+{
+    ///
+    /// Run a small subgraph of components, connected by [Testing::connect], like a
+    /// [Flow] with the packages defined by [Testing::input_to] as inputs. Return all
+    /// packages sent by the components, including the packages delivered by the connections.
+    ///
+    /// ```
+    /// use rs_flow::prelude::*;
+    /// use rs_flow::testing::Testing;
+    ///
+    /// #[derive(Inputs)]
+    /// struct In;
+    ///
+    /// #[derive(Outputs)]
+    /// struct Out;
+    ///
+    /// struct Double;
+    ///
+    /// #[async_trait]
+    /// impl ComponentSchema for Double {
+    ///     type Inputs = In;
+    ///     type Outputs = Out;
+    ///
+    ///     type Global = ();
+    ///
+    ///     async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+    ///         while let Some(package) = ctx.receive(In) {
+    ///             ctx.send(Out, (package.get_number()? * 2.0).into());
+    ///         }
+    ///         Ok(Next::Continue)
+    ///     }
+    /// }
+    ///
+    /// tokio_test::block_on(async {
+    ///     let first = Component::new(1, Double);
+    ///     let second = Component::new(2, Double);
+    ///     let result = Testing::new(())
+    ///         .input_to(first.to(0), 5.into())
+    ///         .connect(Connection::by(first.from(0), second.to(0)))
+    ///         .test_flow([first, second], 10)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(result.sent(1, Out), &[Package::number(10.0)]);
+    ///     assert_eq!(result.sent(2, Out), &[Package::number(20.0)]);
+    /// });
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - Error if a [Component] or [Connection] is invalid, see [Flow::from_parts]
+    /// - Error [CycleLimitExceeded](crate::error::Error::CycleLimitExceeded) if still
+    ///   have components ready to run after `max_cicles` cicles
+    /// - Same errors returned by [Flow::run_with_inputs]
+    pub async fn test_flow(
+        self,
+        components: impl IntoIterator<Item = Component<G>>,
+        max_cicles: u32,
+    ) -> RunResult<TestingFlowResult<G>> {
+        let connected = self
+            .connections
+            .iter()
+            .map(|connection| connection.from())
+            .collect();
+        let flow = Flow::from_parts(components, self.connections)?;
+        let (global, sent) = flow
+            .run_recording(self.global, self.inputs_to, max_cicles)
+            .await?;
+
+        Ok(TestingFlowResult {
+            sent,
+            connected,
+            global,
+        })
+    }
+}
+
+/// Result of a subgraph run by [Testing::test_flow]
+#[derive(Debug)]
+pub struct TestingFlowResult<G> {
+    /// The packages sent by each output port, in the order that they were sent
+    pub sent: HashMap<Point, Vec<Package>>,
+    connected: HashSet<Point>,
+    global: G,
+}
+
+impl<G> TestingFlowResult<G> {
+    /// Return the packages sent to a output port of a component
+    pub fn sent<O: Outputs>(&self, id: Id, port: O) -> &[Package] {
+        self.sent
+            .get(&Point::new(id, port.into_port()))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return the packages sent to the output ports that are not connected in the subgraph
+    pub fn terminal(&self) -> HashMap<Point, &[Package]> {
+        self.sent
+            .iter()
+            .filter(|(point, _)| !self.connected.contains(point))
+            .map(|(point, packages)| (*point, packages.as_slice()))
+            .collect()
+    }
+
+    /// Return the global data after the subgraph run
+    pub fn global(&self) -> &G {
+        &self.global
+    }
+
+    /// Consume the result returning the global data
+    pub fn into_global(self) -> G {
+        self.global
+    }
+}
+
+/// Result of a component run by [Testing]
+#[derive(Debug)]
+pub struct TestingResult<G> {
+    /// The [Next] returned by the component, in the last run
+    pub next: Next,
+    /// The packages sent by the component in each output port, in all runs
+    pub outputs: HashMap<PortId, Vec<Package>>,
+    /// The packages sent by the component in each output port, for each run
+    /// of [Testing::test_cycles]
+    pub outputs_by_cycle: Vec<HashMap<PortId, Vec<Package>>>,
+    global: G,
+}
+
+impl<G> TestingResult<G> {
+    /// Return the packages sent to a output port
+    pub fn output<O: Outputs>(&self, port: O) -> &[Package] {
+        self.outputs
+            .get(&port.into_port())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return the packages sent to a output port in a run of [Testing::test_cycles],
+    /// the first run is 0
+    pub fn output_at<O: Outputs>(&self, run: usize, port: O) -> &[Package] {
+        self.outputs_by_cycle
+            .get(run)
+            .and_then(|outputs| outputs.get(&port.into_port()))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return the number of runs of the component
+    pub fn runs(&self) -> usize {
+        self.outputs_by_cycle.len()
+    }
+
+    /// Return the global data after the component run
+    pub fn global(&self) -> &G {
+        &self.global
+    }
+
+    /// Return the global data of type `T` after the component run, when the global
+    /// data is composed of many types, like a [GlobalBag](crate::GlobalBag), see [Globals]
+    pub fn global_ref<T: 'static>(&self) -> Option<&T>
+    where
+        G: Globals,
+    {
+        self.global.get()
+    }
+
+    /// Assert that the global data of type `T` is equal to `expected`, see [TestingResult::global_ref]
+    ///
+    /// # Panics
+    ///
+    /// Panic if the global data not have the type `T`, or if is different of `expected`
+    #[track_caller]
+    pub fn assert_global_eq<T: PartialEq + Debug + 'static>(&self, expected: &T)
+    where
+        G: Globals,
+    {
+        let type_name = std::any::type_name::<T>();
+        match self.global_ref::<T>() {
+            Some(global) => assert_eq!(global, expected, "global of type {type_name}"),
+            None => panic!("Global data not have the type {type_name}"),
+        }
+    }
+
+    /// Consume the result returning the global data
+    pub fn into_global(self) -> G {
+        self.global
+    }
+}
+
+impl<G> Flow<G>
+where
+    G: Send + Sync + 'static,
+{
+    ///
+    /// Run this Flow `runs` times with a copy of the same global, and assert that all runs
+    /// return the same global data and that the [sinks](Flow::sinks) send the same packages,
+    /// in the same order. Return the global of the first run.
+    ///
+    /// That is usefull to detect nondeterminism in the order that packages are sent
+    /// and received, like a fan-in of many components that record the order of packages.
+    ///
+    /// # Error
+    ///
+    /// Error if any run of the [Flow] fail
+    ///
+    /// # Panics
+    ///
+    /// Panic with the first line that diverge in the [Debug] of the global or of the
+    /// packages sent by the sinks, if any run diverge of the first run
+    ///
+    pub async fn assert_deterministic(&self, global: G, runs: usize) -> RunResult<G>
+    where
+        G: Clone + PartialEq + Debug,
+    {
+        let (expected, expected_outputs) = self.run_sinks(global.clone()).await?;
+        for run in 1..runs {
+            let (result, outputs) = self.run_sinks(global.clone()).await?;
+            if result != expected {
+                panic!(
+                    "Flow is not deterministic, run {run} diverged from run 0 in the global\n{}",
+                    first_divergence(&expected, &result)
+                );
+            }
+            if outputs != expected_outputs {
+                panic!(
+                    "Flow is not deterministic, run {run} diverged from run 0 in the outputs\n{}",
+                    first_divergence(&expected_outputs, &outputs)
+                );
+            }
+        }
+        Ok(expected)
+    }
+
+    /// Run this Flow returning the packages sent by each output port of the sinks, sorted by port
+    async fn run_sinks(&self, global: G) -> RunResult<(G, Vec<(Point, Vec<Package>)>)> {
+        let sinks = self.sinks();
+        let (global, sent) = self.run_recording(global, HashMap::new(), u32::MAX).await?;
+
+        let mut outputs = sent
+            .into_iter()
+            .filter(|(point, _)| sinks.contains(&point.id()))
+            .collect::<Vec<_>>();
+        outputs.sort_by_key(|(point, _)| (point.id(), point.port()));
+
+        Ok((global, outputs))
+    }
+}
+
+/// Describe the first line of the pretty [Debug] of two values that differ
+fn first_divergence<T: Debug>(expected: &T, result: &T) -> String {
+    let expected = format!("{expected:#?}");
+    let result = format!("{result:#?}");
+
+    let mut expected_lines = expected.lines();
+    let mut result_lines = result.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), result_lines.next()) {
+            (Some(a), Some(b)) if a == b => line += 1,
+            (a, b) => {
+                return format!(
+                    "line {line}:\n- {}\n+ {}",
+                    a.unwrap_or("<end>"),
+                    b.unwrap_or("<end>")
+                )
+            }
+        }
+    }
+}
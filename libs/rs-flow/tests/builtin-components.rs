@@ -0,0 +1,571 @@
+use rs_flow::components::assert::{Assert, AssertionFailures};
+use rs_flow::components::collector::{Collected, Collector};
+use rs_flow::components::dedup::Dedup;
+use rs_flow::components::map::MapComponent;
+use rs_flow::components::router::Router;
+use rs_flow::prelude::*;
+use rs_flow::PackageError;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+struct Numbers(Vec<f64>);
+
+#[async_trait]
+impl ComponentSchema for Numbers {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in &self.0 {
+            ctx.send(Out, Package::number(*number));
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Collect;
+
+#[async_trait]
+impl ComponentSchema for Collect {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.push(number))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+async fn run_dedup(numbers: Vec<f64>, dedup: Dedup<Vec<f64>>) -> Result<Vec<f64>> {
+    let numbers = Component::new(1, Numbers(numbers));
+    let dedup = Component::new(2, dedup);
+    let collect = Component::new(3, Collect);
+
+    let conn_numbers = Connection::by(numbers.from(0), dedup.to(0));
+    let conn_dedup = Connection::by(dedup.from(0), collect.to(0));
+
+    let mut collected = Flow::new()
+        .add_component(numbers)?
+        .add_component(dedup)?
+        .add_component(collect)?
+        .add_connection(conn_numbers)?
+        .add_connection(conn_dedup)?
+        .run(Vec::new())
+        .await?;
+
+    collected.sort_by(f64::total_cmp);
+    Ok(collected)
+}
+
+fn number_key(package: &Package) -> String {
+    format!("{package:?}")
+}
+
+#[tokio::test]
+async fn dedup_forward_only_uniques() -> Result<()> {
+    let collected = run_dedup(vec![1.0, 2.0, 1.0, 3.0, 2.0, 1.0], Dedup::new(number_key)).await?;
+    assert_eq!(collected, vec![1.0, 2.0, 3.0]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dedup_with_capacity_forget_oldest() -> Result<()> {
+    let dedup = Dedup::with_capacity(number_key, 1);
+    let collected = run_dedup(vec![1.0, 1.0, 2.0, 1.0], dedup).await?;
+    assert_eq!(collected, vec![1.0, 1.0, 2.0]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dedup_forget_keys_between_runs() -> Result<()> {
+    let numbers = Component::new(1, Numbers(vec![1.0, 2.0, 1.0]));
+    let dedup = Component::new(2, Dedup::new(number_key));
+    let collect = Component::new(3, Collect);
+
+    let conn_numbers = Connection::by(numbers.from(0), dedup.to(0));
+    let conn_dedup = Connection::by(dedup.from(0), collect.to(0));
+
+    let flow = Flow::new()
+        .add_component(numbers)?
+        .add_component(dedup)?
+        .add_component(collect)?
+        .add_connection(conn_numbers)?
+        .add_connection(conn_dedup)?;
+
+    let mut first = flow.run(Vec::new()).await?;
+    let mut second = flow.run(Vec::new()).await?;
+    first.sort_by(f64::total_cmp);
+    second.sort_by(f64::total_cmp);
+
+    assert_eq!(first, vec![1.0, 2.0]);
+    assert_eq!(second, vec![1.0, 2.0]);
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Dedup capacity must be greater than 0")]
+fn dedup_reject_capacity_zero() {
+    let _: Dedup<()> = Dedup::with_capacity(number_key, 0);
+}
+
+struct Users;
+
+#[async_trait]
+impl ComponentSchema for Users {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<(&'static str, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let roles = ["admin", "guest", "bot", "admin", "unknown"];
+        for (id, role) in roles.into_iter().enumerate() {
+            let user = Package::object([("role", role)]);
+            ctx.send(Out, Package::object([("id", id.into()), ("user", user)]));
+        }
+        ctx.send(Out, Package::object([("id", 5)]));
+        Ok(Next::Continue)
+    }
+}
+
+struct Label(&'static str);
+
+#[async_trait]
+impl ComponentSchema for Label {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(&'static str, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let id = package.get_object()?.remove("id").unwrap().get_number()?;
+            ctx.with_mut_global(|global| global.push((self.0, id)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[derive(Outputs)]
+enum Role {
+    Admin,
+    Guest,
+    Bot,
+    Unmatched,
+}
+
+#[tokio::test]
+async fn router_by_nested_field() -> Result<()> {
+    let router = Router::builder()
+        .rule("user.role", "admin", Role::Admin)
+        .rule("user.role", "guest", Role::Guest)
+        .rule("user.role", "bot", Role::Bot)
+        .build(Role::Unmatched);
+
+    let users = Component::new(1, Users);
+    let router = Component::new(2, router);
+    let labels = [
+        (Role::Admin, Component::new(3, Label("admin"))),
+        (Role::Guest, Component::new(4, Label("guest"))),
+        (Role::Bot, Component::new(5, Label("bot"))),
+        (Role::Unmatched, Component::new(6, Label("unmatched"))),
+    ];
+
+    let mut connections = vec![Connection::by(users.from(0), router.to(0))];
+    for (role, label) in &labels {
+        connections.push(Connection::by(router.from(role.into_port()), label.to(0)));
+    }
+
+    let mut flow = Flow::new().add_component(users)?.add_component(router)?;
+    for (_, label) in labels {
+        flow = flow.add_component(label)?;
+    }
+    for connection in connections {
+        flow = flow.add_connection(connection)?;
+    }
+
+    let mut routed = flow.run(Vec::new()).await?;
+    routed.sort_by(|a, b| a.1.total_cmp(&b.1));
+    assert_eq!(
+        routed,
+        vec![
+            ("admin", 0.0),
+            ("guest", 1.0),
+            ("bot", 2.0),
+            ("admin", 3.0),
+            ("unmatched", 4.0),
+            ("unmatched", 5.0),
+        ]
+    );
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Mapped {
+    numbers: Vec<f64>,
+    errors: Vec<String>,
+}
+
+struct Mixed;
+
+#[async_trait]
+impl ComponentSchema for Mixed {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Mapped;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, Package::number(1.0));
+        ctx.send(Out, Package::string("not a number"));
+        ctx.send(Out, Package::number(2.0));
+        Ok(Next::Continue)
+    }
+}
+
+struct CollectMapped;
+
+#[derive(Inputs)]
+enum MappedIn {
+    Number,
+    Error,
+}
+
+#[async_trait]
+impl ComponentSchema for CollectMapped {
+    type Inputs = MappedIn;
+    type Outputs = ();
+
+    type Global = Mapped;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(MappedIn::Number) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.numbers.push(number))?;
+        }
+        while let Some(package) = ctx.receive(MappedIn::Error) {
+            let error = package.get_string()?;
+            ctx.with_mut_global(|global| global.errors.push(error))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+fn double(package: Package) -> std::result::Result<Package, PackageError> {
+    Ok(Package::number(package.get_number()? * 2.0))
+}
+
+async fn run_map(map: MapComponent<Mapped>) -> Result<Mapped> {
+    use rs_flow::components::map::Out as MapOut;
+
+    let mixed = Component::new(1, Mixed);
+    let map = Component::new(2, map);
+    // Eager to receive the numbers and errors in the same cicle
+    let collect = Component::eager(3, CollectMapped);
+
+    let connections = [
+        Connection::by(mixed.from(0), map.to(0)),
+        Connection::by(map.from(MapOut::Package.into_port()), collect.to(0)),
+        Connection::by(map.from(MapOut::Error.into_port()), collect.to(1)),
+    ];
+
+    Flow::from_parts([mixed, map, collect], connections)?
+        .run(Mapped::default())
+        .await
+}
+
+#[tokio::test]
+async fn map_happy_path() -> Result<()> {
+    let map = MapComponent::new(|package| Ok(Package::number(package.get_number()? + 1.0)));
+
+    let numbers = Component::new(1, Numbers(vec![1.0, 2.0]));
+    let map = Component::new(2, map);
+    let collect = Component::new(3, Collect);
+
+    let connections = [
+        Connection::by(numbers.from(0), map.to(0)),
+        Connection::by(map.from(0), collect.to(0)),
+    ];
+
+    // the error output of a map created by new can be left unconnected
+    let flow = Flow::from_parts([numbers, map, collect], connections)?.strict_outputs();
+    assert!(flow.validate().is_ok());
+
+    let mut collected = flow.run(Vec::new()).await?;
+    collected.sort_by(f64::total_cmp);
+    assert_eq!(collected, vec![2.0, 3.0]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn map_error_interrupt_flow() {
+    let error = run_map(MapComponent::new(double)).await.err().unwrap();
+    assert!(matches!(
+        error.downcast_ref::<PackageError>(),
+        Some(PackageError::NotNumber)
+    ));
+}
+
+#[tokio::test]
+async fn try_map_route_errors() -> Result<()> {
+    let mut mapped = run_map(MapComponent::try_map(double)).await?;
+
+    mapped.numbers.sort_by(f64::total_cmp);
+    assert_eq!(mapped.numbers, vec![2.0, 4.0]);
+    assert_eq!(mapped.errors, vec![PackageError::NotNumber.to_string()]);
+
+    Ok(())
+}
+
+struct Evens;
+struct Odds;
+
+#[derive(Default)]
+struct Results {
+    evens: Vec<Package>,
+    odds: Vec<Package>,
+}
+
+impl Collected<Evens> for Results {
+    fn collected(&mut self) -> &mut Vec<Package> {
+        &mut self.evens
+    }
+}
+
+impl Collected<Odds> for Results {
+    fn collected(&mut self) -> &mut Vec<Package> {
+        &mut self.odds
+    }
+}
+
+/// Send the numbers to a [Collector] of `Results`
+struct Produce(Vec<f64>);
+
+#[async_trait]
+impl ComponentSchema for Produce {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Results;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in &self.0 {
+            ctx.send(Out, Package::number(*number));
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn collector_from_two_producers() -> Result<()> {
+    let results = Flow::from_parts(
+        [
+            Component::new(1, Produce(vec![2.0, 4.0])),
+            Component::new(2, Produce(vec![6.0])),
+            Component::new(3, Produce(vec![1.0, 3.0])),
+            Component::new(4, Collector::new(Evens)),
+            Component::new(5, Collector::new(Odds)),
+        ],
+        [
+            Connection::new(1, 0, 4, 0),
+            Connection::new(2, 0, 4, 0),
+            Connection::new(3, 0, 5, 0),
+        ],
+    )?
+    .run(Results::default())
+    .await?;
+
+    let mut evens = results
+        .evens
+        .into_iter()
+        .map(Package::get_number)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    evens.sort_by(f64::total_cmp);
+    assert_eq!(evens, vec![2.0, 4.0, 6.0]);
+    assert_eq!(results.odds.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sub_flow_as_component() -> Result<()> {
+    use rs_flow::components::map::{In as MapIn, Out as MapOut};
+    use rs_flow::components::sub_flow::SubFlow;
+    use rs_flow::connection::Point;
+
+    // inner flow: double and add one, collecting the doubled numbers
+    let inner = || {
+        let double = Component::new(1, MapComponent::new(double));
+        let increment = Component::new(
+            2,
+            MapComponent::new(|package| Ok(Package::number(package.get_number()? + 1.0))),
+        );
+        let collect = Component::new(3, Collect);
+        Flow::from_parts(
+            [double, increment, collect],
+            [
+                Connection::new(
+                    1,
+                    MapOut::Package.into_port(),
+                    2,
+                    MapIn::Package.into_port(),
+                ),
+                Connection::new(1, MapOut::Package.into_port(), 3, 0),
+            ],
+        )
+    };
+
+    let sub_flow: SubFlow<Vec<f64>, Vec<f64>, In, Out> = SubFlow::builder(inner()?, Vec::new)
+        .input(In, Point::new(1, MapIn::Package.into_port()))
+        .output(Point::new(2, MapOut::Package.into_port()), Out)
+        // negated to distinguish the inner global in the outer global
+        .merge(|outer: &mut Vec<f64>, inner| outer.extend(inner.into_iter().map(|n| -n)))
+        .build()?;
+
+    let mut global = Flow::from_parts(
+        [
+            Component::new(1, Numbers(vec![1.0, 2.0, 3.0])),
+            Component::new(2, sub_flow),
+            Component::new(3, Collect),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?
+    .run(Vec::new())
+    .await?;
+    global.sort_by(f64::total_cmp);
+    assert_eq!(global, vec![-6.0, -4.0, -2.0, 3.0, 5.0, 7.0]);
+
+    let missing = SubFlow::<Vec<f64>, Vec<f64>, In, Out>::builder(inner()?, Vec::new)
+        .output(Point::new(2, MapOut::Error.into_port() + 1), Out)
+        .build();
+    assert!(matches!(
+        missing
+            .err()
+            .and_then(|error| error.downcast::<Error>().ok())
+            .as_deref(),
+        Some(Error::OutPortNotFound {
+            component: 2,
+            out_port: 2
+        })
+    ));
+
+    Ok(())
+}
+
+fn positive(package: &Package) -> bool {
+    matches!(package, Package::Number(number) if *number > 0.0)
+}
+
+async fn run_assert(numbers: Vec<f64>) -> Result<Vec<f64>> {
+    let numbers = Component::new(1, Numbers(numbers));
+    let assert = Component::new(2, Assert::new(positive));
+    let collect = Component::new(3, Collect);
+
+    let connections = [
+        Connection::by(numbers.from(0), assert.to(0)),
+        Connection::by(assert.from(0), collect.to(0)),
+    ];
+
+    Flow::from_parts([numbers, assert, collect], connections)?
+        .run(Vec::new())
+        .await
+}
+
+#[tokio::test]
+async fn assert_forward_passing_stream() -> Result<()> {
+    let collected = run_assert(vec![1.0, 2.0, 3.0]).await?;
+    assert_eq!(collected, vec![1.0, 2.0, 3.0]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn assert_failure_interrupt_flow() {
+    let error = run_assert(vec![1.0, -2.0, 3.0]).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::AssertionFailed { component: 2, package }) if *package == Package::number(-2.0)
+    ));
+}
+
+#[derive(Default)]
+struct Checked {
+    numbers: Vec<f64>,
+    failures: Vec<(Id, Package)>,
+}
+
+impl AssertionFailures for Checked {
+    fn failures(&mut self) -> &mut Vec<(Id, Package)> {
+        &mut self.failures
+    }
+}
+
+struct CheckedNumbers;
+
+#[async_trait]
+impl ComponentSchema for CheckedNumbers {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Checked;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in [1.0, -2.0, 3.0] {
+            ctx.send(Out, Package::number(number));
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct CollectChecked;
+
+#[async_trait]
+impl ComponentSchema for CollectChecked {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Checked;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.numbers.push(number))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn assert_recording_failures() -> Result<()> {
+    let checked = Flow::from_parts(
+        [
+            Component::new(1, CheckedNumbers),
+            Component::new(2, Assert::recording(positive)),
+            Component::new(3, CollectChecked),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?
+    .run(Checked::default())
+    .await?;
+
+    assert_eq!(checked.numbers, vec![1.0, 3.0]);
+    assert_eq!(checked.failures, vec![(2, Package::number(-2.0))]);
+
+    Ok(())
+}
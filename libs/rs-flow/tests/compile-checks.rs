@@ -0,0 +1,5 @@
+#[test]
+fn component_global_must_match_flow() {
+    let tests = trybuild::TestCases::new();
+    tests.compile_fail("tests/ui/component-global-mismatch.rs");
+}
@@ -13,7 +13,7 @@ pub struct Message {
 }
 
 impl Message {
-    pub fn new<'a>(message: &'a str) -> Self {
+    pub fn new(message: &str) -> Self {
         Self {
             message: message.to_string(),
         }
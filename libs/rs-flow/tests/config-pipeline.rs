@@ -0,0 +1,167 @@
+#![cfg(feature = "json")]
+
+use rs_flow::prelude::*;
+use rs_flow::spec::{ComponentRegistry, ComponentSpec, FlowSpec};
+use serde::Deserialize;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+/// Send the numbers from `start` until `end` (exclusive)
+#[derive(Deserialize)]
+struct Range {
+    start: u32,
+    end: u32,
+}
+
+#[async_trait]
+impl ComponentSchema for Range {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in self.start..self.end {
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Send `number * mul + add` of each number received, recording the results
+#[derive(Deserialize)]
+struct Affine {
+    mul: f64,
+    add: f64,
+}
+
+#[async_trait]
+impl ComponentSchema for Affine {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()? * self.mul + self.add;
+            ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn config_driven_pipeline() -> Result<()> {
+    let spec: FlowSpec = serde_json::from_str(include_str!("fixtures/pipeline.json"))?;
+
+    let registry = ComponentRegistry::new()
+        .register::<Range>("Range")
+        .register::<Affine>("Affine");
+
+    let mut outputs = registry.build(&spec)?.run(Vec::new()).await?;
+    outputs.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    assert_eq!(
+        outputs,
+        vec![
+            (2, 2.0),
+            (2, 4.0),
+            (2, 6.0),
+            (3, 12.5),
+            (3, 14.5),
+            (3, 16.5)
+        ]
+    );
+
+    let mut spec = spec;
+    spec.components[0].type_name = "Counter".to_owned();
+    assert!(matches!(
+        registry.build(&spec).err(),
+        Some(Error::ComponentTypeNotRegistered { type_name }) if type_name == "Counter"
+    ));
+
+    spec.components[0] =
+        ComponentSpec::new(1, "Range").with_config(Package::object([("start", "one")]));
+    assert!(matches!(
+        registry.build(&spec).err(),
+        Some(Error::InvalidComponentConfig { id: 1, .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn flow_from_json_graph() -> Result<()> {
+    let registry = ComponentRegistry::new()
+        .register::<Range>("Range")
+        .register::<Affine>("Affine");
+
+    let json = r#"{
+        "components": [
+            { "id": 1, "type": "Range", "data": { "start": 0, "end": 2 } },
+            { "id": 2, "type": "Affine", "data": { "mul": 3, "add": 1 } }
+        ],
+        "connections": [{ "from": 1, "out_port": 0, "to": 2, "in_port": 0 }]
+    }"#;
+    let mut outputs = registry.from_json(json)?.run(Vec::new()).await?;
+    outputs.sort_by(|a, b| a.1.total_cmp(&b.1));
+    assert_eq!(outputs, vec![(2, 1.0), (2, 4.0)]);
+
+    let unknown = json.replace("\"Affine\"", "\"Scale\"");
+    assert!(matches!(
+        registry.from_json(&unknown).err(),
+        Some(Error::ComponentTypeNotRegistered { type_name }) if type_name == "Scale"
+    ));
+    assert!(matches!(
+        registry.from_json("{ \"components\": [] ").err(),
+        Some(Error::InvalidFlowJson(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn flow_spec_round_trip() -> Result<()> {
+    let registry = ComponentRegistry::new()
+        .register::<Range>("Range")
+        .register::<Affine>("Affine");
+
+    let mut spec: FlowSpec = serde_json::from_str(include_str!("fixtures/pipeline.json"))?;
+    spec.components[2].ty = Type::Eager;
+    let flow = registry.build(&spec)?;
+
+    let saved = flow.to_spec();
+    assert_eq!(saved.connections, spec.connections);
+    assert_eq!(saved.components.len(), 3);
+    assert_eq!(saved.components[0].type_name, "Range");
+    assert_eq!(saved.components[1].config, spec.components[1].config);
+    assert_eq!(saved.components[2].ty, Type::Eager);
+    assert!(saved.components[0].inputs.is_empty());
+    assert_eq!(saved.components[0].outputs[0].label, Some("Out"));
+
+    let json = serde_json::to_string(&saved)?;
+    let loaded = registry.from_json(&json)?;
+    assert_eq!(loaded.to_spec(), saved);
+
+    let sort = |mut outputs: Vec<(Id, f64)>| {
+        outputs.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        outputs
+    };
+    assert_eq!(
+        sort(loaded.run(Vec::new()).await?),
+        sort(flow.run(Vec::new()).await?)
+    );
+
+    // components not created by a registry are described by the path of your type
+    let manual = Flow::new().add_component(Component::new(1, Range { start: 0, end: 1 }))?;
+    let spec = manual.to_spec();
+    assert!(spec.components[0].type_name.ends_with("::Range"));
+    assert_eq!(spec.components[0].config, Package::Empty);
+
+    Ok(())
+}
@@ -0,0 +1,1349 @@
+use rs_flow::prelude::*;
+use rs_flow::GlobalBag;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+struct Source(&'static str);
+
+#[async_trait]
+impl ComponentSchema for Source {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, self.0.into());
+        Ok(Next::Continue)
+    }
+}
+
+struct Lineage;
+
+#[async_trait]
+impl ComponentSchema for Lineage {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some((package, source)) = ctx.receive_with_source(In) {
+            let message = package.get_string()?;
+            ctx.with_mut_global(|global| global.push((message, source)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn receive_with_source() -> Result<()> {
+    let a = Component::new(1, Source("a"));
+    let b = Component::new(2, Source("b"));
+    let lineage = Component::new(3, Lineage);
+
+    let conn_a = Connection::by(a.from(0), lineage.to(0));
+    let conn_b = Connection::by(b.from(0), lineage.to(0));
+
+    let mut received = Flow::new()
+        .add_component(a)?
+        .add_component(b)?
+        .add_component(lineage)?
+        .add_connection(conn_a)?
+        .add_connection(conn_b)?
+        .run(Vec::new())
+        .await?;
+
+    received.sort();
+    assert_eq!(received, vec![("a".to_owned(), 1), ("b".to_owned(), 2)]);
+
+    Ok(())
+}
+
+struct Numbers;
+
+#[async_trait]
+impl ComponentSchema for Numbers {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in [3, 1, 2] {
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Drain;
+
+#[async_trait]
+impl ComponentSchema for Drain {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.push(number))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+async fn drain_order(policy: QueuePolicy) -> Result<Vec<f64>> {
+    let numbers = Component::new(1, Numbers);
+    let drain = Component::new(2, Drain).input_policy(0, policy);
+
+    let connection = Connection::by(numbers.from(0), drain.to(0));
+
+    Flow::new()
+        .add_component(numbers)?
+        .add_component(drain)?
+        .add_connection(connection)?
+        .run(Vec::new())
+        .await
+}
+
+#[tokio::test]
+async fn input_queue_policies() -> Result<()> {
+    let fifo = drain_order(QueuePolicy::Fifo).await?;
+    let mut lifo = drain_order(QueuePolicy::Lifo).await?;
+    let priority = drain_order(QueuePolicy::priority(|package| {
+        package.clone().get_number().unwrap_or_default() as i64
+    }))
+    .await?;
+
+    lifo.reverse();
+    assert_eq!(fifo, vec![3.0, 1.0, 2.0]);
+    assert_eq!(fifo, lifo);
+    assert_eq!(priority, vec![3.0, 2.0, 1.0]);
+
+    Ok(())
+}
+
+/// Interleave single sends with a broadcast batch
+struct Interleave;
+
+#[async_trait]
+impl ComponentSchema for Interleave {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, 1.into());
+        ctx.send(Out, 2.into());
+        ctx.send_all_broadcast(vec![3.into(), 4.into()]);
+        ctx.send(Out, 5.into());
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn packages_received_in_send_order() -> Result<()> {
+    let received = Flow::from_parts(
+        [Component::new(1, Interleave), Component::new(2, Drain)],
+        [Connection::new(1, 0, 2, 0)],
+    )?
+    .run(Vec::new())
+    .await?;
+
+    assert_eq!(received, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    Ok(())
+}
+
+struct Thousand;
+
+#[async_trait]
+impl ComponentSchema for Thousand {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<u32>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in 0..1000 {
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Paced;
+
+#[async_trait]
+impl ComponentSchema for Paced {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<u32>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while !ctx.should_yield() {
+            if ctx.receive(In).is_none() {
+                break;
+            }
+            let cicle = ctx.cicle();
+            ctx.with_mut_global(|global| global.push(cicle))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn cycle_budget() -> Result<()> {
+    let thousand = Component::new(1, Thousand);
+    let paced = Component::new(2, Paced).cycle_budget(100);
+
+    let connection = Connection::by(thousand.from(0), paced.to(0));
+
+    let cicles = Flow::new()
+        .add_component(thousand)?
+        .add_component(paced)?
+        .add_connection(connection)?
+        .run(Vec::new())
+        .await?;
+
+    assert_eq!(cicles.len(), 1000);
+    for cicle in 1..=10 {
+        let received = cicles.iter().filter(|c| **c == cicle).count();
+        assert_eq!(received, 100);
+    }
+
+    Ok(())
+}
+
+struct Stream;
+
+#[async_trait]
+impl ComponentSchema for Stream {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in [1, 2, 3] {
+            ctx.send(Out, number.into());
+        }
+        ctx.send_eos(Out);
+        Ok(Next::Continue)
+    }
+}
+
+/// Sum the packages received and flush the sum to global on end-of-stream
+#[derive(Default)]
+struct Summer {
+    sum: std::sync::Mutex<f64>,
+}
+
+#[async_trait]
+impl ComponentSchema for Summer {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let mut sum = self.sum.lock().unwrap();
+        let mut eos = false;
+        while let Some(package) = ctx.receive(In) {
+            if package.is_eos() {
+                eos = true;
+            } else {
+                *sum += package.get_number()?;
+            }
+        }
+        if eos {
+            let flushed = std::mem::take(&mut *sum);
+            ctx.with_mut_global(|global| global.push(flushed))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn end_of_stream() -> Result<()> {
+    let stream = Component::new(1, Stream);
+    let summer = Component::new(2, Summer::default());
+
+    let connection = Connection::by(stream.from(0), summer.to(0));
+
+    let flushed = Flow::new()
+        .add_component(stream)?
+        .add_component(summer)?
+        .add_connection(connection)?
+        .run(Vec::new())
+        .await?;
+
+    assert_eq!(flushed, vec![6.0]);
+
+    let eos: Option<f64> = Package::end_of_stream().try_into()?;
+    assert_eq!(eos, None);
+    assert!(Package::try_from(Package::end_of_stream())?.is_eos());
+
+    Ok(())
+}
+
+struct Increment;
+
+#[async_trait]
+impl ComponentSchema for Increment {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = u64;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for _ in 0..10 {
+            ctx.global_add(1u64);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn batched_global_add() -> Result<()> {
+    let mut flow = Flow::new();
+    for id in 1..=10 {
+        flow = flow.add_component(Component::new(id, Increment))?;
+    }
+
+    let counter = flow.run(5).await?;
+    assert_eq!(counter, 105);
+
+    Ok(())
+}
+
+#[derive(Outputs)]
+enum Split {
+    Left,
+    Right,
+}
+
+struct Splitter;
+
+#[async_trait]
+impl ComponentSchema for Splitter {
+    type Inputs = ();
+    type Outputs = Split;
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send_all_broadcast(vec![1.into(), 2.into(), 3.into()]);
+        Ok(Next::Continue)
+    }
+}
+
+struct Collect;
+
+#[async_trait]
+impl ComponentSchema for Collect {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn send_all_broadcast() -> Result<()> {
+    let splitter = Component::new(1, Splitter);
+    let left = Component::new(2, Collect);
+    let right = Component::new(3, Collect);
+
+    let connections = [
+        Connection::by(splitter.from(Split::Left.into_port()), left.to(0)),
+        Connection::by(splitter.from(Split::Right.into_port()), right.to(0)),
+    ];
+
+    let mut received = Flow::from_parts([splitter, left, right], connections)?
+        .run(Vec::new())
+        .await?;
+    received.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert_eq!(
+        received,
+        vec![(2, 1.0), (2, 2.0), (2, 3.0), (3, 1.0), (3, 2.0), (3, 3.0)]
+    );
+
+    Ok(())
+}
+
+#[derive(Inputs)]
+enum Pair {
+    Fast,
+    Slow,
+}
+
+struct Ping;
+
+#[async_trait]
+impl ComponentSchema for Ping {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<bool>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, Package::empty());
+        Ok(Next::Continue)
+    }
+}
+
+struct Relay;
+
+#[async_trait]
+impl ComponentSchema for Relay {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<bool>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Record if each input port received a package
+struct Join;
+
+#[async_trait]
+impl ComponentSchema for Join {
+    type Inputs = Pair;
+    type Outputs = ();
+
+    type Global = Vec<bool>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let fast = ctx.receive(Pair::Fast).is_some();
+        let slow = ctx.receive(Pair::Slow).is_some();
+        ctx.with_mut_global(|global| global.extend([fast, slow]))?;
+        Ok(Next::Continue)
+    }
+}
+
+async fn join_after_delay(ttl: Option<u32>) -> Result<Vec<bool>> {
+    // 1 -> 4 (fast), sent in cicle 1 and received in cicle 4
+    // 1 -> 2 -> 3 -> 4 (slow)
+    let ping = Component::new(1, Ping);
+    let relays = [2, 3].map(|id| Component::new(id, Relay));
+    let mut join = Component::new(4, Join);
+    if let Some(ttl) = ttl {
+        join = join.input_ttl(Pair::Fast.into_port(), ttl);
+    }
+
+    let connections = [
+        Connection::by(ping.from(0), join.to(Pair::Fast.into_port())),
+        Connection::by(ping.from(0), relays[0].to(0)),
+        Connection::by(relays[0].from(0), relays[1].to(0)),
+        Connection::by(relays[1].from(0), join.to(Pair::Slow.into_port())),
+    ];
+    let components = [ping, join].into_iter().chain(relays);
+
+    Flow::from_parts(components, connections)?
+        .run(Vec::new())
+        .await
+}
+
+#[tokio::test]
+async fn input_ttl_discard_stale_packages() -> Result<()> {
+    assert_eq!(join_after_delay(None).await?, vec![true, true]);
+    assert_eq!(join_after_delay(Some(3)).await?, vec![true, true]);
+    assert_eq!(join_after_delay(Some(2)).await?, vec![false, true]);
+
+    Ok(())
+}
+
+/// Only peek the input, recording the head and length
+struct Peek;
+
+#[async_trait]
+impl ComponentSchema for Peek {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let head = ctx.peek(In).cloned().map(Package::get_string).transpose()?;
+        let len = ctx.peek_len(In);
+        assert!(!ctx.consumed());
+        ctx.with_mut_global(|global| global.push((head.unwrap_or_default(), len)))?;
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn peek_without_consume() -> Result<()> {
+    let result = rs_flow::testing::Testing::new(Vec::new())
+        .input(In, "a".into())
+        .input(In, "b".into())
+        .test(Component::new(1, Peek))
+        .await?;
+    assert_eq!(result.global(), &vec![("a".to_owned(), 2)]);
+
+    let error = Flow::new()
+        .add_component(Component::new(1, Source("a")))?
+        .add_component(Component::new(2, Peek))?
+        .add_connection(Connection::new(1, 0, 2, 0))?
+        .run(Vec::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::AnyPackageConsumed { component: 2 })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn peek_skip_stale_packages() -> Result<()> {
+    let result = rs_flow::testing::Testing::new(Vec::new())
+        .input(In, "a".into())
+        .input_at(2, In, "b".into())
+        .test_cycles(Component::new(1, Peek).input_ttl(In.into_port(), 1), 4)
+        .await?;
+
+    // "a" is older than the ttl since the cicle 2, but never taken from the queue
+    assert_eq!(
+        result.global(),
+        &vec![
+            ("a".to_owned(), 1),
+            ("a".to_owned(), 1),
+            ("b".to_owned(), 1),
+            ("b".to_owned(), 1),
+        ]
+    );
+
+    Ok(())
+}
+
+struct Metrics {
+    runs: usize,
+}
+
+struct Config;
+
+/// Record the type names of global
+struct Shape;
+
+#[async_trait]
+impl ComponentSchema for Shape {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = (Metrics, Config);
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let names = ctx.global_type_names()?;
+        assert_eq!(names, vec!["ctx::Metrics", "ctx::Config"]);
+        ctx.with_mut_global(|(metrics, _)| metrics.runs += 1)?;
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn global_type_names() -> Result<()> {
+    let (metrics, _) = Flow::new()
+        .add_component(Component::new(1, Shape))?
+        .run((Metrics { runs: 0 }, Config))
+        .await?;
+    assert_eq!(metrics.runs, 1);
+
+    Ok(())
+}
+
+/// Count the runs in the global data of type [Metrics], and read a missing global
+/// of type [String] if `missing`
+struct Count {
+    missing: bool,
+}
+
+#[async_trait]
+impl ComponentSchema for Count {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = (Metrics, Config);
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut(|metrics: &mut Metrics| metrics.runs += 1)?;
+        ctx.with(|_: &Config| ())?;
+        assert_eq!(ctx.try_with(|_: &String| ())?, None);
+        if self.missing {
+            ctx.with(|_: &String| ())?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn global_of_type() -> Result<()> {
+    let (metrics, _) = Flow::new()
+        .add_component(Component::new(1, Count { missing: false }))?
+        .run((Metrics { runs: 0 }, Config))
+        .await?;
+    assert_eq!(metrics.runs, 1);
+
+    let error = Flow::new()
+        .add_component(Component::new(1, Count { missing: true }))?
+        .run((Metrics { runs: 0 }, Config))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::GlobalNotFound {
+            type_name: "alloc::string::String"
+        })
+    ));
+
+    Ok(())
+}
+
+struct Verbose(bool);
+
+/// Log the number of runs when verbose, reading three distinct globals of a [GlobalBag]
+struct Logged;
+
+#[async_trait]
+impl ComponentSchema for Logged {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = GlobalBag;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let runs = ctx.with_mut(|metrics: &mut Metrics| {
+            metrics.runs += 1;
+            metrics.runs
+        })?;
+        if ctx.with(|verbose: &Verbose| verbose.0)? {
+            ctx.with_mut(|log: &mut Vec<String>| log.push(format!("run {runs}")))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn global_bag() -> Result<()> {
+    let bag = GlobalBag::new()
+        .insert(Metrics { runs: 0 })
+        .insert(Verbose(true))
+        .insert(Vec::<String>::new());
+
+    let mut bag = Flow::new()
+        .add_component(Component::new(1, Logged))?
+        .add_component(Component::new(2, Logged))?
+        .run(bag)
+        .await?;
+
+    assert_eq!(bag.remove::<Metrics>().map(|metrics| metrics.runs), Some(2));
+    assert_eq!(
+        bag.remove::<Vec<String>>(),
+        Some(vec!["run 1".to_owned(), "run 2".to_owned()])
+    );
+    assert!(bag.contains::<Verbose>());
+
+    Ok(())
+}
+
+#[derive(Outputs)]
+enum Demux {
+    First,
+    Second,
+    Third,
+}
+
+struct Demultiplex;
+
+#[async_trait]
+impl ComponentSchema for Demultiplex {
+    type Inputs = ();
+    type Outputs = Demux;
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.fan([
+            (Demux::First, "a"),
+            (Demux::Second, "b"),
+            (Demux::Third, "c"),
+        ])?;
+        Ok(Next::Continue)
+    }
+}
+
+struct Record;
+
+#[async_trait]
+impl ComponentSchema for Record {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let message = package.get_string()?;
+            ctx.with_mut_global(|global| global.push((message, ctx.id())))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn fan_distinct_packages() -> Result<()> {
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Demultiplex),
+            Component::new(2, Record),
+            Component::new(3, Record),
+            Component::new(4, Record),
+        ],
+        [
+            Connection::new(1, Demux::First.into_port(), 2, 0),
+            Connection::new(1, Demux::Second.into_port(), 3, 0),
+            Connection::new(1, Demux::Third.into_port(), 4, 0),
+        ],
+    )?;
+
+    let mut received = flow.run(Vec::new()).await?;
+    received.sort();
+    assert_eq!(
+        received,
+        vec![
+            ("a".to_owned(), 2),
+            ("b".to_owned(), 3),
+            ("c".to_owned(), 4)
+        ]
+    );
+
+    Ok(())
+}
+
+struct Three;
+
+#[async_trait]
+impl ComponentSchema for Three {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<(Id, u32)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for _ in 0..3 {
+            ctx.send(Out, Package::empty());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Forward one package by cicle
+struct RelayOne;
+
+#[async_trait]
+impl ComponentSchema for RelayOne {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<(Id, u32)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        if let Some(package) = ctx.receive(In) {
+            ctx.with_mut_global(|global| global.push((ctx.id(), ctx.cicle())))?;
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Receive one package only in odd cicles
+struct OddSink;
+
+#[async_trait]
+impl ComponentSchema for OddSink {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(Id, u32)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        if ctx.cicle() % 2 == 1 && ctx.receive(In).is_some() {
+            ctx.with_mut_global(|global| global.push((ctx.id(), ctx.cicle())))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn input_capacity_back_pressure() -> Result<()> {
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Three),
+            Component::new(2, RelayOne),
+            Component::new(3, OddSink)
+                .allow_no_consume()
+                .input_capacity(0, 1, Overflow::Block),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?;
+
+    let received = flow.run(Vec::new()).await?;
+    let cicles_of = |id: Id| {
+        received
+            .iter()
+            .filter(|(from, _)| *from == id)
+            .map(|(_, cicle)| *cicle)
+            .collect::<Vec<_>>()
+    };
+
+    // the relay not run while your package wait space in the sink
+    assert_eq!(cicles_of(2), vec![1, 2, 4]);
+    assert_eq!(cicles_of(3), vec![3, 5, 7]);
+
+    Ok(())
+}
+
+async fn drain_with_capacity(capacity: usize, overflow: Overflow) -> Result<Vec<f64>> {
+    Flow::from_parts(
+        [
+            Component::new(1, Numbers),
+            Component::new(2, Drain).input_capacity(0, capacity, overflow),
+        ],
+        [Connection::new(1, 0, 2, 0)],
+    )?
+    .run(Vec::new())
+    .await
+}
+
+#[tokio::test]
+async fn input_capacity_overflow() -> Result<()> {
+    let error = drain_with_capacity(2, Overflow::Error).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::QueueFull {
+            component: 2,
+            port: 0
+        })
+    ));
+
+    assert_eq!(drain_with_capacity(3, Overflow::Error).await?.len(), 3);
+    assert_eq!(
+        drain_with_capacity(2, Overflow::DropOldest).await?,
+        vec![1.0, 2.0]
+    );
+
+    Ok(())
+}
+
+/// Receive the slow port before the fast port
+struct Triage;
+
+#[async_trait]
+impl ComponentSchema for Triage {
+    type Inputs = Pair;
+    type Outputs = ();
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some((port, package)) = ctx.receive_prioritized(&[Pair::Slow, Pair::Fast]) {
+            let message = package.get_string()?;
+            ctx.with_mut_global(|global| global.push((message, port as Id)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn receive_prioritized() -> Result<()> {
+    let result = rs_flow::testing::Testing::new(Vec::new())
+        .input(Pair::Fast, "f1".into())
+        .input(Pair::Fast, "f2".into())
+        .input(Pair::Slow, "s1".into())
+        .test(Component::new(1, Triage))
+        .await?;
+
+    let fast = Pair::Fast.into_port() as Id;
+    let slow = Pair::Slow.into_port() as Id;
+    assert_eq!(
+        result.into_global(),
+        vec![
+            ("s1".to_owned(), slow),
+            ("f1".to_owned(), fast),
+            ("f2".to_owned(), fast)
+        ]
+    );
+
+    Ok(())
+}
+
+#[derive(Inputs)]
+enum Triple {
+    A,
+    B,
+    C,
+}
+
+/// Zip the ports defined at runtime
+struct Zip(Vec<PortId>);
+
+#[async_trait]
+impl ComponentSchema for Zip {
+    type Inputs = Triple;
+    type Outputs = ();
+
+    type Global = Vec<Vec<String>>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(packages) = ctx.receive_many_dyn(&self.0)? {
+            let strings = packages
+                .into_iter()
+                .map(Package::get_string)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            ctx.with_mut_global(|global| global.push(strings))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn receive_many_dyn() -> Result<()> {
+    let testing = || {
+        rs_flow::testing::Testing::new(Vec::new())
+            .input(Triple::A, "a1".into())
+            .input(Triple::A, "a2".into())
+            .input(Triple::B, "b1".into())
+            .input(Triple::B, "b2".into())
+            .input(Triple::C, "c1".into())
+    };
+
+    let result = testing()
+        .test(Component::new(1, Zip(vec![2, 0, 1])))
+        .await?;
+    assert_eq!(result.into_global(), vec![vec!["c1", "a1", "b1"]]);
+
+    let result = testing().test(Component::new(1, Zip(vec![0, 1]))).await?;
+    assert_eq!(
+        result.into_global(),
+        vec![vec!["a1", "b1"], vec!["a2", "b2"]]
+    );
+
+    let error = testing()
+        .test(Component::new(1, Zip(vec![0, 1, 0])))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::InvalidMultipleRecivedPorts { component: 1, .. })
+    ));
+
+    Ok(())
+}
+
+struct Forward;
+
+#[async_trait]
+impl ComponentSchema for Forward {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[derive(Inputs)]
+enum Gate {
+    Data,
+    #[optional]
+    Control,
+}
+
+/// Pause the data after each package received, until receive a control package
+struct Window;
+
+#[async_trait]
+impl ComponentSchema for Window {
+    type Inputs = Gate;
+    type Outputs = ();
+
+    type Global = Vec<(String, Id)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let cicle = ctx.cicle() as Id;
+        if ctx.receive(Gate::Control).is_some() {
+            ctx.reopen(Gate::Data);
+            ctx.with_mut_global(|global| global.push(("reopen".to_owned(), cicle)))?;
+        }
+        while let Some(package) = ctx.receive(Gate::Data) {
+            let message = package.get_string()?;
+            ctx.with_mut_global(|global| global.push((message, cicle)))?;
+            ctx.close(Gate::Data);
+        }
+        assert!(!ctx.is_closed(Gate::Data) || ctx.peek(Gate::Data).is_none());
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn close_and_reopen_input() -> Result<()> {
+    // 1 -> 10 (data)                       received in cicle 2
+    // 1 -> 2 -> 10 (data)                  discarded in cicle 3
+    // 1 -> 2 -> 3 -> 10 (control)          reopen in cicle 4
+    // 1 -> 2 -> 3 -> 4 -> 10 (data)        received in cicle 5
+    let data = Gate::Data.into_port();
+    let control = Gate::Control.into_port();
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Source("a")),
+            Component::new(2, Forward),
+            Component::new(3, Forward),
+            Component::new(4, Forward),
+            Component::new(10, Window),
+        ],
+        [
+            Connection::new(1, 0, 10, data),
+            Connection::new(1, 0, 2, 0),
+            Connection::new(2, 0, 10, data),
+            Connection::new(2, 0, 3, 0),
+            Connection::new(3, 0, 10, control),
+            Connection::new(3, 0, 4, 0),
+            Connection::new(4, 0, 10, data),
+        ],
+    )?;
+
+    let received = flow.run(Vec::new()).await?;
+    assert_eq!(
+        received,
+        vec![
+            ("a".to_owned(), 1),
+            ("reopen".to_owned(), 3),
+            ("a".to_owned(), 4)
+        ]
+    );
+
+    Ok(())
+}
+
+struct Ticker;
+
+#[async_trait]
+impl ComponentSchema for Ticker {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let tick = ctx.state_mut::<u32>();
+        *tick += 1;
+        let tick = *tick;
+
+        ctx.send(Out, tick.into());
+        Ok(if tick < 3 {
+            Next::Retry
+        } else {
+            Next::Continue
+        })
+    }
+}
+
+struct RunningSum;
+
+#[async_trait]
+impl ComponentSchema for RunningSum {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            *ctx.state_mut::<f64>() += package.get_number()?;
+        }
+        let sum = *ctx.state_mut::<f64>();
+        ctx.with_mut_global(|global| global.push(sum))?;
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn state_across_cycles() -> Result<()> {
+    let ticker = Component::new(1, Ticker);
+    let sum = Component::new(2, RunningSum);
+    let conn = Connection::by(ticker.from(0), sum.to(0));
+
+    let flow = Flow::new()
+        .add_component(ticker)?
+        .add_component(sum)?
+        .add_connection(conn)?;
+
+    assert_eq!(flow.run(Vec::new()).await?, vec![1.0, 3.0, 6.0]);
+    // state is fresh in each run
+    assert_eq!(flow.run(Vec::new()).await?, vec![1.0, 3.0, 6.0]);
+
+    Ok(())
+}
+
+/// Forward all packages received, recording the cicle that run
+struct Stamp;
+
+#[async_trait]
+impl ComponentSchema for Stamp {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<(Id, u32)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut_global(|global| global.push((ctx.id(), ctx.cicle())))?;
+        while let Some(package) = ctx.receive(In) {
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn cicle_of_each_wave() -> Result<()> {
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Three),
+            Component::new(2, Stamp),
+            Component::new(3, Stamp),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?;
+
+    let stamps = flow.run(Vec::new()).await?;
+    assert_eq!(stamps, vec![(2, 1), (3, 2)]);
+
+    Ok(())
+}
+
+type Depths = Vec<(Vec<(PortId, usize)>, bool)>;
+
+struct Depth;
+
+#[async_trait]
+impl ComponentSchema for Depth {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Depths;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in [1, 2, 3] {
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Record the pending packages of each port, then close the port `Slow`
+struct Probe;
+
+#[async_trait]
+impl ComponentSchema for Probe {
+    type Inputs = Pair;
+    type Outputs = ();
+
+    type Global = Depths;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for _ in 0..2 {
+            let mut pending = ctx.pending_all().into_iter().collect::<Vec<_>>();
+            pending.sort();
+            let consumed = ctx.consumed();
+            ctx.with_mut_global(|global| global.push((pending, consumed)))?;
+            ctx.close(Pair::Slow);
+        }
+        assert_eq!(ctx.pending(Pair::Fast), 3);
+        assert_eq!(ctx.pending(Pair::Slow), 0);
+
+        while ctx.receive(Pair::Fast).is_some() {}
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn pending_without_consume() -> Result<()> {
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Depth),
+            Component::new(2, Depth),
+            Component::new(3, Probe),
+        ],
+        [
+            Connection::new(1, 0, 3, Pair::Fast.into_port()),
+            Connection::new(2, 0, 3, Pair::Slow.into_port()),
+        ],
+    )?;
+
+    let depths = flow.run(Vec::new()).await?;
+    assert_eq!(
+        depths,
+        vec![(vec![(0, 3), (1, 3)], false), (vec![(0, 3), (1, 0)], false)]
+    );
+
+    Ok(())
+}
+
+#[derive(Outputs)]
+enum Branch {
+    Normal,
+    Paced,
+}
+
+struct Pacer;
+
+#[async_trait]
+impl ComponentSchema for Pacer {
+    type Inputs = ();
+    type Outputs = Branch;
+
+    type Global = Vec<(Id, u32)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Branch::Normal, Package::empty());
+        ctx.send(Branch::Paced, Package::empty());
+        ctx.skip_downstream_this_cycle(Branch::Paced);
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn skip_downstream_this_cycle() -> Result<()> {
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Pacer),
+            Component::new(2, Stamp),
+            Component::new(3, Stamp),
+            Component::new(4, Stamp),
+        ],
+        [
+            Connection::new(1, Branch::Normal.into_port(), 2, 0),
+            Connection::new(1, Branch::Paced.into_port(), 3, 0),
+            Connection::new(3, 0, 4, 0),
+        ],
+    )?;
+
+    let mut stamps = flow.run(Vec::new()).await?;
+    stamps.sort();
+    assert_eq!(stamps, vec![(2, 1), (3, 2), (4, 3)]);
+
+    // a cicle without ready components deliver the held packages
+    let flow = Flow::from_parts(
+        [Component::new(1, Pacer), Component::new(3, Stamp)],
+        [Connection::new(1, Branch::Paced.into_port(), 3, 0)],
+    )?;
+    assert_eq!(flow.run(Vec::new()).await?, vec![(3, 2)]);
+
+    Ok(())
+}
+
+/// Send the odd numbers only to 2, the even numbers only to 3, and 0 to `missing` if defined
+struct Dispatch {
+    missing: Option<Id>,
+}
+
+#[async_trait]
+impl ComponentSchema for Dispatch {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in 1..=4 {
+            let target = if number % 2 == 1 { 2 } else { 3 };
+            ctx.send_to(Out, target, number.into())?;
+        }
+        if let Some(missing) = self.missing {
+            ctx.send_to(Out, missing, 0.into())?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Received;
+
+#[async_trait]
+impl ComponentSchema for Received {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+async fn run_dispatch(missing: Option<Id>) -> Result<Vec<(Id, f64)>> {
+    Flow::from_parts(
+        [
+            Component::new(1, Dispatch { missing }),
+            Component::new(2, Received),
+            Component::new(3, Received),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(1, 0, 3, 0)],
+    )?
+    .run(Vec::new())
+    .await
+}
+
+#[tokio::test]
+async fn send_to_single_target() -> Result<()> {
+    let mut received = run_dispatch(None).await?;
+    received.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    assert_eq!(received, vec![(2, 1.0), (2, 3.0), (3, 2.0), (3, 4.0)]);
+
+    let error = run_dispatch(Some(9)).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::TargetNotConnected {
+            component: 1,
+            out_port: 0,
+            target: 9
+        })
+    ));
+
+    Ok(())
+}
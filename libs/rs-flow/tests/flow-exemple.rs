@@ -42,7 +42,7 @@ impl ComponentSchema for Green {
             ctx.id(),
             ctx.cicle()
         );
-        while let Some(_) = ctx.receive(In) {}
+        while ctx.receive(In).is_some() {}
 
         Ok(Next::Continue)
     }
@@ -62,7 +62,7 @@ impl ComponentSchema for Blue {
             ctx.cicle()
         );
 
-        while let Some(_) = ctx.receive(In) {}
+        while ctx.receive(In).is_some() {}
         ctx.send(Out, Package::Empty);
 
         Ok(Next::Continue)
@@ -109,9 +109,75 @@ async fn flow_example() -> Result<()> {
 
     println!("Initing Flow::run");
 
-    let _ = flow.run(()).await?;
+    flow.run(()).await?;
 
     println!("Flow::run finished");
 
     Ok(())
 }
+
+/// Same flow of `flow_example`, created by [Flow::from_parts]
+fn flow() -> Result<Flow<()>> {
+    let one = Component::new(1, Red);
+    let two = Component::new(2, Red);
+
+    let three = Component::new(3, Blue);
+    let four = Component::new(4, Blue);
+    let five = Component::eager(5, Blue);
+    let six = Component::new(6, Blue);
+    let seven = Component::new(7, Blue);
+    let eight = Component::new(8, Blue);
+
+    let nine = Component::new(9, Green);
+    let ten = Component::new(10, Green);
+
+    let connections = [
+        Connection::by(one.from(0), three.to(0)),
+        Connection::by(one.from(0), five.to(0)),
+        Connection::by(two.from(0), four.to(0)),
+        Connection::by(four.from(0), five.to(0)),
+        Connection::by(three.from(0), eight.to(0)),
+        Connection::by(five.from(0), six.to(0)),
+        Connection::by(five.from(0), seven.to(0)),
+        Connection::by(seven.from(0), eight.to(0)),
+        Connection::by(seven.from(0), nine.to(0)),
+        Connection::by(eight.from(0), ten.to(0)),
+    ];
+
+    let components = [one, two, three, four, five, six, seven, eight, nine, ten];
+
+    Ok(Flow::from_parts(components, connections)?)
+}
+
+#[test]
+fn flow_parts_round_trip() -> Result<()> {
+    let (components, connections, _) = flow()?.into_parts();
+    let ids = components.iter().map(|c| c.id()).collect::<Vec<_>>();
+    assert_eq!(ids, (1..=10).collect::<Vec<_>>());
+    assert_eq!(connections.len(), 10);
+
+    let (recomposed, reconnected, _) =
+        Flow::from_parts(components, connections.clone())?.into_parts();
+    let recomposed = recomposed
+        .iter()
+        .map(|c| (c.id(), c.ty()))
+        .collect::<Vec<_>>();
+    let expected = (1..=10)
+        .map(|id| (id, if id == 5 { Type::Eager } else { Type::Lazy }))
+        .collect::<Vec<_>>();
+    assert_eq!(recomposed, expected);
+    assert_eq!(reconnected, connections);
+
+    Ok(())
+}
+
+#[test]
+fn sources_and_sinks() -> Result<()> {
+    let flow = flow()?;
+
+    assert_eq!(flow.sources(), vec![1, 2]);
+    // the Blue 6 is also a sink, becaurse your output is not connected
+    assert_eq!(flow.sinks(), vec![6, 9, 10]);
+
+    Ok(())
+}
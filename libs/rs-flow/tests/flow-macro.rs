@@ -0,0 +1,135 @@
+use rs_flow::prelude::*;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+struct Message {
+    text: String,
+}
+
+#[async_trait]
+impl ComponentSchema for Message {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<String>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, self.text.as_str().into());
+        Ok(Next::Continue)
+    }
+}
+
+struct Log;
+
+#[async_trait]
+impl ComponentSchema for Log {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<String>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let text = package.get_string()?;
+            ctx.with_mut_global(|global| global.push(text))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn flow_macro() -> Result<()> {
+    let flow = flow! {
+        components: {
+            1 => Message { text: "hello".to_owned() },
+            2 => Message { text: "world".to_owned() },
+            3 => Log,
+        },
+        connections: {
+            1.Out -> 3.In,
+            2.Out -> 3.In,
+        }
+    }?;
+
+    let mut global = flow.run(Vec::new()).await?;
+    global.sort();
+    assert_eq!(global, vec!["hello".to_owned(), "world".to_owned()]);
+
+    let flow: Flow<Vec<String>> = flow! { components: { 1 => Log } }?;
+    assert_eq!(flow.into_parts().0.len(), 1);
+
+    let duplicated = flow! {
+        components: {
+            1 => Log,
+            1 => Log,
+        }
+    };
+    assert!(matches!(
+        duplicated.err(),
+        Some(Error::ComponentAlreadyExist { id: 1 })
+    ));
+
+    Ok(())
+}
+
+/// Join the text of the left input with the optional text of the right input
+struct Join;
+
+#[component(
+    inputs(Left, #[optional] Right),
+    outputs(#[description("Left and right joined by a space")] Joined)
+)]
+#[async_trait]
+impl ComponentSchema for Join {
+    type Global = Vec<String>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        if let Some(left) = ctx.receive(JoinInputs::Left) {
+            let mut text = left.get_string()?;
+            if let Some(right) = ctx.receive(JoinInputs::Right) {
+                text = format!("{text} {}", right.get_string()?);
+            }
+            ctx.send(JoinOutputs::Joined, text.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn component_attribute() -> Result<()> {
+    assert_eq!(JoinInputs::PORTS.port_id_of("Right"), Some(1));
+    assert!(JoinInputs::PORTS.get(1).unwrap().optional);
+    let outputs = JoinOutputs::PORTS;
+    let joined = outputs.get(JoinOutputs::Joined.into_port()).unwrap();
+    assert_eq!(joined.description, Some("Left and right joined by a space"));
+
+    let flow = flow! {
+        components: {
+            1 => Message { text: "hello".to_owned() },
+            2 => Message { text: "world".to_owned() },
+            3 => Join,
+            4 => Log,
+        },
+        connections: {
+            1.Out -> 3.JoinInputs::Left,
+            2.Out -> 3.JoinInputs::Right,
+            3.JoinOutputs::Joined -> 4.In,
+        }
+    }?;
+    assert_eq!(flow.run(Vec::new()).await?, vec!["hello world".to_owned()]);
+
+    Ok(())
+}
+
+#[test]
+fn flow_macro_ui() {
+    let tests = trybuild::TestCases::new();
+    tests.pass("tests/ui/flow-macro-valid.rs");
+    tests.compile_fail("tests/ui/flow-macro-unknown-label.rs");
+    tests.compile_fail("tests/ui/component-port-with-fields.rs");
+    tests.compile_fail("tests/ui/duplicated-port-id.rs");
+}
@@ -0,0 +1,2044 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+
+use rs_flow::connection::Point;
+use rs_flow::ports::PortsError;
+use rs_flow::prelude::*;
+use rs_flow::{
+    ComponentRun, FlowEvent, FlowSnapshot, Instrumentation, PackageKind, PackageSchema,
+    PackageTracer, RunStats,
+};
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+struct Panic;
+
+#[async_trait]
+impl ComponentSchema for Panic {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, _ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        panic!("plugin failure");
+    }
+}
+
+struct Emit;
+
+#[async_trait]
+impl ComponentSchema for Emit {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, Package::empty());
+        Ok(Next::Continue)
+    }
+}
+
+struct Consume;
+
+#[async_trait]
+impl ComponentSchema for Consume {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while ctx.receive(In).is_some() {
+            ctx.with_mut_global(|global| global.push(ctx.id()))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn catch_panics_of_isolated_component() -> Result<()> {
+    let emit = Component::new(1, Emit);
+    let panic = Component::catch_panics(2, Panic);
+    let consume = Component::new(3, Consume);
+
+    let connection = Connection::by(emit.from(0), consume.to(0));
+
+    let flow = Flow::new()
+        .add_component(emit)?
+        .add_component(panic)?
+        .add_component(consume)?
+        .add_connection(connection)?;
+
+    let error = flow.run(Vec::new()).await.unwrap_err();
+    match error.downcast_ref::<Error>() {
+        Some(Error::ComponentPanicked { id, message }) => {
+            assert_eq!(*id, 2);
+            assert_eq!(message, "plugin failure");
+        }
+        _ => panic!("Expected a ComponentPanicked error, found {error:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn max_fan_in_and_fan_out() -> Result<()> {
+    let sources = [1, 2, 3].map(|id| Component::new(id, Emit));
+    let hub = Component::new(4, Consume);
+    let emit = Component::new(5, Emit);
+    let consumers = [6, 7].map(|id| Component::new(id, Consume));
+
+    let mut connections: Vec<Connection> = sources
+        .iter()
+        .map(|source| Connection::by(source.from(0), hub.to(0)))
+        .collect();
+    connections.extend(
+        consumers
+            .iter()
+            .map(|consumer| Connection::by(emit.from(0), consumer.to(0))),
+    );
+
+    let mut flow = Flow::<Vec<Id>>::new();
+    assert_eq!(flow.max_fan_in(), 0);
+    assert_eq!(flow.max_fan_out(), 0);
+
+    for component in sources.into_iter().chain([hub, emit]).chain(consumers) {
+        flow = flow.add_component(component)?;
+    }
+    for connection in connections {
+        flow = flow.add_connection(connection)?;
+    }
+
+    assert_eq!(flow.max_fan_in(), 3);
+    assert_eq!(flow.max_fan_out(), 2);
+
+    Ok(())
+}
+
+struct Tee;
+
+#[async_trait]
+impl ComponentSchema for Tee {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            ctx.with_mut_global(|global| global.push(ctx.id()))?;
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn insert_tee_on_every_edge() -> Result<()> {
+    let a = Component::new(1, Emit);
+    let b = Component::new(2, Emit);
+    let consume = Component::new(3, Consume);
+
+    let conn_a = Connection::by(a.from(0), consume.to(0));
+    let conn_b = Connection::by(b.from(0), consume.to(0));
+
+    let mut flow = Flow::new()
+        .add_component(a)?
+        .add_component(b)?
+        .add_component(consume)?
+        .add_connection(conn_a.clone())?
+        .add_connection(conn_b.clone())?;
+
+    for (id, connection) in [(10, conn_a), (20, conn_b)] {
+        flow = flow.insert_between(connection, Component::new(id, Tee))?;
+    }
+
+    let mut visited = flow.run(Vec::new()).await?;
+    visited.sort();
+    assert_eq!(visited, vec![3, 3, 10, 20]);
+
+    // connections are rewritten, the direct connection no longer exist
+    let error = flow
+        .insert_between(Connection::new(1, 0, 3, 0), Component::new(30, Tee))
+        .err();
+    assert!(matches!(error, Some(Error::ConnectionNotFound { .. })));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn map_connections_redirect() -> Result<()> {
+    let emit = Component::new(1, Emit);
+    let first = Component::new(2, Consume);
+    let second = Component::new(3, Consume);
+
+    let connection = Connection::by(emit.from(0), first.to(0));
+
+    let flow = Flow::new()
+        .add_component(emit)?
+        .add_component(first)?
+        .add_component(second)?
+        .add_connection(connection)?
+        .map_connections(|c| vec![c.clone(), Connection::new(c.from, c.out_port, 3, 0)])?;
+
+    let mut visited = flow.run(Vec::new()).await?;
+    visited.sort();
+    assert_eq!(visited, vec![2, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn diamond_is_not_a_loop() -> Result<()> {
+    let a = Component::new(1, Emit);
+    let b = Component::new(2, Tee);
+    let c = Component::new(3, Tee);
+
+    let connections = [
+        Connection::by(a.from(0), b.to(0)),
+        Connection::by(b.from(0), c.to(0)),
+        Connection::by(a.from(0), c.to(0)),
+    ];
+    let flow = Flow::from_parts([a, b, c], connections)?;
+
+    let error = flow.add_connection(Connection::new(3, 0, 2, 0)).err();
+    assert!(matches!(error, Some(Error::LoopCreated { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn dry_run_connection_checks() -> Result<()> {
+    let a = Component::new(1, Emit);
+    let b = Component::new(2, Tee);
+    let c = Component::new(3, Consume);
+
+    let connections = [
+        Connection::by(a.from(0), b.to(0)),
+        Connection::by(b.from(0), c.to(0)),
+    ];
+    let flow = Flow::from_parts([a, b, c], connections)?;
+
+    let error = flow.can_add_connection(&Connection::new(4, 0, 2, 0)).err();
+    assert!(matches!(error, Some(Error::ComponentNotFound { id: 4 })));
+
+    let error = flow.can_add_connection(&Connection::new(2, 0, 4, 0)).err();
+    assert!(matches!(error, Some(Error::ComponentNotFound { id: 4 })));
+
+    let error = flow.can_add_connection(&Connection::new(1, 1, 3, 0)).err();
+    assert!(matches!(
+        error,
+        Some(Error::OutPortNotFound {
+            component: 1,
+            out_port: 1
+        })
+    ));
+
+    let error = flow.can_add_connection(&Connection::new(1, 0, 3, 1)).err();
+    assert!(matches!(
+        error,
+        Some(Error::InPortNotFound {
+            component: 3,
+            in_port: 1
+        })
+    ));
+
+    let error = flow.can_add_connection(&Connection::new(1, 0, 2, 0)).err();
+    assert!(matches!(error, Some(Error::ConnectionAlreadyExist { .. })));
+
+    let looping = Connection::new(2, 0, 2, 0);
+    assert!(flow.would_create_loop(&looping));
+    let error = flow.can_add_connection(&looping).err();
+    assert!(matches!(error, Some(Error::LoopCreated { .. })));
+
+    let shortcut = Connection::new(1, 0, 3, 0);
+    assert!(!flow.would_create_loop(&shortcut));
+    flow.can_add_connection(&shortcut)?;
+    assert_eq!(flow.max_fan_in(), 1);
+
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[derive(Default, serde::Serialize)]
+struct State {
+    counter: u64,
+    names: Vec<String>,
+}
+
+#[cfg(feature = "json")]
+struct Record;
+
+#[cfg(feature = "json")]
+#[async_trait]
+impl ComponentSchema for Record {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = State;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut_global(|state| {
+            state.counter += 1;
+            state.names.push(format!("record-{}", ctx.id()));
+        })?;
+        Ok(Next::Continue)
+    }
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn global_dump() -> Result<()> {
+    let flow = Flow::from_parts([Component::new(1, Record)], [])?;
+
+    let dump = rs_flow::GlobalDump::default();
+    let state = flow.run_instrumented(State::default(), &dump).await?;
+    let dump = dump.into_dump()?;
+
+    assert_eq!(state.counter, 1);
+    assert_eq!(dump.len(), 2);
+    assert_eq!(dump["counter"], serde_json::json!(1));
+    assert_eq!(dump["names"], serde_json::json!(["record-1"]));
+
+    Ok(())
+}
+
+/// Forward the packages and prune your downstream
+struct Prune;
+
+#[async_trait]
+impl ComponentSchema for Prune {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            ctx.with_mut_global(|global| global.push(ctx.id()))?;
+            ctx.send(Out, package);
+        }
+        ctx.prune_downstream();
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn prune_one_branch_of_diamond() -> Result<()> {
+    // 1 -> 2 (prune) -> 3 -> 6
+    //   -> 4 -------- -> 5 -> 6
+    let source = Component::new(1, Emit);
+    let prune = Component::new(2, Prune);
+    let pruned = Component::new(3, Tee);
+    let live = [4, 5].map(|id| Component::new(id, Tee));
+    let join = Component::new(6, Consume);
+
+    let connections = [
+        Connection::by(source.from(0), prune.to(0)),
+        Connection::by(prune.from(0), pruned.to(0)),
+        Connection::by(pruned.from(0), join.to(0)),
+        Connection::by(source.from(0), live[0].to(0)),
+        Connection::by(live[0].from(0), live[1].to(0)),
+        Connection::by(live[1].from(0), join.to(0)),
+    ];
+    let components = [source, prune, pruned, join].into_iter().chain(live);
+
+    let mut visited = Flow::from_parts(components, connections)?
+        .run(Vec::new())
+        .await?;
+    visited.sort();
+
+    assert_eq!(visited, vec![2, 4, 5, 6]);
+
+    Ok(())
+}
+
+/// Receive a single package by cicle
+struct Slow;
+
+#[async_trait]
+impl ComponentSchema for Slow {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.receive(In);
+        ctx.with_mut_global(|global| global.push(ctx.id()))?;
+        Ok(Next::Continue)
+    }
+}
+
+struct Burst;
+
+#[async_trait]
+impl ComponentSchema for Burst {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for _ in 0..100 {
+            ctx.send(Out, Package::empty());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn cycle_limit() -> Result<()> {
+    let flow = Flow::from_parts(
+        [Component::new(1, Burst), Component::new(2, Slow)],
+        [Connection::new(1, 0, 2, 0)],
+    )?;
+
+    let error = flow.run_with_limit(Vec::new(), 10).await.unwrap_err();
+    let error = error.downcast::<Error>().unwrap();
+    assert!(matches!(*error, Error::CycleLimitExceeded { cicles: 10 }));
+
+    // the burst run in the first cicle and the slow in the next 100 cicles
+    let received = flow.run_with_limit(Vec::new(), 101).await?;
+    assert_eq!(received.len(), 100);
+
+    Ok(())
+}
+
+#[derive(Inputs)]
+enum Both {
+    Left,
+    Right,
+}
+
+struct Zip;
+
+#[async_trait]
+impl ComponentSchema for Zip {
+    type Inputs = Both;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while ctx.receive(Both::Left).is_some() && ctx.receive(Both::Right).is_some() {}
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn deadlock_with_pending_packages() -> Result<()> {
+    // 1 -> 3 (left)
+    // 2 -> 3 (right), but 2 never run because nothing is sent to it
+    let emit = Component::new(1, Emit);
+    let idle = Component::new(2, Tee);
+    let zip = Component::new(3, Zip);
+
+    let connections = [
+        Connection::by(emit.from(0), zip.to(Both::Left.into_port())),
+        Connection::by(idle.from(0), zip.to(Both::Right.into_port())),
+    ];
+    let flow = Flow::from_parts([emit, idle, zip], connections)?;
+
+    let error = flow.run(Vec::new()).await.unwrap_err();
+    let error = error.downcast::<Error>().unwrap();
+    assert!(matches!(*error, Error::Deadlock { pending } if pending == vec![(3, 0, 1)]));
+
+    Ok(())
+}
+
+/// Never receive the packages, only break the flow in the third cicle
+struct Watch;
+
+#[async_trait]
+impl ComponentSchema for Watch {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut_global(|global| global.push(ctx.id()))?;
+        if ctx.cicle() == 2 {
+            return Ok(Next::Break);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn allow_no_consume() -> Result<()> {
+    let connections = [Connection::new(1, 0, 2, 0)];
+
+    let flow = Flow::from_parts(
+        [Component::new(1, Emit), Component::new(2, Watch)],
+        connections.clone(),
+    )?;
+    let error = flow.run(Vec::new()).await.unwrap_err();
+    let error = error.downcast::<Error>().unwrap();
+    assert!(matches!(*error, Error::AnyPackageConsumed { component: 2 }));
+
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Watch).allow_no_consume(),
+        ],
+        connections,
+    )?;
+    let watched = flow.run_with_limit(Vec::new(), 10).await?;
+    assert_eq!(watched, vec![2, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn remove_component_and_connection() -> Result<()> {
+    let components = [1, 2, 3].map(|id| Component::new(id, Tee));
+    let connections = [
+        Connection::new(1, 0, 2, 0),
+        Connection::new(2, 0, 3, 0),
+        Connection::new(1, 0, 3, 0),
+    ];
+    let mut flow = Flow::from_parts(components, connections)?;
+
+    let removed = flow.remove_component(2)?;
+    assert_eq!(removed.id(), 2);
+    assert!(matches!(
+        flow.remove_component(2).err(),
+        Some(Error::ComponentNotFound { id: 2 })
+    ));
+
+    let back = Connection::new(3, 0, 1, 0);
+    assert!(flow.would_create_loop(&back));
+
+    let shortcut = Connection::new(1, 0, 3, 0);
+    flow.remove_connection(&shortcut)?;
+    assert!(matches!(
+        flow.remove_connection(&shortcut).err(),
+        Some(Error::ConnectionNotFound { .. })
+    ));
+
+    // without connections 1 is no more a ancestor of 3
+    assert!(!flow.would_create_loop(&back));
+
+    let (components, connections, _) = flow.add_connection(back.clone())?.into_parts();
+    let ids = components.iter().map(Component::id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 3]);
+    assert_eq!(connections, vec![back]);
+
+    Ok(())
+}
+
+#[test]
+fn groups_in_dot() -> Result<()> {
+    let components = [Component::new(1, Emit)]
+        .into_iter()
+        .chain([2, 3].map(|id| Component::new(id, Tee)))
+        .chain([Component::new(4, Consume)]);
+    let connections = [
+        Connection::new(1, 0, 2, 0),
+        Connection::new(2, 0, 3, 0),
+        Connection::new(3, 0, 4, 0),
+    ];
+
+    let flow = Flow::<Vec<Id>>::from_parts(components, connections)?
+        .group("transform", &[2, 3])?
+        .group("io", &[1])?
+        .group("io", &[4, 1])?;
+
+    assert_eq!(flow.groups()["transform"], vec![2, 3]);
+    assert_eq!(flow.groups()["io"], vec![1, 4]);
+    assert!(matches!(
+        flow.group("missing", &[5]).err(),
+        Some(Error::ComponentNotFound { id: 5 })
+    ));
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Emit), Component::new(2, Consume)],
+        [Connection::new(1, 0, 2, 0)],
+    )?
+    .group("sink", &[2])?;
+    assert_eq!(
+        flow.to_dot(),
+        concat!(
+            "digraph {\n",
+            "  subgraph cluster_0 {\n",
+            "    label=\"sink\";\n",
+            "    2 [label=\"2 (Lazy)\"];\n",
+            "  }\n",
+            "  1 [label=\"1 (Lazy)\", style=bold];\n",
+            "  1 -> 2 [label=\"Out -> In\"];\n",
+            "}\n"
+        )
+    );
+
+    Ok(())
+}
+
+#[derive(Outputs)]
+enum Response {
+    Body,
+    #[optional]
+    #[description("Request failed")]
+    Error,
+}
+
+/// Send to both outputs
+struct Request;
+
+#[async_trait]
+impl ComponentSchema for Request {
+    type Inputs = ();
+    type Outputs = Response;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Response::Body, Package::empty());
+        ctx.send(Response::Error, Package::empty());
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn strict_outputs_with_optional_output() -> Result<()> {
+    assert!(
+        !Response::PORTS
+            .get(Response::Body.into_port())
+            .unwrap()
+            .optional
+    );
+    assert!(
+        Response::PORTS
+            .get(Response::Error.into_port())
+            .unwrap()
+            .optional
+    );
+
+    let body = Response::Body.into_port();
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Request), Component::new(2, Consume)],
+        [Connection::new(1, body, 2, 0)],
+    )?
+    .strict_outputs();
+    flow.run(Vec::new()).await?;
+
+    let error = Flow::<Vec<Id>>::new()
+        .add_component(Component::new(1, Request))?
+        .strict_outputs()
+        .run(Vec::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::OutPortNotConnected { component: 1, out_port }) if *out_port == body
+    ));
+
+    Ok(())
+}
+
+/// Send your id as a integer
+struct EmitId;
+
+#[async_trait]
+impl ComponentSchema for EmitId {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, ctx.id().into());
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn trace_package_path() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, EmitId),
+            Component::new(2, Tee),
+            Component::new(3, Consume),
+            Component::new(4, EmitId),
+            Component::new(5, Consume),
+        ],
+        [
+            Connection::new(1, 0, 2, 0),
+            Connection::new(2, 0, 3, 0),
+            Connection::new(4, 0, 5, 0),
+        ],
+    )?;
+
+    let tracer = PackageTracer::new(Package::integer(1));
+    flow.run_instrumented(Vec::new(), &tracer).await?;
+    assert_eq!(
+        tracer.path().hops(),
+        &[
+            (0, Point::new(1, 0), Point::new(2, 0)),
+            (1, Point::new(2, 0), Point::new(3, 0)),
+        ]
+    );
+
+    let tracer = PackageTracer::new(Package::integer(9));
+    flow.run_instrumented(Vec::new(), &tracer).await?;
+    assert!(tracer.path().is_empty());
+
+    Ok(())
+}
+
+#[derive(Inputs)]
+enum Configured {
+    Data,
+    #[optional]
+    Override,
+}
+
+/// Record the data received, or the override when received
+struct ConfiguredConsume;
+
+#[async_trait]
+impl ComponentSchema for ConfiguredConsume {
+    type Inputs = Configured;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let data = ctx.receive(Configured::Data).map(Package::get_number);
+        let value = match ctx.receive(Configured::Override) {
+            Some(package) => package.get_number()?,
+            None => data.transpose()?.unwrap_or_default(),
+        };
+        ctx.with_mut_global(|global| global.push(value as Id))?;
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn optional_input_not_block_ready() -> Result<()> {
+    let data = Configured::Data.into_port();
+    let config = Configured::Override.into_port();
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, EmitId),
+            Component::new(2, ConfiguredConsume),
+        ],
+        [Connection::new(1, 0, 2, data)],
+    )?;
+    assert_eq!(flow.run(Vec::new()).await?, vec![1]);
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, EmitId),
+            Component::new(2, ConfiguredConsume),
+            Component::new(3, EmitId),
+        ],
+        [
+            Connection::new(1, 0, 2, data),
+            Connection::new(3, 0, 2, config),
+        ],
+    )?;
+    assert_eq!(flow.run(Vec::new()).await?, vec![3]);
+
+    Ok(())
+}
+
+/// Never finish your run
+#[cfg(feature = "tokio")]
+struct Hang;
+
+#[cfg(feature = "tokio")]
+#[async_trait]
+impl ComponentSchema for Hang {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, _ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        futures::future::pending::<()>().await;
+        Ok(Next::Continue)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn component_timeout() -> Result<()> {
+    let timeout = std::time::Duration::from_millis(20);
+    let error =
+        Flow::<Vec<Id>>::from_parts([Component::new(1, Hang), Component::new(2, Emit)], [])?
+            .component_timeout(timeout)
+            .run(Vec::new())
+            .await
+            .unwrap_err();
+
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ComponentTimeout { component: 1, elapsed }) if *elapsed == timeout
+    ));
+
+    let flow =
+        Flow::<Vec<Id>>::from_parts([Component::new(1, Emit)], [])?.component_timeout(timeout);
+    flow.run(Vec::new()).await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn run_cancellable() -> Result<()> {
+    use tokio_util::sync::CancellationToken;
+
+    let flow = Flow::<Vec<Id>>::from_parts([Component::new(1, Hang)], [])?;
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        cancel.cancel();
+    });
+    let error = flow.run_cancellable(Vec::new(), token).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::Cancelled)
+    ));
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Emit), Component::new(2, Consume)],
+        [Connection::new(1, 0, 2, 0)],
+    )?;
+    assert_eq!(
+        flow.run_cancellable(Vec::new(), CancellationToken::new())
+            .await?,
+        vec![2]
+    );
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let error = flow.run_cancellable(Vec::new(), token).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::Cancelled)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lifecycle_events() -> Result<()> {
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let hook_events = events.clone();
+
+    Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Emit), Component::new(2, Consume)],
+        [Connection::new(1, 0, 2, 0)],
+    )?
+    .on_event(move |event| hook_events.lock().unwrap().push(event))
+    .run(Vec::new())
+    .await?;
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            FlowEvent::CycleStarted {
+                cycle: 0,
+                ready: vec![1]
+            },
+            FlowEvent::ComponentFinished {
+                id: 1,
+                next: Next::Continue
+            },
+            FlowEvent::QueuesRefreshed,
+            FlowEvent::CycleStarted {
+                cycle: 1,
+                ready: vec![2]
+            },
+            FlowEvent::ComponentFinished {
+                id: 2,
+                next: Next::Continue
+            },
+            FlowEvent::QueuesRefreshed,
+            FlowEvent::FlowFinished,
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn clone_flow_with_offset() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Tee),
+            Component::new(3, Consume),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?
+    .group("sink", &[3])?;
+
+    let copy = flow.clone_offset(100)?;
+    assert_eq!(copy.groups()["sink"], vec![103]);
+
+    let (components, connections, _) = copy.into_parts();
+    assert_eq!(
+        components.iter().map(Component::id).collect::<Vec<_>>(),
+        vec![101, 102, 103]
+    );
+    assert_eq!(
+        connections,
+        vec![
+            Connection::new(101, 0, 102, 0),
+            Connection::new(102, 0, 103, 0)
+        ]
+    );
+
+    let copy = flow.clone_offset(100)?;
+    assert_eq!(copy.run(Vec::new()).await?, vec![102, 103]);
+    assert_eq!(flow.run(Vec::new()).await?, vec![2, 3]);
+
+    assert!(matches!(
+        flow.clone_offset(Id::MAX).err(),
+        Some(Error::IdOverflow { .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_instrumented_stats() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Emit),
+            Component::new(3, Tee),
+            Component::new(4, Consume),
+        ],
+        [
+            Connection::new(1, 0, 3, 0),
+            Connection::new(2, 0, 3, 0),
+            Connection::new(3, 0, 4, 0),
+        ],
+    )?;
+
+    let stats = RunStats::default();
+    let visited = flow.run_instrumented(Vec::new(), &stats).await?;
+    assert_eq!(visited, vec![3, 3, 4, 4]);
+
+    let emit = stats.get(1).unwrap();
+    assert_eq!(emit.invocations, 1);
+    assert_eq!(emit.packages_sent, 1);
+    assert_eq!(emit.packages_received, 0);
+
+    let tee = stats.get(3).unwrap();
+    assert_eq!(tee.invocations, 1);
+    assert_eq!(tee.packages_sent, 2);
+    assert_eq!(tee.packages_received, 2);
+
+    let consume = stats.get(4).unwrap();
+    assert_eq!(consume.packages_sent, 0);
+    assert_eq!(consume.packages_received, 2);
+
+    assert_eq!(
+        stats.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn dot_with_types_and_port_labels() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Request),
+            Component::eager(2, Tee),
+            Component::new(3, Consume),
+        ],
+        [
+            Connection::new(1, Response::Error.into_port(), 2, 0),
+            Connection::new(2, 0, 3, 0),
+        ],
+    )?;
+
+    let dot = flow.to_dot();
+    assert!(dot.contains("  1 [label=\"1 (Lazy)\", style=bold];\n"));
+    assert!(dot.contains("  2 [label=\"2 (Eager)\"];\n"));
+    assert!(dot.contains("  3 [label=\"3 (Lazy)\"];\n"));
+    assert!(dot.contains("  1 -> 2 [label=\"Error -> In\"];\n"));
+    assert!(dot.contains("  2 -> 3 [label=\"Out -> In\"];\n"));
+
+    Ok(())
+}
+
+#[test]
+fn mermaid_with_types_and_port_labels() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Request), Component::eager(2, Consume)],
+        [Connection::new(1, Response::Error.into_port(), 2, 0)],
+    )?;
+
+    assert_eq!(
+        flow.to_mermaid(),
+        concat!(
+            "flowchart LR\n",
+            "  c1[\"1 (Lazy)\"]\n",
+            "  c2[\"2 (Eager)\"]\n",
+            "  c1 -->|\"Error -> In\"| c2\n",
+            "  class c1 source\n",
+            "  class c2 sink\n",
+            "  classDef source stroke-width:3px\n",
+            "  classDef sink stroke-dasharray:5 5\n",
+        )
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn once_component_run_a_single_time() -> Result<()> {
+    // 4 receive a package in cicle 2 (from 1) and in cicle 3 (from 3)
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Emit),
+            Component::new(3, Tee),
+            Component::once(4, Consume),
+        ],
+        [
+            Connection::new(1, 0, 4, 0),
+            Connection::new(2, 0, 3, 0),
+            Connection::new(3, 0, 4, 0),
+        ],
+    )?;
+
+    let stats = RunStats::default();
+    let mut visited = flow.run_instrumented(Vec::new(), &stats).await?;
+    visited.sort();
+    assert_eq!(visited, vec![3, 4]);
+    assert_eq!(stats.get(4).unwrap().invocations, 1);
+
+    let mut visited = flow.clone_offset(10)?.run(Vec::new()).await?;
+    visited.sort();
+    assert_eq!(visited, vec![13, 14]);
+
+    Ok(())
+}
+
+#[derive(Inputs)]
+enum Sides {
+    Left,
+    Right,
+}
+
+struct Join;
+
+#[async_trait]
+impl ComponentSchema for Join {
+    type Inputs = Sides;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.receive(Sides::Left);
+        ctx.receive(Sides::Right);
+        Ok(Next::Continue)
+    }
+}
+
+#[test]
+fn validate_report_all_problems() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Tee),
+            Component::new(3, Consume),
+            Component::new(4, Request),
+            Component::new(5, Join),
+            Component::new(6, Panic),
+        ],
+        [
+            Connection::new(1, 0, 2, 0),
+            Connection::new(4, Response::Body.into_port(), 5, Sides::Left.into_port()),
+        ],
+    )?;
+
+    let errors = flow.validate().unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(
+        errors[0],
+        Error::OutPortUnused {
+            component: 2,
+            out_port: 0
+        }
+    ));
+    assert!(matches!(errors[1], Error::ComponentDisconnected { id: 3 }));
+    assert!(matches!(
+        errors[2],
+        Error::InPortNotConnected {
+            component: 5,
+            in_port: 1
+        }
+    ));
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Emit), Component::new(2, Consume)],
+        [Connection::new(1, 0, 2, 0)],
+    )?;
+    assert!(flow.validate().is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_with_inputs_as_entry_point() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Tee),
+            Component::new(2, Consume),
+            Component::new(3, Tee),
+        ],
+        [Connection::new(1, 0, 2, 0)],
+    )?;
+
+    let inputs = HashMap::from([(Point::new(1, 0), vec![Package::empty(), Package::empty()])]);
+    let visited = flow.run_with_inputs(Vec::new(), inputs).await?;
+    assert_eq!(visited, vec![1, 1, 2, 2]);
+
+    let inputs = HashMap::from([(Point::new(1, 1), vec![Package::empty()])]);
+    let error = flow.run_with_inputs(Vec::new(), inputs).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::InPortNotFound {
+            component: 1,
+            in_port: 1
+        })
+    ));
+
+    let inputs = HashMap::from([(Point::new(4, 0), vec![Package::empty()])]);
+    let error = flow.run_with_inputs(Vec::new(), inputs).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ComponentNotFound { id: 4 })
+    ));
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Calls {
+    cycles_started: u32,
+    cycles_finished: u32,
+    runs: Vec<Id>,
+    moved: Vec<(Point, Point, usize)>,
+}
+
+struct Recorder(Arc<Mutex<Calls>>);
+
+impl<G> Instrumentation<G> for Recorder {
+    fn cycle_started(&self, _cycle: u32, _ready: &[Id]) {
+        self.0.lock().unwrap().cycles_started += 1;
+    }
+
+    fn component_run(&self, run: &ComponentRun) {
+        self.0.lock().unwrap().runs.push(run.id);
+    }
+
+    fn packages_moved(&self, from: Point, to: Point, count: usize) {
+        self.0.lock().unwrap().moved.push((from, to, count));
+    }
+
+    fn cycle_finished(&self, _cycle: u32) {
+        self.0.lock().unwrap().cycles_finished += 1;
+    }
+}
+
+#[tokio::test]
+async fn custom_instrumentation() -> Result<()> {
+    let calls = Arc::new(Mutex::new(Calls::default()));
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Tee),
+            Component::new(3, Consume),
+            Component::new(4, Consume),
+        ],
+        [
+            Connection::new(1, 0, 2, 0),
+            Connection::new(2, 0, 3, 0),
+            Connection::new(2, 0, 4, 0),
+        ],
+    )?
+    .instrumentation(Recorder(calls.clone()));
+
+    flow.run(Vec::new()).await?;
+
+    let mut calls = calls.lock().unwrap();
+    assert_eq!(calls.cycles_started, 3);
+    assert_eq!(calls.cycles_finished, 3);
+
+    calls.runs.sort();
+    assert_eq!(calls.runs, vec![1, 2, 3, 4]);
+
+    calls
+        .moved
+        .sort_by_key(|(from, to, _)| (from.id(), to.id()));
+    assert_eq!(
+        calls.moved,
+        vec![
+            (Point::new(1, 0), Point::new(2, 0), 1),
+            (Point::new(2, 0), Point::new(3, 0), 1),
+            (Point::new(2, 0), Point::new(4, 0), 1),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_instrumented_with_flow_instrumentation() -> Result<()> {
+    let calls = Arc::new(Mutex::new(Calls::default()));
+
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [Component::new(1, Emit), Component::new(2, Consume)],
+        [Connection::new(1, 0, 2, 0)],
+    )?
+    .instrumentation(Recorder(calls.clone()));
+
+    let stats = RunStats::default();
+    let other = Arc::new(Mutex::new(Calls::default()));
+    flow.run_instrumented(Vec::new(), &stats).await?;
+    flow.run_instrumented(Vec::new(), &Recorder(other.clone()))
+        .await?;
+
+    // the instrumentation of the flow observe both runs
+    assert_eq!(calls.lock().unwrap().runs, vec![1, 2, 1, 2]);
+    assert_eq!(other.lock().unwrap().runs, vec![1, 2]);
+    assert_eq!(other.lock().unwrap().cycles_started, 2);
+    assert_eq!(stats.get(1).unwrap().packages_sent, 1);
+    assert_eq!(stats.get(2).unwrap().packages_received, 1);
+
+    Ok(())
+}
+
+/// Record and forward the packages
+struct Audit;
+
+#[async_trait]
+impl ComponentSchema for Audit {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            ctx.with_mut_global(|global| global.push(ctx.id()))?;
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[test]
+fn validate_type_constraints() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Audit),
+            Component::new(3, Tee),
+            Component::new(4, Consume),
+            Component::new(5, Tee),
+            Component::new(6, Consume),
+        ],
+        [
+            Connection::new(1, 0, 2, 0),
+            Connection::new(2, 0, 3, 0),
+            Connection::new(3, 0, 4, 0),
+            Connection::new(1, 0, 5, 0),
+            Connection::new(5, 0, 6, 0),
+        ],
+    )?;
+
+    assert!(flow.validate().is_ok());
+
+    for after in ["Audit", "flow::Audit"] {
+        let errors = flow
+            .clone_offset(0)?
+            .add_type_constraint("Tee", after)
+            .validate()
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            Error::TypeConstraintViolated {
+                upstream,
+                downstream,
+                ..
+            } => assert_eq!((*upstream, *downstream), (2, 3)),
+            error => panic!("Expected a TypeConstraintViolated error, found {error:?}"),
+        }
+    }
+
+    // no audit is downstream of a tee
+    let flow = flow.add_type_constraint("Audit", "Tee");
+    assert!(flow.validate().is_ok());
+
+    Ok(())
+}
+
+/// Return [Next::Retry] in the first runs, recording each run
+struct Poll {
+    retries: u32,
+    runs: Mutex<u32>,
+}
+
+impl Poll {
+    fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            runs: Mutex::new(0),
+        }
+    }
+
+    fn ready(&self) -> bool {
+        let mut runs = self.runs.lock().unwrap();
+        *runs += 1;
+        *runs > self.retries
+    }
+}
+
+#[async_trait]
+impl ComponentSchema for Poll {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut_global(|global| global.push(ctx.id()))?;
+        if !self.ready() {
+            return Ok(Next::Retry);
+        }
+        ctx.send(Out, Package::empty());
+        Ok(Next::Continue)
+    }
+}
+
+/// Not receive the packages in the first runs
+struct Patient(Poll);
+
+#[async_trait]
+impl ComponentSchema for Patient {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut_global(|global| global.push(ctx.id()))?;
+        if !self.0.ready() {
+            return Ok(Next::Retry);
+        }
+        while ctx.receive(In).is_some() {}
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn retry_without_progress() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Poll::new(2)),
+            Component::new(2, Patient(Poll::new(1))),
+        ],
+        [Connection::new(1, 0, 2, 0)],
+    )?;
+
+    // 1 retry in cicles 1 and 2, and send in cicle 3
+    // 2 retry in cicle 4 without consume, and receive in cicle 5
+    let visited = flow.run(Vec::new()).await?;
+    assert_eq!(visited, vec![1, 1, 1, 2, 2]);
+
+    Ok(())
+}
+
+struct Number(f64);
+
+#[async_trait]
+impl ComponentSchema for Number {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.with_mut_global(|global| global.push((ctx.id(), self.0)))?;
+        ctx.send(Out, self.0.into());
+        Ok(Next::Continue)
+    }
+}
+
+struct Scale(f64);
+
+#[async_trait]
+impl ComponentSchema for Scale {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()? * self.0;
+            ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Log;
+
+#[async_trait]
+impl ComponentSchema for Log {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn run_incremental_only_changed_subtree() -> Result<()> {
+    let flow = |factor| {
+        Flow::from_parts(
+            [
+                Component::new(1, Number(2.0)),
+                Component::new(2, Scale(factor)),
+                Component::new(3, Log),
+                Component::new(4, Number(5.0)),
+            ],
+            [
+                Connection::new(1, 0, 2, 0),
+                Connection::new(2, 0, 3, 0),
+                Connection::new(4, 0, 3, 0),
+            ],
+        )
+    };
+    let sorted = |mut runs: Vec<(Id, f64)>| {
+        runs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        runs
+    };
+
+    // without previous outputs all components run
+    let (runs, snapshot) = flow(10.0)?
+        .run_incremental(&FlowSnapshot::default(), &[], Vec::new())
+        .await?;
+    assert_eq!(
+        sorted(runs),
+        vec![(1, 2.0), (2, 20.0), (3, 5.0), (3, 20.0), (4, 5.0)]
+    );
+
+    // the outputs of 1 and 4 are reused
+    let (runs, snapshot) = flow(100.0)?
+        .run_incremental(&snapshot, &[2], Vec::new())
+        .await?;
+    assert_eq!(sorted(runs), vec![(2, 200.0), (3, 5.0), (3, 200.0)]);
+    assert_eq!(snapshot.outputs(Point::new(1, 0)).len(), 1);
+    let scaled = snapshot.outputs(Point::new(2, 0))[0].clone();
+    assert_eq!(scaled.get_number()?, 200.0);
+
+    let error = flow(100.0)?
+        .run_incremental(&snapshot, &[5], Vec::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ComponentNotFound { id: 5 })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn run_blocking_without_runtime() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Tee),
+            Component::new(3, Consume),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?;
+
+    let visited = flow.run_blocking(Vec::new())?;
+    assert_eq!(visited, vec![2, 3]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn deterministic_order_in_cycle() -> Result<()> {
+    let run = || async {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let hook_events = events.clone();
+
+        let tees = [6, 4, 2, 5, 1, 3];
+        let components = tees
+            .iter()
+            .flat_map(|id| [Component::new(id + 10, Emit), Component::new(*id, Tee)]);
+        let connections = tees.iter().map(|id| Connection::new(id + 10, 0, *id, 0));
+        let log = Flow::<Vec<Id>>::from_parts(components, connections)?
+            .on_event(move |event| {
+                if let FlowEvent::ComponentFinished { id, .. } = event {
+                    hook_events.lock().unwrap().push(id);
+                }
+            })
+            .run(Vec::new())
+            .await?;
+
+        let finished = events.lock().unwrap().clone();
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>((log, finished))
+    };
+
+    let (log, finished) = run().await?;
+    assert_eq!(log, vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(finished, vec![11, 12, 13, 14, 15, 16, 1, 2, 3, 4, 5, 6]);
+
+    assert_eq!(run().await?, (log, finished));
+
+    Ok(())
+}
+
+/// Fail when receive a odd number
+struct Odd;
+
+#[async_trait]
+impl ComponentSchema for Odd {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            if number % 2.0 == 1.0 {
+                return Err(format!("odd number {number}").into());
+            }
+            ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Fail in the first run, after receive the packages
+struct Flaky(Mutex<bool>);
+
+#[async_trait]
+impl ComponentSchema for Flaky {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<(Id, f64)>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let package = ctx.receive(In).unwrap();
+        if !std::mem::replace(&mut *self.0.lock().unwrap(), true) {
+            return Err("first run".into());
+        }
+        let number = package.get_number()?;
+        ctx.with_mut_global(|global| global.push((ctx.id(), number)))?;
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn debugger_skip_abort_and_retry() -> Result<()> {
+    use rs_flow::{DebugAction, DebugContext, Debugger};
+
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Number(1.0)),
+            Component::new(2, Number(3.0)),
+            Component::new(3, Number(2.0)),
+            Component::new(4, Odd),
+            Component::new(5, Odd),
+            Component::new(6, Odd),
+        ],
+        [
+            Connection::new(1, 0, 4, 0),
+            Connection::new(2, 0, 5, 0),
+            Connection::new(3, 0, 6, 0),
+        ],
+    )?;
+
+    let mut calls = Vec::new();
+    let debugger = Debugger::new(|error, debug| {
+        calls.push((error.to_string(), debug.clone()));
+        match calls.len() {
+            1 => DebugAction::Skip,
+            _ => DebugAction::Abort,
+        }
+    });
+    let error = flow
+        .run_instrumented(Vec::new(), &debugger)
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "odd number 3");
+
+    let debug = |component| DebugContext {
+        component,
+        cicle: 1,
+        retries: 0,
+        inputs: vec![(0, 0)],
+        pending: vec![],
+    };
+    assert_eq!(
+        calls,
+        vec![
+            ("odd number 1".to_owned(), debug(4)),
+            ("odd number 3".to_owned(), debug(5))
+        ]
+    );
+
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, Number(2.0)),
+            Component::new(2, Flaky(Mutex::new(false))),
+        ],
+        [Connection::new(1, 0, 2, 0)],
+    )?;
+    let mut retries = Vec::new();
+    let debugger = Debugger::new(|_, debug| {
+        retries.push(debug.retries);
+        DebugAction::Retry
+    });
+    let global = flow.run_instrumented(Vec::new(), &debugger).await?;
+    // the package received before the error is received again
+    assert_eq!(global, vec![(1, 2.0), (2, 2.0)]);
+    assert_eq!(retries, vec![0]);
+
+    Ok(())
+}
+
+const COORDINATE: PackageSchema =
+    PackageSchema::Object(&[("x", PackageSchema::Number), ("y", PackageSchema::Number)]);
+
+#[derive(Inputs)]
+enum Plot {
+    #[schema(COORDINATE)]
+    Coordinate,
+    #[schema(PackageSchema::Array(&PackageSchema::String))]
+    Labels,
+}
+
+#[derive(Outputs)]
+#[schema(PackageSchema::String)]
+struct Text;
+
+struct Chart;
+
+#[async_trait]
+impl ComponentSchema for Chart {
+    type Inputs = Plot;
+    type Outputs = Text;
+
+    type Global = ();
+
+    async fn run(&self, _: &mut Ctx<Self::Global>) -> Result<Next> {
+        Ok(Next::Continue)
+    }
+}
+
+#[test]
+fn port_schemas() -> Result<()> {
+    let flow = Flow::new().add_component(Component::new(1, Chart))?;
+
+    let coordinate = flow.in_port_schema(1, Plot::Coordinate.into_port());
+    assert_eq!(coordinate, Some(&COORDINATE));
+    let labels = flow.in_port_schema(1, Plot::Labels.into_port()).unwrap();
+    assert_eq!(labels, &PackageSchema::Array(&PackageSchema::String));
+    assert_eq!(flow.in_port_schema(1, 2), None);
+    assert_eq!(flow.in_port_schema(2, 0), None);
+
+    // a output of strings can not be connected to the input of coordinates
+    let text = flow.out_port_schema(1, Text.into_port()).unwrap();
+    assert_eq!(text, &PackageSchema::String);
+    assert!(!COORDINATE.accepts(text));
+    assert!(labels.accepts(&PackageSchema::Array(&PackageSchema::String)));
+
+    Ok(())
+}
+
+struct Fetch;
+
+#[async_trait]
+impl ComponentSchema for Fetch {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = ();
+
+    async fn run(&self, _: &mut Ctx<Self::Global>) -> Result<Next> {
+        Ok(Next::Continue)
+    }
+
+    fn type_name() -> &'static str {
+        "HttpRequest"
+    }
+
+    fn description() -> &'static str {
+        "Fetch a url"
+    }
+}
+
+#[test]
+fn component_type_name_and_description() {
+    let fetch = Component::new(1, Fetch);
+    assert_eq!(fetch.type_name(), "HttpRequest");
+    assert_eq!(fetch.description(), "Fetch a url");
+
+    // by default the path of type without description
+    let chart = Component::new(2, Chart);
+    assert_eq!(chart.type_name(), std::any::type_name::<Chart>());
+    assert!(chart.type_name().ends_with("::Chart"));
+    assert_eq!(chart.description(), "");
+}
+
+/// Sleep before receive the packages
+#[cfg(feature = "tokio")]
+struct Sleep(Duration);
+
+#[cfg(feature = "tokio")]
+#[async_trait]
+impl ComponentSchema for Sleep {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        tokio::time::sleep(self.0).await;
+        while ctx.receive(In).is_some() {}
+        ctx.with_mut_global(|global| global.push(ctx.id()))?;
+        Ok(Next::Continue)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn cycle_deadline() -> Result<()> {
+    let flow = Flow::<Vec<Id>>::from_parts(
+        [
+            Component::new(1, Emit),
+            Component::new(2, Sleep(Duration::from_millis(5))),
+            Component::new(3, Sleep(Duration::from_millis(200))),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(1, 0, 3, 0)],
+    )?;
+
+    let deadline = Duration::from_millis(50);
+    let error = flow
+        .run_with_cycle_deadline(Vec::new(), deadline)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::CycleDeadlineExceeded { cicle: 1, deadline: d }) if *d == deadline
+    ));
+
+    let visited = flow
+        .run_with_cycle_deadline(Vec::new(), Duration::from_secs(5))
+        .await?;
+    assert_eq!(visited, vec![2, 3]);
+
+    Ok(())
+}
+
+/// Merge the ids received by a number of input ports defined in construction
+struct MergeN(PortId);
+
+#[async_trait]
+impl ComponentSchema for MergeN {
+    type Inputs = PortId;
+    type Outputs = ();
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for port in 0..self.0 {
+            while let Some(package) = ctx.receive(port) {
+                let id = package.get_number()? as Id;
+                ctx.with_mut_global(|global| global.push(id))?;
+            }
+        }
+        Ok(Next::Continue)
+    }
+
+    fn inputs(&self) -> Ports {
+        Ports::owned((0..self.0).map(Port::new).collect())
+    }
+}
+
+#[tokio::test]
+async fn ports_defined_in_construction() -> Result<()> {
+    let merge = Component::new(4, MergeN(3));
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, EmitId),
+            Component::new(2, EmitId),
+            Component::new(3, EmitId),
+            merge,
+        ],
+        [
+            Connection::new(1, 0, 4, 0),
+            Connection::new(2, 0, 4, 1),
+            Connection::new(3, 0, 4, 2),
+        ],
+    )?;
+    assert_eq!(flow.run(Vec::new()).await?, vec![1, 2, 3]);
+
+    let error = Flow::from_parts(
+        [Component::new(1, EmitId), Component::new(4, MergeN(3))],
+        [Connection::new(1, 0, 4, 3)],
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(
+        error,
+        Error::InPortNotFound {
+            component: 4,
+            in_port: 3
+        }
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn ports_collisions() {
+    let ports = Ports::try_new((0..3).map(Port::new).chain([Port::new(1)]).collect());
+    assert_eq!(ports.unwrap_err(), PortsError::DuplicateId { port: 1 });
+
+    let ports = Ports::try_new(vec![
+        Port::from(0, "Left", None),
+        Port::from(1, "Right", None),
+        Port::from(2, "Left", Some("Other left")),
+    ]);
+    let error = ports.unwrap_err();
+    assert_eq!(error, PortsError::DuplicateLabel { label: "Left" });
+    assert_eq!(error.to_string(), "Found ports with same label = \"Left\"");
+
+    // ports without label not collide
+    assert_eq!(
+        Ports::try_new((0..3).map(Port::new).collect())
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[derive(Inputs, Clone, Copy)]
+enum Doubling {
+    #[optional]
+    Start,
+    #[optional]
+    Again,
+}
+
+/// Double the numbers received by both inputs
+struct Double;
+
+#[async_trait]
+impl ComponentSchema for Double {
+    type Inputs = Doubling;
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for port in [Doubling::Start, Doubling::Again] {
+            while let Some(package) = ctx.receive(port) {
+                ctx.send(Out, (package.get_number()? * 2.0).into());
+            }
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Record the numbers received and send back the numbers less than 50
+struct Check;
+
+#[async_trait]
+impl ComponentSchema for Check {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<Id>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let value = package.get_number()?;
+            ctx.with_mut_global(|global| global.push(value as Id))?;
+            if value < 50.0 {
+                ctx.send(Out, value.into());
+            }
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn feedback_connection_closes_loop() -> Result<()> {
+    let back = Connection::new(3, 0, 2, 1);
+    let flow = Flow::from_parts(
+        [
+            Component::new(1, EmitId),
+            Component::new(2, Double),
+            Component::new(3, Check),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    )?;
+    assert!(matches!(
+        flow.can_add_connection(&back),
+        Err(Error::LoopCreated { .. })
+    ));
+
+    let flow = flow.add_feedback_connection(back.clone())?;
+    assert_eq!(flow.feedback_connections(), vec![back]);
+    assert!(flow
+        .to_dot()
+        .contains("3 -> 2 [label=\"Out -> Again\", style=dashed];"));
+    assert_eq!(flow.sinks(), vec![3]);
+
+    assert_eq!(flow.run(Vec::new()).await?, vec![2, 4, 8, 16, 32, 64]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn feedback_connection_kept_in_parts() -> Result<()> {
+    // the only connection to the input of Check is the feedback connection
+    let back = Connection::new(2, 0, 3, 0);
+    let flow = Flow::from_parts_with_feedback(
+        [
+            Component::new(1, EmitId),
+            Component::new(2, Double),
+            Component::new(3, Check),
+        ],
+        [Connection::new(1, 0, 2, 0), Connection::new(3, 0, 2, 1)],
+        [back.clone()],
+    )?;
+    assert!(flow.validate().is_ok());
+
+    let copy = flow.clone_offset(10)?;
+    assert_eq!(
+        copy.feedback_connections(),
+        vec![Connection::new(12, 0, 13, 0)]
+    );
+    assert!(copy.validate().is_ok());
+
+    let (components, connections, feedback) = flow.into_parts();
+    assert_eq!(feedback, vec![back]);
+
+    let flow = Flow::from_parts_with_feedback(components, connections, feedback)?;
+    assert!(flow.validate().is_ok());
+    assert_eq!(flow.run(Vec::new()).await?, vec![2, 4, 8, 16, 32, 64]);
+
+    Ok(())
+}
+
+#[derive(Outputs)]
+#[kind(Integer)]
+struct Count;
+
+#[derive(Inputs)]
+enum Typed {
+    #[kind(Number)]
+    Value,
+    #[kind(String)]
+    Name,
+    Untyped,
+}
+
+/// Component with declared kinds in your ports
+struct Kinds;
+
+#[async_trait]
+impl ComponentSchema for Kinds {
+    type Inputs = Typed;
+    type Outputs = Count;
+
+    type Global = ();
+
+    async fn run(&self, _: &mut Ctx<Self::Global>) -> Result<Next> {
+        Ok(Next::Continue)
+    }
+}
+
+#[test]
+fn connect_ports_of_declared_kinds() -> Result<()> {
+    let flow = Flow::new()
+        .add_component(Component::new(1, Kinds))?
+        .add_component(Component::new(2, Kinds))?;
+
+    flow.can_add_connection(&Connection::new(1, 0, 2, Typed::Value.into_port()))?;
+    flow.can_add_connection(&Connection::new(1, 0, 2, Typed::Untyped.into_port()))?;
+
+    let name = Connection::new(1, 0, 2, Typed::Name.into_port());
+    let error = flow.add_connection(name.clone()).err().unwrap();
+    assert!(matches!(
+        error,
+        Error::PortKindMismatch {
+            connection,
+            out_kind: PackageKind::Integer,
+            in_kind: PackageKind::String,
+        } if connection == name
+    ));
+
+    Ok(())
+}
+
+#[derive(Outputs, Debug, Clone, Copy)]
+enum Pinned {
+    Gap,
+    #[port(id = 0)]
+    First,
+    #[port(id = 5)]
+    Fifth,
+    Other,
+}
+
+#[derive(Inputs)]
+#[port(id = 3)]
+struct Third;
+
+#[test]
+fn explicit_port_ids() {
+    let ids = [Pinned::Gap, Pinned::First, Pinned::Fifth, Pinned::Other].map(|p| p.into_port());
+    assert_eq!(ids, [1, 0, 5, 2]);
+    assert_eq!(Pinned::PORTS.port_id_of("Fifth"), Some(5));
+    assert_eq!(Pinned::PORTS.get(2).unwrap().label, Some("Other"));
+
+    assert_eq!(Third.into_port(), 3);
+    assert!(Third::PORTS.contains(3));
+}
@@ -0,0 +1,637 @@
+use std::collections::{HashMap, HashSet};
+
+use rs_flow::{HashablePackage, Package};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Shape<'a> {
+    Point,
+    Circle(f64),
+    Named { name: &'a str },
+}
+
+#[test]
+fn deserialize_borrowed() {
+    let bytes = Package::bytes(&[1, 2, 3]);
+    let borrowed: &[u8] = bytes.deserialize_borrowed().unwrap();
+    assert_eq!(borrowed, &[1, 2, 3]);
+
+    let shapes = Package::array([
+        Package::string("Point"),
+        Package::object([("Circle", 2.5)]),
+        Package::object([("Named", Package::object([("name", "square")]))]),
+    ]);
+    let shapes: Vec<Shape> = shapes.deserialize_borrowed().unwrap();
+    assert_eq!(
+        shapes,
+        vec![
+            Shape::Point,
+            Shape::Circle(2.5),
+            Shape::Named { name: "square" }
+        ]
+    );
+
+    let object = Package::object([("1", true), ("2", false)]);
+    let map: HashMap<u8, bool> = object.deserialize_borrowed().unwrap();
+    assert_eq!(map, HashMap::from([(1, true), (2, false)]));
+
+    assert!(Package::number(1.0).deserialize_borrowed::<&str>().is_err());
+}
+
+/// Serialize as `depth` arrays nested inside each other
+struct Nested(usize);
+
+impl serde::Serialize for Nested {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(1))?;
+        if self.0 > 1 {
+            seq.serialize_element(&Nested(self.0 - 1))?;
+        }
+        seq.end()
+    }
+}
+
+#[test]
+fn serialize_with_depth_limit() {
+    let error = Package::try_from_with_limit(Nested(1000), 100).unwrap_err();
+    assert!(error.to_string().contains("max depth exceeded"));
+
+    let package = Package::try_from_with_limit(Nested(100), 100).unwrap();
+    assert!(package.is_array());
+    assert!(Package::try_from_with_limit(Nested(101), 100).is_err());
+}
+
+#[test]
+fn from_pairs_nested() {
+    let package = Package::from_pairs(&[
+        ("/user/name", "bob".into()),
+        ("user/address/city", "Recife".into()),
+        ("active", true.into()),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        package,
+        Package::object([
+            (
+                "user",
+                Package::object([
+                    ("name", Package::string("bob")),
+                    ("address", Package::object([("city", "Recife")])),
+                ])
+            ),
+            ("active", Package::bool(true)),
+        ])
+    );
+}
+
+#[test]
+fn from_pairs_conflict() {
+    let scalar_then_nested =
+        Package::from_pairs(&[("user", "bob".into()), ("user/name", "bob".into())]);
+    assert!(matches!(
+        scalar_then_nested,
+        Err(rs_flow::PackageError::PathConflict { path }) if path == "user/name"
+    ));
+
+    let nested_then_scalar =
+        Package::from_pairs(&[("user/name", "bob".into()), ("user", "bob".into())]);
+    assert!(matches!(
+        nested_then_scalar,
+        Err(rs_flow::PackageError::PathConflict { path }) if path == "user"
+    ));
+
+    let duplicated = Package::from_pairs(&[("a/b", 1.into()), ("a/b", 2.into())]);
+    assert!(duplicated.is_err());
+}
+
+#[test]
+fn integer_without_precision_loss() {
+    let id: u64 = 9007199254740993;
+
+    let package = Package::try_from(id).unwrap();
+    assert_eq!(package, Package::integer(9007199254740993));
+    assert_eq!(package.clone().try_into::<u64>().unwrap(), id);
+    assert_eq!(
+        package.deserialize_borrowed::<u64>().unwrap(),
+        id,
+        "borrowed deserialization keep the integer"
+    );
+
+    let timestamp = Package::try_from(-1_700_000_000_123_456_789i64).unwrap();
+    assert!(timestamp.is_integer());
+    assert_eq!(
+        timestamp.try_into::<i64>().unwrap(),
+        -1_700_000_000_123_456_789
+    );
+
+    let big = Package::try_from(u64::MAX).unwrap();
+    assert_eq!(big, Package::number(u64::MAX as f64));
+    assert_eq!(Package::from(u64::MAX), big);
+}
+
+#[test]
+fn integer_from_conversion_match_serializer() {
+    let id: i64 = 9007199254740993;
+    assert_eq!(Package::from(id), Package::try_from(id).unwrap());
+    assert_eq!(
+        Package::from(id as u64),
+        Package::try_from(id as u64).unwrap()
+    );
+    assert_eq!(Package::from(3usize), Package::integer(3));
+    assert_eq!(Package::from(3i32), Package::integer(3));
+    assert_eq!(Package::from(3u8), Package::try_from(3u8).unwrap());
+    assert_eq!(Package::from(3.0), Package::number(3.0));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn integer_from_equal_json_integer() {
+    use serde_json::json;
+
+    assert_eq!(Package::from(1), Package::from(json!(1)));
+    assert_eq!(Package::from(-7i8), Package::from(json!(-7)));
+    assert_eq!(
+        HashablePackage::from(Package::from(1u16)),
+        HashablePackage::from(Package::from(json!(1)))
+    );
+}
+
+#[test]
+fn integer_from_number() {
+    assert_eq!(Package::number(24.0).try_into::<u64>().unwrap(), 24);
+    assert_eq!(Package::number(-3.0).try_into::<i32>().unwrap(), -3);
+    assert_eq!(Package::integer(3).try_into::<f64>().unwrap(), 3.0);
+
+    assert!(Package::number(2.5).try_into::<u64>().is_err());
+    assert!(Package::number(2.5).try_into::<i8>().is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_value_round_trip() {
+    use serde_json::json;
+
+    let value = json!({
+        "name": "bob",
+        "age": 24,
+        "score": 9.5,
+        "active": true,
+        "address": null,
+        "tags": ["a", "b"]
+    });
+
+    let package = Package::from(value.clone());
+    assert_eq!(package.get_path("age"), Some(&Package::integer(24)));
+    assert_eq!(package.get_path("score"), Some(&Package::number(9.5)));
+    assert_eq!(package.get_path("address"), Some(&Package::empty()));
+    assert_eq!(package.get_path("tags.1"), Some(&Package::string("b")));
+
+    assert_eq!(serde_json::Value::from(package), value);
+
+    let bytes = serde_json::Value::from(Package::bytes(&[1, 2, 3]));
+    assert_eq!(bytes, json!({ "$bytes": "AQID" }));
+    assert_eq!(
+        serde_json::to_value(Package::bytes(&[1, 2, 3])).unwrap(),
+        bytes,
+        "same format of serde"
+    );
+    assert_eq!(Package::from(bytes), Package::bytes(&[1, 2, 3]));
+
+    assert!(Package::from(json!([1, 2, 3])).is_array());
+    assert!(Package::from(json!({ "$bytes": "not base64!" })).is_object());
+    assert!(Package::from(json!({ "$bytes": "AQID", "other": 1 })).is_object());
+}
+
+#[test]
+fn strict_integer_conversions() {
+    assert_eq!(Package::number(255.0).try_into_strict::<u8>().unwrap(), 255);
+    assert_eq!(
+        Package::integer(-300).try_into_strict::<i16>().unwrap(),
+        -300
+    );
+
+    let out_of_range = Package::number(300.0).try_into_strict::<u8>().unwrap_err();
+    assert!(out_of_range
+        .to_string()
+        .contains("value out of range for u8"));
+    assert!(Package::integer(-1).try_into_strict::<u8>().is_err());
+    assert!(Package::integer(40_000).try_into_strict::<i16>().is_err());
+    assert!(Package::number(-40_000.0).try_into_strict::<i16>().is_err());
+
+    let fractional = Package::number(2.5).try_into_strict::<i16>().unwrap_err();
+    assert!(fractional.to_string().contains("is not integral"));
+
+    // nested values are also checked
+    let array = Package::array([Package::number(1.0), Package::number(256.0)]);
+    assert!(array.clone().try_into_strict::<Vec<u8>>().is_err());
+    assert_eq!(array.try_into::<Vec<u8>>().unwrap(), vec![1, 0]);
+}
+
+#[test]
+fn hashable_package_dedup() {
+    #[derive(serde::Serialize)]
+    struct Point {
+        x: f64,
+        y: f64,
+        tags: Vec<&'static str>,
+    }
+
+    let from_struct = Package::try_from(Point {
+        x: 0.0,
+        y: f64::NAN,
+        tags: vec!["a", "b"],
+    })
+    .unwrap();
+    let from_pairs = Package::object([
+        ("tags", Package::array(["a", "b"])),
+        ("y", Package::number(-f64::NAN)),
+        ("x", Package::number(-0.0)),
+    ]);
+
+    let mut set = HashSet::new();
+    assert!(set.insert(HashablePackage::from(from_struct)));
+    assert!(!set.insert(HashablePackage::from(from_pairs)));
+    assert!(set.insert(HashablePackage::from(Package::object([
+        ("tags", Package::array(["b", "a"])),
+        ("y", Package::number(f64::NAN)),
+        ("x", Package::number(0.0)),
+    ]))));
+    assert!(set.insert(HashablePackage::from(Package::Integer(1))));
+    assert!(set.insert(HashablePackage::from(Package::number(1.0))));
+    assert_eq!(set.len(), 4);
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn bytes_as_typed_slice() {
+    use rs_flow::PackageError;
+
+    let numbers = [1.5f32, -2.0, 8.25];
+    let bytes = numbers
+        .iter()
+        .flat_map(|number| number.to_ne_bytes())
+        .collect::<Vec<_>>();
+    let package = Package::bytes(&bytes);
+    assert_eq!(package.get_f32_slice().unwrap(), &numbers);
+    assert_eq!(package.get_u16_slice().unwrap().len(), 6);
+
+    let integers = [7i32, -1];
+    let bytes = integers
+        .iter()
+        .flat_map(|integer| integer.to_ne_bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(Package::bytes(&bytes).get_i32_slice().unwrap(), &integers);
+
+    let misaligned = Package::bytes(&[0; 7]);
+    assert!(matches!(
+        misaligned.get_f32_slice(),
+        Err(PackageError::MisalignedBytes { len: 7, size: 4 })
+    ));
+    assert!(matches!(
+        misaligned.get_f64_slice(),
+        Err(PackageError::MisalignedBytes { len: 7, size: 8 })
+    ));
+    assert!(matches!(
+        Package::number(1.0).get_f32_slice(),
+        Err(PackageError::NotBytes)
+    ));
+}
+
+#[test]
+fn validate_package_schema() {
+    use rs_flow::{PackageError, PackageSchema};
+
+    const ORDER: PackageSchema = PackageSchema::Object(&[
+        ("id", PackageSchema::Integer),
+        (
+            "items",
+            PackageSchema::Array(&PackageSchema::Object(&[("price", PackageSchema::Number)])),
+        ),
+    ]);
+
+    let order = |price: Package| {
+        Package::object([
+            ("id", Package::integer(7)),
+            (
+                "items",
+                Package::array([Package::object([("price", price)])]),
+            ),
+        ])
+    };
+    assert!(order(Package::number(2.5)).validate(&ORDER).is_ok());
+    assert!(matches!(
+        order(Package::string("free")).validate(&ORDER),
+        Err(PackageError::SchemaMismatch { path }) if path == "items.0.price"
+    ));
+    assert!(matches!(
+        Package::empty().validate(&ORDER),
+        Err(PackageError::SchemaMismatch { path }) if path.is_empty()
+    ));
+    assert!(Package::empty().validate(&PackageSchema::Any).is_ok());
+}
+
+#[test]
+fn nested_path_access() {
+    let mut package = Package::object([(
+        "user",
+        Package::object([
+            ("address", Package::object([("city", "Recife")])),
+            ("items", Package::array([Package::object([("price", 2.5)])])),
+        ]),
+    )]);
+
+    assert_eq!(
+        package.get_path("user.address.city"),
+        Some(&Package::string("Recife"))
+    );
+    assert_eq!(
+        package.get_path("user.items.0.price"),
+        Some(&Package::number(2.5))
+    );
+    assert_eq!(package.get_path("user.items.1.price"), None);
+    assert_eq!(package.get_path("user.items.first"), None);
+    assert_eq!(package.get_path("user.address.city.name"), None);
+    assert_eq!(package.get_path("user.missing"), None);
+
+    *package.get_path_mut("user.items.0.price").unwrap() = Package::number(3.0);
+    assert_eq!(
+        package.get_path("user.items.0.price"),
+        Some(&Package::number(3.0))
+    );
+    assert!(package.get_path_mut("user.address.zip").is_none());
+}
+
+#[test]
+fn deep_merge_objects() {
+    let base = Package::object([
+        (
+            "server",
+            Package::object([("host", Package::string("localhost")), ("port", 80.into())]),
+        ),
+        ("tags", Package::array(["a", "b"])),
+        ("debug", Package::bool(false)),
+    ]);
+    let patch = Package::object([
+        ("server", Package::object([("port", 8080)])),
+        ("tags", Package::array(["c"])),
+        ("name", Package::string("api")),
+    ]);
+
+    let merged = base.clone().merged(patch);
+    assert_eq!(
+        merged,
+        Package::object([
+            (
+                "server",
+                Package::object([
+                    ("host", Package::string("localhost")),
+                    ("port", 8080.into())
+                ]),
+            ),
+            ("tags", Package::array(["c"])),
+            ("debug", Package::bool(false)),
+            ("name", Package::string("api")),
+        ])
+    );
+
+    // a patch that is not a object replace the object
+    let mut replaced = base;
+    replaced.merge(Package::number(1.0));
+    assert_eq!(replaced, Package::number(1.0));
+
+    let mut scalar = Package::string("old");
+    scalar.merge(Package::object([("new", true)]));
+    assert_eq!(scalar, Package::object([("new", true)]));
+}
+
+#[test]
+fn package_equality() {
+    use std::collections::HashMap;
+
+    let mut first = HashMap::new();
+    first.insert("a".to_owned(), Package::number(1.0));
+    first.insert(
+        "nested".to_owned(),
+        Package::object([("x", Package::array([1, 2])), ("y", "text".into())]),
+    );
+
+    let mut second = HashMap::new();
+    second.insert(
+        "nested".to_owned(),
+        Package::object([("y", "text".into()), ("x", Package::array([1, 2]))]),
+    );
+    second.insert("a".to_owned(), Package::number(1.0));
+
+    assert_eq!(Package::Object(first.clone()), Package::Object(second));
+
+    first.insert("b".to_owned(), Package::empty());
+    assert_ne!(Package::Object(first), Package::object([("a", 1.0)]));
+
+    assert_ne!(Package::array([1, 2]), Package::array([2, 1]));
+    assert_ne!(Package::number(f64::NAN), Package::number(f64::NAN));
+    assert_ne!(Package::integer(1), Package::number(1.0));
+}
+
+#[test]
+fn display_and_pretty() {
+    let package = Package::object([
+        ("id", Package::integer(7)),
+        ("score", Package::number(9.5)),
+        (
+            "user",
+            Package::object([("name", Package::string("bob")), ("admin", false.into())]),
+        ),
+        ("tags", Package::array(["a", "b"])),
+        ("empty", Package::array(Vec::<Package>::new())),
+    ]);
+
+    assert_eq!(
+        package.to_string(),
+        r#"{"empty": [], "id": 7, "score": 9.5, "tags": ["a", "b"], "user": {"admin": false, "name": "bob"}}"#
+    );
+    assert_eq!(
+        package.to_pretty(),
+        concat!(
+            "{\n",
+            "  \"empty\": [],\n",
+            "  \"id\": 7,\n",
+            "  \"score\": 9.5,\n",
+            "  \"tags\": [\n",
+            "    \"a\",\n",
+            "    \"b\"\n",
+            "  ],\n",
+            "  \"user\": {\n",
+            "    \"admin\": false,\n",
+            "    \"name\": \"bob\"\n",
+            "  }\n",
+            "}"
+        )
+    );
+    assert_eq!(Package::empty().to_string(), "null");
+    assert_eq!(Package::bytes(&[1, 2, 3]).to_string(), "<3 bytes>");
+
+    // deeply nested packages are cut
+    let deep = (0..1000).fold(Package::integer(1), |package, _| Package::array([package]));
+    let display = deep.to_string();
+    assert!(display.starts_with("[[[[") && display.contains("..."));
+    assert!(!display.contains('1'));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn bytes_json_round_trip() {
+    let package = Package::object([
+        ("raw", Package::bytes(&[1, 2, 3])),
+        ("numbers", Package::array([1, 2, 3])),
+    ]);
+
+    let json = serde_json::to_value(&package).unwrap();
+    assert_eq!(json["raw"], serde_json::json!({ "$bytes": "AQID" }));
+
+    let text = serde_json::to_string(&package).unwrap();
+    let back: Package = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, package);
+    assert!(back.get_path("raw").is_some_and(Package::is_bytes));
+    assert!(back.get_path("numbers").is_some_and(Package::is_array));
+
+    // a object with other fields, or a value not encoded, is a object
+    let object: Package = serde_json::from_str(r#"{"$bytes": "AQID", "len": 3}"#).unwrap();
+    assert!(object.is_object());
+    let object: Package = serde_json::from_str(r#"{"$bytes": "not base64!"}"#).unwrap();
+    assert!(object.is_object());
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn msgpack_round_trip() {
+    let package = Package::object([
+        ("id", Package::integer(9007199254740993)),
+        ("score", Package::number(9.5)),
+        ("raw", Package::bytes(&[1, 2, 3])),
+        (
+            "items",
+            Package::array([Package::empty(), true.into(), "a".into()]),
+        ),
+    ]);
+
+    let encoded = package.to_msgpack().unwrap();
+    let decoded = Package::from_msgpack(&encoded).unwrap();
+    assert_eq!(decoded, package);
+    assert!(decoded.get_path("raw").is_some_and(Package::is_bytes));
+
+    assert!(matches!(
+        Package::from_msgpack(&[0xc1]),
+        Err(rs_flow::PackageError::DecodeMsgpack(_))
+    ));
+}
+
+#[test]
+fn integer_getters_with_range_checks() {
+    use rs_flow::PackageError;
+
+    assert_eq!(Package::integer(-7).get_i64().unwrap(), -7);
+    assert_eq!(Package::number(42.0).get_i64().unwrap(), 42);
+    assert_eq!(Package::number(42.0).get_u64().unwrap(), 42);
+    assert_eq!(Package::integer(3).get_usize().unwrap(), 3);
+    assert_eq!(
+        Package::integer(i64::MAX).get_u64().unwrap(),
+        i64::MAX as u64
+    );
+
+    for package in [
+        Package::number(2.5),
+        Package::number(f64::NAN),
+        Package::number(f64::INFINITY),
+        Package::number(1e20),
+    ] {
+        assert!(matches!(
+            package.get_i64(),
+            Err(PackageError::NumberOutOfRange)
+        ));
+    }
+    assert!(matches!(
+        Package::integer(-1).get_u64(),
+        Err(PackageError::NumberOutOfRange)
+    ));
+    assert!(matches!(
+        Package::number(-1.0).get_usize(),
+        Err(PackageError::NumberOutOfRange)
+    ));
+    assert!(matches!(
+        Package::string("1").get_usize(),
+        Err(PackageError::NotNumber)
+    ));
+}
+
+/// Record the keys in the order that the deserializer visit them
+#[derive(Debug, PartialEq)]
+struct Keys(Vec<String>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for KeysVisitor {
+            type Value = Keys;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("struct Row")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Keys, A::Error> {
+                let mut keys = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                    keys.push(key);
+                }
+                Ok(Keys(keys))
+            }
+        }
+
+        deserializer.deserialize_struct("Row", &["id", "name", "price", "stock"], KeysVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Row {
+    id: u32,
+    name: String,
+    note: Option<String>,
+}
+
+#[test]
+fn deserialize_struct_in_declared_order() {
+    let row = Package::object([
+        ("stock", Package::from(3)),
+        ("price", Package::from(1.5)),
+        ("extra", Package::empty()),
+        ("name", Package::string("pen")),
+        ("another", Package::empty()),
+        ("id", Package::from(7)),
+    ]);
+    let expected = Keys(
+        ["id", "name", "price", "stock", "another", "extra"]
+            .map(str::to_owned)
+            .to_vec(),
+    );
+    assert_eq!(row.deserialize_borrowed::<Keys>().unwrap(), expected);
+    assert_eq!(row.clone().try_into::<Keys>().unwrap(), expected);
+
+    let row = Package::object([("name", Package::string("pen")), ("id", Package::from(7))]);
+    let expected = Row {
+        id: 7,
+        name: "pen".to_owned(),
+        note: None,
+    };
+    assert_eq!(row.clone().try_into::<Row>().unwrap(), expected);
+
+    let missing = Package::object([("id", 7)]).try_into::<Row>().unwrap_err();
+    assert!(missing.to_string().contains("not have the field 'name'"));
+    let missing = Package::object([("id", 7)])
+        .deserialize_borrowed::<Row>()
+        .unwrap_err();
+    assert!(missing.to_string().contains("not have the field 'name'"));
+}
@@ -0,0 +1,60 @@
+use rs_flow::connection::Connection;
+use rs_flow::spec::{ComponentSpec, FlowSpec};
+use rs_flow::Package;
+
+fn spec() -> FlowSpec {
+    FlowSpec {
+        components: vec![
+            ComponentSpec::new(1, "Source"),
+            ComponentSpec::new(2, "Filter").with_config(Package::object([("min", 10)])),
+            ComponentSpec::new(3, "Sink"),
+        ],
+        connections: vec![Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)],
+    }
+}
+
+#[test]
+fn diff_added_node_and_removed_edge() {
+    let before = spec();
+    let mut after = spec();
+    after.components.push(ComponentSpec::new(4, "Logger"));
+    after
+        .connections
+        .retain(|c| c != &Connection::new(2, 0, 3, 0));
+
+    let diff = before.diff(&after);
+
+    assert_eq!(diff.added_components, vec![ComponentSpec::new(4, "Logger")]);
+    assert!(diff.removed_components.is_empty());
+    assert!(diff.changed_configs.is_empty());
+    assert!(diff.added_connections.is_empty());
+    assert_eq!(diff.removed_connections, vec![Connection::new(2, 0, 3, 0)]);
+
+    assert_eq!(
+        diff.to_string(),
+        "+ component 4 (Logger)\n- connection 2:0 -> 3:0\n"
+    );
+    assert!(before.diff(&before).is_empty());
+}
+
+#[test]
+fn diff_changed_config_and_type() {
+    let before = spec();
+    let mut after = spec();
+    after.components[1].config = Package::object([("min", 20)]);
+    after.components[2].type_name = "Printer".to_owned();
+
+    let diff = before.diff(&after);
+
+    assert_eq!(diff.changed_configs.len(), 1);
+    assert_eq!(diff.changed_configs[0].id, 2);
+    assert_eq!(
+        diff.changed_configs[0].after,
+        Package::object([("min", 20)])
+    );
+    assert_eq!(diff.removed_components, vec![ComponentSpec::new(3, "Sink")]);
+    assert_eq!(
+        diff.added_components,
+        vec![ComponentSpec::new(3, "Printer")]
+    );
+}
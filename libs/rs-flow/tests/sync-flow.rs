@@ -0,0 +1,121 @@
+use rs_flow::prelude::*;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+struct Numbers;
+
+impl SyncComponentSchema for Numbers {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in [1, 2, 3] {
+            ctx.send(Out, number.into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Double;
+
+impl SyncComponentSchema for Double {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.send(Out, (number * 2.0).into());
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct Collect;
+
+impl SyncComponentSchema for Collect {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut_global(|global| global.push(number))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+struct AsyncCollect;
+
+#[async_trait]
+impl ComponentSchema for AsyncCollect {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while ctx.receive(In).is_some() {}
+        Ok(Next::Continue)
+    }
+}
+
+fn connections() -> [Connection; 2] {
+    [Connection::new(1, 0, 2, 0), Connection::new(2, 0, 3, 0)]
+}
+
+#[test]
+fn run_without_async_runtime() -> Result<()> {
+    let mut flow = SyncFlow::new()
+        .add_component(Component::sync(1, Numbers))?
+        .add_component(Component::sync(2, Double))?
+        .add_component(Component::sync(3, Collect))?;
+    for connection in connections() {
+        flow = flow.add_connection(connection)?;
+    }
+
+    let mut numbers = flow.run(Vec::new())?;
+    numbers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert_eq!(numbers, vec![2.0, 4.0, 6.0]);
+
+    Ok(())
+}
+
+#[test]
+fn reject_async_components() {
+    let error = SyncFlow::new()
+        .add_component(Component::new(1, AsyncCollect))
+        .err();
+
+    assert!(matches!(error, Some(Error::ComponentNotSync { id: 1 })));
+}
+
+#[tokio::test]
+async fn sync_components_in_async_flow() -> Result<()> {
+    let components = [
+        Component::sync(1, Numbers),
+        Component::sync(2, Double),
+        Component::sync(3, Collect),
+    ];
+
+    let mut numbers = Flow::from_parts(components, connections())?
+        .run(Vec::new())
+        .await?;
+    numbers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert_eq!(numbers, vec![2.0, 4.0, 6.0]);
+
+    Ok(())
+}
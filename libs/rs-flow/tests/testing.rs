@@ -0,0 +1,331 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rs_flow::connection::Point;
+use rs_flow::prelude::*;
+use rs_flow::testing::{Testing, TestingResult};
+use rs_flow::GlobalBag;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+struct Out;
+
+struct Source;
+
+#[async_trait]
+impl ComponentSchema for Source {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, (ctx.id() as f64).into());
+        Ok(Next::Continue)
+    }
+}
+
+/// Sum all packages received in a fan-in
+struct Sum;
+
+#[async_trait]
+impl ComponentSchema for Sum {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        let mut sum = 0.0;
+        while let Some(package) = ctx.receive(In) {
+            sum += package.get_number()?;
+        }
+        ctx.with_mut_global(|global| global.push(sum))?;
+        Ok(Next::Continue)
+    }
+}
+
+/// Record a different value in each run
+struct Counter(AtomicUsize);
+
+#[async_trait]
+impl ComponentSchema for Counter {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while ctx.receive(In).is_some() {}
+        let count = self.0.fetch_add(1, Ordering::SeqCst);
+        ctx.with_mut_global(|global| global.push(count as f64))?;
+        Ok(Next::Continue)
+    }
+}
+
+fn fan_in(sink: Component<Vec<f64>>) -> Result<Flow<Vec<f64>>> {
+    let sources = [1, 2, 3].map(|id| Component::new(id, Source));
+    let connections = sources
+        .iter()
+        .map(|source| Connection::by(source.from(0), sink.to(0)))
+        .collect::<Vec<_>>();
+
+    Ok(Flow::from_parts(
+        sources.into_iter().chain([sink]),
+        connections,
+    )?)
+}
+
+#[tokio::test]
+async fn deterministic_fan_in() -> Result<()> {
+    let flow = fan_in(Component::new(4, Sum))?;
+
+    let sums = flow.assert_deterministic(Vec::new(), 10).await?;
+    assert_eq!(sums, vec![6.0]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic(expected = "run 1 diverged from run 0")]
+async fn nondeterministic_fan_in() {
+    let flow = fan_in(Component::new(4, Counter(AtomicUsize::new(0)))).unwrap();
+
+    flow.assert_deterministic(Vec::new(), 2).await.unwrap();
+}
+
+/// Forward all packages received, in the order that they were received
+struct Forward;
+
+#[async_trait]
+impl ComponentSchema for Forward {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            ctx.send(Out, package);
+        }
+        Ok(Next::Continue)
+    }
+}
+
+/// Send the numbers in order
+struct Numbers(Vec<f64>);
+
+#[async_trait]
+impl ComponentSchema for Numbers {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        for number in &self.0 {
+            ctx.send(Out, Package::number(*number));
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn deterministic_outputs() -> Result<()> {
+    let numbers = Component::new(1, Numbers(vec![3.0, 1.0, 2.0]));
+    let forward = Component::new(2, Forward);
+    let connection = Connection::by(numbers.from(0), forward.to(0));
+    let flow = Flow::from_parts([numbers, forward], [connection])?;
+
+    flow.assert_deterministic(Vec::new(), 10).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic(expected = "diverged from run 0 in the outputs")]
+async fn nondeterministic_fan_in_order() {
+    let sources = (1..=8)
+        .map(|id| Component::new(id, Source))
+        .collect::<Vec<_>>();
+    let forward = Component::new(9, Forward);
+    let connections = sources
+        .iter()
+        .map(|source| Connection::by(source.from(0), forward.to(0)))
+        .collect::<Vec<_>>();
+    let flow = Flow::from_parts(sources.into_iter().chain([forward]), connections).unwrap();
+
+    // the global is always empty, only the order of the packages forwarded diverge
+    flow.assert_deterministic(Vec::new(), 20).await.unwrap();
+}
+
+/// Record the cicle that run
+struct CicleRecorder;
+
+#[async_trait]
+impl ComponentSchema for CicleRecorder {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out, (ctx.cicle() as f64).into());
+        ctx.with_mut_global(|global| global.push(ctx.cicle() as f64))?;
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn testing_at_cycle() {
+    let result = Testing::new(Vec::new())
+        .at_cycle(5)
+        .test(Component::new(1, CicleRecorder))
+        .await
+        .unwrap();
+
+    assert_eq!(result.next, Next::Continue);
+    assert_eq!(result.output(Out), &[Package::number(5.0)]);
+    assert_eq!(result.into_global(), vec![5.0]);
+}
+
+/// Emit the sum of each window of 2 packages, break when a package is zero
+struct Window;
+
+#[async_trait]
+impl ComponentSchema for Window {
+    type Inputs = In;
+    type Outputs = Out;
+
+    type Global = Vec<f64>;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        if let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            if number == 0.0 {
+                return Ok(Next::Break);
+            }
+            let window = ctx.state_mut::<Vec<f64>>();
+            window.push(number);
+            if window.len() == 2 {
+                let sum = window.drain(..).sum::<f64>();
+                ctx.send(Out, sum.into());
+            }
+        }
+        Ok(Next::Continue)
+    }
+}
+
+#[tokio::test]
+async fn testing_many_cycles() {
+    let result = Testing::new(Vec::new())
+        .input(In, 1.into())
+        .input(In, 2.into())
+        .input_at(2, In, 3.into())
+        .input_at(3, In, 4.into())
+        .input_at(4, In, 0.into())
+        .input_at(5, In, 5.into())
+        .test_cycles(Component::new(1, Window), 10)
+        .await
+        .unwrap();
+
+    // the second package of first input is received in the cicle 1
+    assert_eq!(result.runs(), 5);
+    assert_eq!(result.next, Next::Break);
+    assert_eq!(result.output_at(0, Out), &[]);
+    assert_eq!(result.output_at(1, Out), &[Package::from(3.0)]);
+    assert_eq!(result.output_at(2, Out), &[]);
+    assert_eq!(result.output_at(3, Out), &[Package::from(7.0)]);
+    assert_eq!(result.output_at(4, Out), &[]);
+    assert_eq!(
+        result.output(Out),
+        &[Package::from(3.0), Package::from(7.0)]
+    );
+
+    let result = Testing::new(Vec::new())
+        .at_cycle(3)
+        .test_cycles(Component::new(1, CicleRecorder), 3)
+        .await
+        .unwrap();
+    assert_eq!(result.runs(), 3);
+    assert_eq!(result.into_global(), vec![3.0, 4.0, 5.0]);
+}
+
+#[tokio::test]
+async fn testing_connected_components() {
+    let testing = || {
+        let first = Component::new(1, Window);
+        let second = Component::new(2, Window);
+        let testing = [1, 2, 3, 4]
+            .into_iter()
+            .fold(Testing::new(Vec::new()), |testing, number| {
+                testing.input_to(first.to(0), number.into())
+            })
+            .connect(Connection::by(first.from(0), second.to(0)));
+        (testing, [first, second])
+    };
+
+    let (harness, components) = testing();
+    let result = harness.test_flow(components, 10).await.unwrap();
+    assert_eq!(
+        result.sent(1, Out),
+        &[Package::from(3.0), Package::from(7.0)]
+    );
+    assert_eq!(result.sent(2, Out), &[Package::from(10.0)]);
+    let terminal = result.terminal();
+    assert_eq!(terminal.len(), 1);
+    assert_eq!(terminal[&Point::new(2, 0)], &[Package::from(10.0)]);
+
+    let (harness, components) = testing();
+    let error = harness.test_flow(components, 2).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::CycleLimitExceeded { .. })
+    ));
+}
+
+/// Count the packages received and record your numbers, in two globals of a [GlobalBag]
+struct Tally;
+
+#[async_trait]
+impl ComponentSchema for Tally {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = GlobalBag;
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while let Some(package) = ctx.receive(In) {
+            let number = package.get_number()?;
+            ctx.with_mut(|count: &mut u32| *count += 1)?;
+            ctx.with_mut(|numbers: &mut Vec<f64>| numbers.push(number))?;
+        }
+        Ok(Next::Continue)
+    }
+}
+
+async fn tally() -> TestingResult<GlobalBag> {
+    Testing::new(GlobalBag::new().insert(0u32).insert(Vec::<f64>::new()))
+        .input(In, 1.into())
+        .input(In, 2.5.into())
+        .test(Component::new(1, Tally))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn testing_globals_of_type() {
+    let result = tally().await;
+
+    result.assert_global_eq(&2u32);
+    result.assert_global_eq(&vec![1.0, 2.5]);
+    assert_eq!(result.global_ref::<u32>(), Some(&2));
+    assert_eq!(result.global_ref::<String>(), None);
+}
+
+#[tokio::test]
+#[should_panic(expected = "global of type u32")]
+async fn testing_global_not_equal() {
+    tally().await.assert_global_eq(&3u32);
+}
@@ -0,0 +1,19 @@
+use rs_flow::prelude::*;
+
+struct Count;
+
+#[async_trait]
+impl ComponentSchema for Count {
+    type Inputs = ();
+    type Outputs = ();
+
+    type Global = u32;
+
+    async fn run(&self, _ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        Ok(Next::Continue)
+    }
+}
+
+fn main() {
+    let _ = Flow::<String>::new().add_component(Component::new(1, Count));
+}
@@ -0,0 +1,15 @@
+use rs_flow::prelude::*;
+
+struct Parse;
+
+#[component(inputs(Text(String)), outputs(Number))]
+#[async_trait]
+impl ComponentSchema for Parse {
+    type Global = ();
+
+    async fn run(&self, _ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        Ok(Next::Continue)
+    }
+}
+
+fn main() {}
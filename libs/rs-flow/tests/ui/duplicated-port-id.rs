@@ -0,0 +1,11 @@
+use rs_flow::prelude::*;
+
+#[derive(Outputs)]
+enum Out {
+    #[port(id = 1)]
+    Ok,
+    #[port(id = 1)]
+    Error,
+}
+
+fn main() {}
@@ -0,0 +1,54 @@
+use rs_flow::prelude::*;
+
+#[derive(Inputs)]
+struct In;
+
+#[derive(Outputs)]
+enum Out {
+    Data,
+    #[allow(dead_code)]
+    Error,
+}
+
+struct Source;
+
+#[async_trait]
+impl ComponentSchema for Source {
+    type Inputs = ();
+    type Outputs = Out;
+
+    type Global = ();
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        ctx.send(Out::Data, Package::empty());
+        Ok(Next::Continue)
+    }
+}
+
+struct Sink;
+
+#[async_trait]
+impl ComponentSchema for Sink {
+    type Inputs = In;
+    type Outputs = ();
+
+    type Global = ();
+
+    async fn run(&self, ctx: &mut Ctx<Self::Global>) -> Result<Next> {
+        while ctx.receive(In).is_some() {}
+        Ok(Next::Continue)
+    }
+}
+
+fn main() {
+    let flow: std::result::Result<Flow<()>, Error> = flow! {
+        components: {
+            1 => Source,
+            2 => Sink,
+        },
+        connections: {
+            1.Out::Data -> 2.In,
+        },
+    };
+    assert!(flow.is_ok());
+}